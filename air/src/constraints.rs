@@ -1,6 +1,10 @@
 use super::{AuxEvaluationFrame, AuxTraceRandElements, MainEvaluationFrame};
 use giza_core::{
-    range, ExtensionOf, Felt, FieldElement, FlagDecomposition, OffsetDecomposition, Range,
+    range, AuxArgument, ExtensionOf, Felt, FieldElement, FlagDecomposition, OffsetDecomposition,
+    Range, A_M_PRIME_OFFSET, A_RC_PRIME_OFFSET, MEM_A_TRACE_OFFSET, MEM_TABLE_A_OFFSET,
+    MEM_TABLE_M_OFFSET, MEM_TABLE_V_OFFSET, MEM_V_TRACE_OFFSET, OFF_X_TRACE_OFFSET, P_M_OFFSET,
+    P_RC_OFFSET, RC_TABLE_M_OFFSET, RC_TABLE_OFFSET, STEP_HEIGHT, STEP_MEM_WIDTH, S_M_OFFSET,
+    S_RC_OFFSET, V_M_PRIME_OFFSET, W_M_OFFSET, W_RC_OFFSET,
 };
 
 pub trait EvaluationResult<E: FieldElement> {
@@ -8,7 +12,9 @@ pub trait EvaluationResult<E: FieldElement> {
     fn evaluate_operand_constraints(&mut self, frame: &MainEvaluationFrame<E>);
     fn evaluate_register_constraints(&mut self, frame: &MainEvaluationFrame<E>);
     fn evaluate_opcode_constraints(&mut self, frame: &MainEvaluationFrame<E>);
-    fn enforce_selector(&mut self, frame: &MainEvaluationFrame<E>);
+    fn evaluate_filler_constraints(&mut self, frame: &MainEvaluationFrame<E>);
+    fn enforce_step(&mut self, step_mask: E);
+    fn evaluate_table_constraints(&mut self, frame: &MainEvaluationFrame<E>);
 }
 
 pub trait AuxEvaluationResult<E: FieldElement, F: FieldElement + ExtensionOf<E>> {
@@ -24,6 +30,18 @@ pub trait AuxEvaluationResult<E: FieldElement, F: FieldElement + ExtensionOf<E>>
         aux_frame: &AuxEvaluationFrame<F>,
         aux_rand_elements: &AuxTraceRandElements<F>,
     );
+    fn evaluate_logup_memory_constraints(
+        &mut self,
+        main_frame: &MainEvaluationFrame<E>,
+        aux_frame: &AuxEvaluationFrame<F>,
+        aux_rand_elements: &AuxTraceRandElements<F>,
+    );
+    fn evaluate_logup_range_check_constraints(
+        &mut self,
+        main_frame: &MainEvaluationFrame<E>,
+        aux_frame: &AuxEvaluationFrame<F>,
+        aux_rand_elements: &AuxTraceRandElements<F>,
+    );
 }
 
 /// Main constraint identifiers
@@ -42,16 +60,120 @@ const MUL_2: usize = 27;
 const CALL_1: usize = 28;
 const CALL_2: usize = 29;
 const ASSERT_EQ: usize = 30;
+const FILLER_VALUE: Range<usize> = range(31, STEP_HEIGHT - STEP_MEM_WIDTH);
+
+/// LogUp table constraint identifiers, following the step constraints
+const MEM_TABLE_A: usize = FILLER_VALUE.end;
+const MEM_TABLE_V: usize = MEM_TABLE_A + 1;
+const RC_TABLE: usize = MEM_TABLE_V + 1;
 
 /// Aux constraint identifiers
-const A_M_PRIME: Range<usize> = range(0, 4);
-const V_M_PRIME: Range<usize> = range(4, 4);
-const P_M: Range<usize> = range(8, 4);
-const A_RC_PRIME: Range<usize> = range(12, 3);
-const P_RC: Range<usize> = range(15, 3);
+const A_M_PRIME: usize = 0;
+const V_M_PRIME: usize = 1;
+const P_M: usize = 2;
+const A_RC_PRIME: usize = 3;
+const P_RC: usize = 4;
+
+/// LogUp aux constraint identifiers
+const S_M: usize = 0;
+const W_M: usize = 1;
+const S_RC: usize = 2;
+const W_RC: usize = 3;
 
 const TWO: Felt = Felt::TWO;
 
+/// Names of the operand, register and opcode constraints, from INST to ASSERT_EQ
+const CPU_CONSTRAINT_NAMES: [&str; ASSERT_EQ - INST + 1] = [
+    "INST",
+    "DST_ADDR",
+    "OP0_ADDR",
+    "OP1_ADDR",
+    "NEXT_AP",
+    "NEXT_FP",
+    "NEXT_PC_1",
+    "NEXT_PC_2",
+    "T0",
+    "T1",
+    "MUL_1",
+    "MUL_2",
+    "CALL_1",
+    "CALL_2",
+    "ASSERT_EQ",
+];
+
+/// Kinds of main constraints, by the rows they read
+pub(crate) enum MainConstraint {
+    /// Constraint on the cells of a step and the registers of the next step
+    Cpu,
+    /// Constraint on the filler memory access at the given row of a step
+    Filler(usize),
+    /// Constraint on the given columns at the current and next row
+    Table(&'static [usize]),
+}
+
+/// Returns the name of a main constraint
+pub(crate) fn main_constraint_name(idx: usize) -> String {
+    match idx {
+        0..=15 => format!("F{}", idx),
+        INST..=ASSERT_EQ => CPU_CONSTRAINT_NAMES[idx - INST].to_string(),
+        _ if FILLER_VALUE.contains(&idx) => format!("FILLER_VALUE[{}]", idx - FILLER_VALUE.start),
+        MEM_TABLE_A => "MEM_TABLE_A".to_string(),
+        MEM_TABLE_V => "MEM_TABLE_V".to_string(),
+        RC_TABLE => "RC_TABLE".to_string(),
+        _ => panic!("Unknown main constraint {}", idx),
+    }
+}
+
+/// Returns the kind of a main constraint
+pub(crate) fn main_constraint_kind(idx: usize) -> MainConstraint {
+    match idx {
+        0..=ASSERT_EQ => MainConstraint::Cpu,
+        _ if FILLER_VALUE.contains(&idx) => {
+            MainConstraint::Filler(STEP_MEM_WIDTH + idx - FILLER_VALUE.start)
+        }
+        MEM_TABLE_A | MEM_TABLE_V => {
+            MainConstraint::Table(&[MEM_TABLE_A_OFFSET, MEM_TABLE_V_OFFSET])
+        }
+        RC_TABLE => MainConstraint::Table(&[RC_TABLE_OFFSET]),
+        _ => panic!("Unknown main constraint {}", idx),
+    }
+}
+
+/// Returns the name of an aux constraint, together with the main and aux columns it reads
+/// at the current and next row
+pub(crate) fn aux_constraint(
+    argument: AuxArgument,
+    idx: usize,
+) -> (&'static str, &'static [usize], &'static [usize]) {
+    const MEM_COLUMNS: &[usize] = &[MEM_A_TRACE_OFFSET, MEM_V_TRACE_OFFSET];
+    const RC_COLUMNS: &[usize] = &[OFF_X_TRACE_OFFSET];
+    const MEM_TABLE_COLUMNS: &[usize] = &[
+        MEM_A_TRACE_OFFSET,
+        MEM_V_TRACE_OFFSET,
+        MEM_TABLE_A_OFFSET,
+        MEM_TABLE_V_OFFSET,
+        MEM_TABLE_M_OFFSET,
+    ];
+    const RC_TABLE_COLUMNS: &[usize] = &[OFF_X_TRACE_OFFSET, RC_TABLE_OFFSET, RC_TABLE_M_OFFSET];
+    const AUX_MEM_COLUMNS: &[usize] = &[A_M_PRIME_OFFSET, V_M_PRIME_OFFSET, P_M_OFFSET];
+    const AUX_RC_COLUMNS: &[usize] = &[A_RC_PRIME_OFFSET, P_RC_OFFSET];
+    const AUX_LOGUP_MEM_COLUMNS: &[usize] = &[S_M_OFFSET, W_M_OFFSET];
+    const AUX_LOGUP_RC_COLUMNS: &[usize] = &[S_RC_OFFSET, W_RC_OFFSET];
+
+    match (argument, idx) {
+        (AuxArgument::Permutation, A_M_PRIME) => ("A_M_PRIME", MEM_COLUMNS, AUX_MEM_COLUMNS),
+        (AuxArgument::Permutation, V_M_PRIME) => ("V_M_PRIME", MEM_COLUMNS, AUX_MEM_COLUMNS),
+        (AuxArgument::Permutation, P_M) => ("P_M", MEM_COLUMNS, AUX_MEM_COLUMNS),
+        (AuxArgument::Permutation, A_RC_PRIME) => ("A_RC_PRIME", RC_COLUMNS, AUX_RC_COLUMNS),
+        (AuxArgument::Permutation, P_RC) => ("P_RC", RC_COLUMNS, AUX_RC_COLUMNS),
+        (AuxArgument::LogUp, S_M) => ("S_M", MEM_TABLE_COLUMNS, AUX_LOGUP_MEM_COLUMNS),
+        (AuxArgument::LogUp, W_M) => ("W_M", MEM_TABLE_COLUMNS, AUX_LOGUP_MEM_COLUMNS),
+        (AuxArgument::LogUp, S_RC) => ("S_RC", RC_TABLE_COLUMNS, AUX_LOGUP_RC_COLUMNS),
+        (AuxArgument::LogUp, W_RC) => ("W_RC", RC_TABLE_COLUMNS, AUX_LOGUP_RC_COLUMNS),
+        _ => panic!("Unknown aux constraint {}", idx),
+    }
+}
+
 impl<E: FieldElement + From<Felt>> EvaluationResult<E> for [E] {
     fn evaluate_instr_constraints(&mut self, frame: &MainEvaluationFrame<E>) {
         let curr = frame.current();
@@ -122,11 +244,11 @@ impl<E: FieldElement + From<Felt>> EvaluationResult<E> for [E] {
         self[NEXT_PC_1] =
             (curr.t1() - curr.f_pc_jnz()) * (next.pc() - (curr.pc() + curr.inst_size()));
         self[NEXT_PC_2] = curr.t0() * (next.pc() - (curr.pc() + curr.op1()))
-           + (one - curr.f_pc_jnz()) * next.pc()
-           - ((one - curr.f_pc_abs() - curr.f_pc_rel() - curr.f_pc_jnz())
-                   * (curr.pc() + curr.inst_size())
-               + curr.f_pc_abs() * curr.res()
-               + curr.f_pc_rel() * (curr.pc() + curr.res()));
+            + (one - curr.f_pc_jnz()) * next.pc()
+            - ((one - curr.f_pc_abs() - curr.f_pc_rel() - curr.f_pc_jnz())
+                * (curr.pc() + curr.inst_size())
+                + curr.f_pc_abs() * curr.res()
+                + curr.f_pc_rel() * (curr.pc() + curr.res()));
         self[T0] = curr.f_pc_jnz() * curr.dst() - curr.t0();
         self[T1] = curr.t0() * curr.res() - curr.t1();
     }
@@ -144,12 +266,35 @@ impl<E: FieldElement + From<Felt>> EvaluationResult<E> for [E] {
         self[ASSERT_EQ] = curr.f_opc_aeq() * (curr.dst() - curr.res());
     }
 
-    fn enforce_selector(&mut self, frame: &MainEvaluationFrame<E>) {
+    fn evaluate_filler_constraints(&mut self, frame: &MainEvaluationFrame<E>) {
         let curr = frame.current();
-        for n in INST..=ASSERT_EQ {
-            self[n] *= curr.selector();
+        // Filler memory accesses fill memory holes and make room for public memory. They are
+        // constrained to zero values, so that they can only claim unused memory cells to be
+        // zero, and can't be used to assign values to cells read by the execution.
+        for (i, n) in FILLER_VALUE.enumerate() {
+            self[n] = curr.filler_value(i);
         }
     }
+
+    fn enforce_step(&mut self, step_mask: E) {
+        // A step spans STEP_HEIGHT rows, and its constraints only hold at its first row
+        for result in self[..FILLER_VALUE.end].iter_mut() {
+            *result *= step_mask;
+        }
+    }
+
+    fn evaluate_table_constraints(&mut self, frame: &MainEvaluationFrame<E>) {
+        let curr = frame.segment();
+        let one: E = Felt::ONE.into();
+        // Continuity constraints
+        self[MEM_TABLE_A] = (curr.mem_table_a(1) - curr.mem_table_a(0))
+            * (curr.mem_table_a(1) - curr.mem_table_a(0) - one);
+        self[RC_TABLE] =
+            (curr.rc_table(1) - curr.rc_table(0)) * (curr.rc_table(1) - curr.rc_table(0) - one);
+        // Single-valued constraint
+        self[MEM_TABLE_V] = (curr.mem_table_v(1) - curr.mem_table_v(0))
+            * (curr.mem_table_a(1) - curr.mem_table_a(0) - one);
+    }
 }
 
 impl<E, F> AuxEvaluationResult<E, F> for [F]
@@ -171,22 +316,16 @@ where
         let alpha = random_elements[1];
 
         // Continuity constraint
-        for (i, n) in A_M_PRIME.enumerate() {
-            self[n] = (aux.a_m_prime(i + 1) - aux.a_m_prime(i))
-                * (aux.a_m_prime(i + 1) - aux.a_m_prime(i) - F::ONE);
-        }
+        self[A_M_PRIME] =
+            (aux.a_m_prime(1) - aux.a_m_prime(0)) * (aux.a_m_prime(1) - aux.a_m_prime(0) - F::ONE);
         // Single-valued constraint
-        for (i, n) in V_M_PRIME.enumerate() {
-            self[n] = (aux.v_m_prime(i + 1) - aux.v_m_prime(i))
-                * (aux.a_m_prime(i + 1) - aux.a_m_prime(i) - F::ONE);
-        }
+        self[V_M_PRIME] =
+            (aux.v_m_prime(1) - aux.v_m_prime(0)) * (aux.a_m_prime(1) - aux.a_m_prime(0) - F::ONE);
         // Cumulative product step
-        for (i, n) in P_M.enumerate() {
-            let a_m: F = curr.a_m(i + 1).into();
-            let v_m: F = curr.v_m(i + 1).into();
-            self[n] = (z - (aux.a_m_prime(i + 1) + alpha * aux.v_m_prime(i + 1))) * aux.p_m(i + 1)
-                - (z - (a_m + alpha * v_m)) * aux.p_m(i);
-        }
+        let a_m: F = curr.a_m(1).into();
+        let v_m: F = curr.v_m(1).into();
+        self[P_M] = (z - (aux.a_m_prime(1) + alpha * aux.v_m_prime(1))) * aux.p_m(1)
+            - (z - (a_m + alpha * v_m)) * aux.p_m(0);
     }
 
     fn evaluate_range_check_constraints(
@@ -202,14 +341,85 @@ where
         let z = random_elements[0];
 
         // Continuity constraint
-        for (i, n) in A_RC_PRIME.enumerate() {
-            self[n] = (aux.a_rc_prime(i + 1) - aux.a_rc_prime(i))
-                * (aux.a_rc_prime(i + 1) - aux.a_rc_prime(i) - F::ONE);
-        }
+        self[A_RC_PRIME] = (aux.a_rc_prime(1) - aux.a_rc_prime(0))
+            * (aux.a_rc_prime(1) - aux.a_rc_prime(0) - F::ONE);
         // Cumulative product step
-        for (i, n) in P_RC.enumerate() {
-            self[n] = (z - aux.a_rc_prime(i + 1)) * aux.p_rc(i + 1)
-                - (z - curr.a_rc(i + 1).into()) * aux.p_rc(i)
-        }
+        self[P_RC] =
+            (z - aux.a_rc_prime(1)) * aux.p_rc(1) - (z - curr.a_rc(1).into()) * aux.p_rc(0);
+    }
+
+    fn evaluate_logup_memory_constraints(
+        &mut self,
+        main_frame: &MainEvaluationFrame<E>,
+        aux_frame: &AuxEvaluationFrame<F>,
+        aux_rand_elements: &AuxTraceRandElements<F>,
+    ) {
+        let curr = main_frame.segment();
+        let aux = aux_frame.segment();
+
+        let random_elements = aux_rand_elements.get_segment_elements(0);
+        let z = random_elements[0];
+        let alpha = random_elements[1];
+
+        let a_m: F = curr.a_m(0).into();
+        let v_m: F = curr.v_m(0).into();
+        let table_a: F = curr.mem_table_a(0).into();
+        let table_v: F = curr.mem_table_v(0).into();
+        let table_m: F = curr.mem_table_m(0).into();
+
+        // Inverse of the access term
+        self[W_M] = aux.w_m(0) * (z - (a_m + alpha * v_m)) - F::ONE;
+        // Running sum step
+        self[S_M] =
+            (aux.s_m(1) - aux.s_m(0) - aux.w_m(0)) * (z - (table_a + alpha * table_v)) + table_m;
+    }
+
+    fn evaluate_logup_range_check_constraints(
+        &mut self,
+        main_frame: &MainEvaluationFrame<E>,
+        aux_frame: &AuxEvaluationFrame<F>,
+        aux_rand_elements: &AuxTraceRandElements<F>,
+    ) {
+        let curr = main_frame.segment();
+        let aux = aux_frame.segment();
+
+        let random_elements = aux_rand_elements.get_segment_elements(1);
+        let z = random_elements[0];
+
+        let a_rc: F = curr.a_rc(0).into();
+        let table: F = curr.rc_table(0).into();
+        let table_m: F = curr.rc_table_m(0).into();
+
+        // Inverse of the offset term
+        self[W_RC] = aux.w_rc(0) * (z - a_rc) - F::ONE;
+        // Running sum step
+        self[S_RC] = (aux.s_rc(1) - aux.s_rc(0) - aux.w_rc(0)) * (z - table) + table_m;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use giza_core::{MEM_A_TRACE_OFFSET, MEM_V_TRACE_OFFSET, TRACE_WIDTH};
+    use winter_air::{EvaluationFrame, Table};
+
+    fn evaluate(table: Table<Felt>) -> Vec<Felt> {
+        let frame = MainEvaluationFrame::from_table(table);
+        let mut result = vec![Felt::ZERO; FILLER_VALUE.end];
+        result.evaluate_filler_constraints(&frame);
+        result
+    }
+
+    #[test]
+    fn test_memory_holes() {
+        // The last filler access of the step fills the hole at address 42
+        let row = STEP_HEIGHT - 1;
+        let mut table = Table::new(MainEvaluationFrame::<Felt>::num_rows(), TRACE_WIDTH);
+        table.rows_mut().nth(row).unwrap()[MEM_A_TRACE_OFFSET] = Felt::from(42u64);
+        assert!(evaluate(table.clone()).iter().all(|x| *x == Felt::ZERO));
+
+        // Assigning a value to the hole is rejected
+        table.rows_mut().nth(row).unwrap()[MEM_V_TRACE_OFFSET] = Felt::from(7u64);
+        assert_ne!(evaluate(table)[FILLER_VALUE.end - 1], Felt::ZERO);
     }
 }
@@ -0,0 +1,360 @@
+//! Constraint debugger. When a trace doesn't satisfy the AIR, the prover only finds out that
+//! the constraint evaluations aren't divisible by their divisors, and the verifier rejects
+//! the proof. The debugger evaluates the transition constraints at every row of the trace
+//! and the boundary assertions instead, and names the ones that don't hold, with the step at
+//! fault and the values they read.
+
+use super::constraints::{
+    aux_constraint, main_constraint_kind, main_constraint_name, MainConstraint,
+};
+use super::{AuxEvaluationFrame, MainEvaluationFrame, ProcessorAir};
+use core::fmt;
+use giza_core::{
+    flags::*, AuxArgument, Felt, FieldElement, FieldHelpers, FlagGroupDecomposition, Word,
+    AUX_LOGUP_MEM_COLUMN_NAMES, AUX_LOGUP_RC_COLUMN_NAMES, AUX_MEM_COLUMN_NAMES,
+    AUX_RC_COLUMN_NAMES, LOGUP_TABLE_COLUMN_NAMES, MEM_A_TRACE_OFFSET, MEM_V_TRACE_OFFSET, PC,
+    STEP_CELL_NAMES, STEP_HEIGHT, TRACE_COLUMN_NAMES,
+};
+use winter_air::{Air, AuxTraceRandElements, EvaluationFrame, Table};
+
+/// A transition constraint or a boundary assertion that doesn't hold at a row of the trace
+#[derive(Clone, Debug)]
+pub struct ConstraintFailure {
+    /// Name of the constraint, e.g. `NEXT_PC_1` or `FILLER_VALUE[3]`, or of the asserted
+    /// cell, e.g. `ASSERT_PC`
+    pub constraint: String,
+    /// Row at which the constraint is evaluated
+    pub row: usize,
+    /// Step spanning the row, with its pc and decoded instruction
+    pub step: usize,
+    pub pc: Felt,
+    pub instruction: String,
+    /// Evaluation of the constraint
+    pub evaluation: Felt,
+    /// Trace values read by the constraint, by name
+    pub values: Vec<(String, Felt)>,
+}
+
+impl fmt::Display for ConstraintFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} fails at row {} (step {}, pc {}: {})",
+            self.constraint,
+            self.row,
+            self.step,
+            format_felt(self.pc),
+            self.instruction
+        )?;
+        write!(f, "\n    evaluation = {}", format_felt(self.evaluation))?;
+        for (name, value) in self.values.iter() {
+            write!(f, "\n    {} = {}", name, format_felt(*value))?;
+        }
+        Ok(())
+    }
+}
+
+impl ProcessorAir {
+    /// Evaluates the transition constraints at every row of a trace, given by the columns of
+    /// its main segment and of its aux segments, and by the random elements each aux segment
+    /// was built with. Returns the constraints that don't hold, outside of the rows they are
+    /// exempted from.
+    pub fn check_transitions(
+        &self,
+        main: &[&[Felt]],
+        aux: &[&[Felt]],
+        aux_rand_elements: Vec<Vec<Felt>>,
+    ) -> Vec<ConstraintFailure> {
+        let trace_len = self.trace_length();
+        let mut rand_elements = AuxTraceRandElements::new();
+        for elements in aux_rand_elements {
+            rand_elements.add_segment_elements(elements);
+        }
+        let periodic_columns = self.get_periodic_column_values();
+        let main_names = main_column_names();
+        let aux_names = aux_column_names(self.argument);
+
+        let mut failures = vec![];
+        for row in 0..trace_len {
+            let main_frame = MainEvaluationFrame::from_table(read_frame(
+                main,
+                row,
+                MainEvaluationFrame::<Felt>::offsets(),
+            ));
+            let aux_frame = AuxEvaluationFrame::from_table(read_frame(
+                aux,
+                row,
+                AuxEvaluationFrame::<Felt>::offsets(),
+            ));
+            let periodic_values = periodic_columns
+                .iter()
+                .map(|column| column[row % column.len()])
+                .collect::<Vec<_>>();
+
+            let mut result = vec![Felt::ZERO; self.main_exemptions.len()];
+            self.evaluate_transition(&main_frame, &periodic_values, &mut result);
+            for (idx, evaluation) in result.into_iter().enumerate() {
+                if evaluation == Felt::ZERO || row >= trace_len - self.main_exemptions[idx] {
+                    continue;
+                }
+                let values = match main_constraint_kind(idx) {
+                    MainConstraint::Cpu => step_values(&main_frame),
+                    MainConstraint::Filler(offset) => column_values(
+                        main,
+                        &main_names,
+                        &[MEM_A_TRACE_OFFSET, MEM_V_TRACE_OFFSET],
+                        row + offset,
+                        1,
+                    ),
+                    MainConstraint::Table(columns) => {
+                        column_values(main, &main_names, columns, row, 2)
+                    }
+                };
+                let name = main_constraint_name(idx);
+                failures.push(failure(main, name, row, evaluation, values));
+            }
+
+            let mut result = vec![Felt::ZERO; self.aux_exemptions.len()];
+            self.evaluate_aux_transition(
+                &main_frame,
+                &aux_frame,
+                &periodic_values,
+                &rand_elements,
+                &mut result,
+            );
+            for (idx, evaluation) in result.into_iter().enumerate() {
+                if evaluation == Felt::ZERO || row >= trace_len - self.aux_exemptions[idx] {
+                    continue;
+                }
+                let (name, main_columns, aux_columns) = aux_constraint(self.argument, idx);
+                let mut values = column_values(main, &main_names, main_columns, row, 2);
+                values.extend(column_values(aux, &aux_names, aux_columns, row, 2));
+                failures.push(failure(main, name.to_string(), row, evaluation, values));
+            }
+        }
+        failures
+    }
+
+    /// Evaluates the boundary assertions against a trace, given as for
+    /// [check_transitions](Self::check_transitions). Returns the assertions that don't hold,
+    /// named after the asserted cell, with the value it holds and the expected one.
+    pub fn check_assertions(
+        &self,
+        main: &[&[Felt]],
+        aux: &[&[Felt]],
+        aux_rand_elements: Vec<Vec<Felt>>,
+    ) -> Vec<ConstraintFailure> {
+        let mut rand_elements = AuxTraceRandElements::new();
+        for elements in aux_rand_elements {
+            rand_elements.add_segment_elements(elements);
+        }
+        let main_names = main_column_names();
+        let aux_names = aux_column_names(self.argument);
+        let assertions = self
+            .get_assertions()
+            .into_iter()
+            .map(|assertion| (main, &main_names, true, assertion))
+            .chain(
+                self.get_aux_assertions(&rand_elements)
+                    .into_iter()
+                    .map(|assertion| (aux, &aux_names, false, assertion)),
+            );
+
+        let mut failures = vec![];
+        for (columns, names, is_main, assertion) in assertions {
+            let column = assertion.column();
+            assertion.apply(self.trace_length(), |row, expected| {
+                let value = columns[column][row];
+                if value == expected {
+                    return;
+                }
+                // Cells of a step are named after what they hold, other cells after their
+                // column
+                let cell = match STEP_CELL_NAMES.get(column) {
+                    Some(cells) if is_main => cells.get(row % STEP_HEIGHT).copied(),
+                    _ => None,
+                };
+                let name = format!("ASSERT_{}", cell.unwrap_or(names[column]).to_uppercase());
+                let values = vec![
+                    (format!("{}[{}]", names[column], row), value),
+                    ("expected".to_string(), expected),
+                ];
+                failures.push(failure(main, name, row, value - expected, values));
+            });
+        }
+        failures
+    }
+}
+
+/// Returns the names of the main trace columns, including the LogUp tables
+fn main_column_names() -> Vec<&'static str> {
+    TRACE_COLUMN_NAMES
+        .iter()
+        .chain(LOGUP_TABLE_COLUMN_NAMES.iter())
+        .copied()
+        .collect()
+}
+
+/// Returns the names of the aux trace columns of an argument
+fn aux_column_names(argument: AuxArgument) -> Vec<&'static str> {
+    match argument {
+        AuxArgument::Permutation => [&AUX_MEM_COLUMN_NAMES[..], &AUX_RC_COLUMN_NAMES[..]].concat(),
+        AuxArgument::LogUp => [
+            &AUX_LOGUP_MEM_COLUMN_NAMES[..],
+            &AUX_LOGUP_RC_COLUMN_NAMES[..],
+        ]
+        .concat(),
+    }
+}
+
+/// Reads the rows of a frame at the given offsets from a row, wrapping around the trace
+fn read_frame(columns: &[&[Felt]], row: usize, offsets: &[usize]) -> Table<Felt> {
+    let mut table = Table::new(offsets.len(), columns.len());
+    for (values, offset) in table.rows_mut().zip(offsets) {
+        for (i, column) in columns.iter().enumerate() {
+            values[i] = column[(row + offset) % column.len()];
+        }
+    }
+    table
+}
+
+/// Describes a failing constraint, with the step spanning its row
+fn failure(
+    main: &[&[Felt]],
+    constraint: String,
+    row: usize,
+    evaluation: Felt,
+    values: Vec<(String, Felt)>,
+) -> ConstraintFailure {
+    let step = row / STEP_HEIGHT;
+    let step_row = step * STEP_HEIGHT;
+    ConstraintFailure {
+        constraint,
+        row,
+        step,
+        pc: main[PC.0][step_row + PC.1],
+        instruction: decode_instruction(main[MEM_V_TRACE_OFFSET][step_row]),
+        evaluation,
+        values,
+    }
+}
+
+/// Returns the cells of a step read by its constraints, and the registers of the next step
+fn step_values(frame: &MainEvaluationFrame<Felt>) -> Vec<(String, Felt)> {
+    let curr = frame.current();
+    let next = frame.next();
+    [
+        ("pc", curr.pc()),
+        ("ap", curr.ap()),
+        ("fp", curr.fp()),
+        ("inst", curr.inst()),
+        ("dst_addr", curr.dst_addr()),
+        ("dst", curr.dst()),
+        ("op0_addr", curr.op0_addr()),
+        ("op0", curr.op0()),
+        ("op1_addr", curr.op1_addr()),
+        ("op1", curr.op1()),
+        ("res", curr.res()),
+        ("t0", curr.t0()),
+        ("t1", curr.t1()),
+        ("mul", curr.mul()),
+        ("next_pc", next.pc()),
+        ("next_ap", next.ap()),
+        ("next_fp", next.fp()),
+    ]
+    .iter()
+    .map(|(name, value)| (name.to_string(), *value))
+    .collect()
+}
+
+/// Returns the values of the given columns over a number of rows, named after the column
+/// and the row
+fn column_values(
+    columns: &[&[Felt]],
+    names: &[&str],
+    indices: &[usize],
+    row: usize,
+    num_rows: usize,
+) -> Vec<(String, Felt)> {
+    let mut values = vec![];
+    for &i in indices {
+        for row in row..row + num_rows {
+            let row = row % columns[i].len();
+            values.push((format!("{}[{}]", names[i], row), columns[i][row]));
+        }
+    }
+    values
+}
+
+/// Decodes an instruction into a description close to Cairo assembly
+fn decode_instruction(inst: Felt) -> String {
+    let word = Word::new(inst);
+    let invalid = || format!("invalid instruction {}", format_felt(inst));
+    let offset = |pos| word.word().chunk_u16(pos).to_u64() as i64 - (1 << 15);
+    let register = |fp| if fp == 1 { "fp" } else { "ap" };
+
+    let dst = format!("[{}{:+}]", register(word.dst_reg()), offset(POS_DST));
+    let op0 = format!("[{}{:+}]", register(word.op0_reg()), offset(POS_OP0));
+    let op1 = match word.op1_src() {
+        OP1_DBL => format!("[{}{:+}]", op0, offset(POS_OP1)),
+        OP1_VAL => format!("[pc{:+}]", offset(POS_OP1)),
+        OP1_FP => format!("[fp{:+}]", offset(POS_OP1)),
+        OP1_AP => format!("[ap{:+}]", offset(POS_OP1)),
+        _ => return invalid(),
+    };
+    let res = match word.res_log() {
+        RES_ONE => op1.clone(),
+        RES_ADD => format!("{} + {}", op0, op1),
+        RES_MUL => format!("{} * {}", op0, op1),
+        _ => return invalid(),
+    };
+
+    let mut parts = vec![];
+    match (word.opcode(), word.pc_up()) {
+        (OPC_JMP_INC, PC_SIZ) => {}
+        (OPC_JMP_INC, PC_ABS) => parts.push(format!("jmp abs {}", res)),
+        (OPC_JMP_INC, PC_REL) => parts.push(format!("jmp rel {}", res)),
+        (OPC_JMP_INC, PC_JNZ) => parts.push(format!("jmp rel {} if {} != 0", op1, dst)),
+        (OPC_CALL, PC_ABS) => parts.push(format!("call abs {}", res)),
+        (OPC_CALL, PC_REL) => parts.push(format!("call rel {}", res)),
+        (OPC_RET, PC_ABS) => parts.push("ret".to_string()),
+        (OPC_AEQ, PC_SIZ) => parts.push(format!("{} = {}", dst, res)),
+        _ => return invalid(),
+    }
+    match word.ap_up() {
+        AP_Z2 => {}
+        AP_ADD => parts.push(format!("ap += {}", res)),
+        AP_ONE => parts.push("ap++".to_string()),
+        _ => return invalid(),
+    }
+    match parts.is_empty() {
+        true => "nop".to_string(),
+        false => parts.join(", "),
+    }
+}
+
+/// Formats small values and their negations in decimal, and other values in hexadecimal
+fn format_felt(value: Felt) -> String {
+    if Felt::from(value.to_u64()) == value {
+        value.to_u64().to_string()
+    } else if Felt::from((-value).to_u64()) == -value {
+        format!("-{}", (-value).to_u64())
+    } else {
+        format!("0x{}", value.to_hex_le().trim_start_matches('0'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_instruction() {
+        let decode = |inst: u64| decode_instruction(Felt::from(inst));
+        assert_eq!(decode(0x480680017fff8000), "[ap+0] = [pc+1], ap++");
+        assert_eq!(decode(0x10780017fff7fff), "jmp rel [pc+1]");
+        assert_eq!(decode(0x1104800180018000), "call rel [pc+1]");
+        assert_eq!(decode(0x208b7fff7fff7ffe), "ret");
+        assert_eq!(decode(0x48307fff7fff8000), "[ap+0] = [ap-1] + [ap-1], ap++");
+    }
+}
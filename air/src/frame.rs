@@ -57,7 +57,8 @@ impl<E: FieldElement> EvaluationFrame<E> for MainEvaluationFrame<E> {
     }
 
     fn offsets() -> &'static [usize] {
-        &[0, 1]
+        // The rows of the current step, and the first two rows of the next step
+        &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17]
     }
 }
 
@@ -66,7 +67,7 @@ impl<'a, E: FieldElement> MainEvaluationFrame<E> {
         MainFrameSegment::new(&self.table, 0)
     }
     pub fn next(&'a self) -> MainFrameSegment<'a, E> {
-        MainFrameSegment::new(&self.table, 1)
+        MainFrameSegment::new(&self.table, STEP_HEIGHT)
     }
     pub fn segment(&'a self) -> MainFrameSegment<'a, E> {
         MainFrameSegment::new(&self.table, 0)
@@ -80,13 +81,10 @@ pub struct MainFrameSegment<'a, E: FieldElement> {
 
 enum DataSegment {
     Flags,
-    ResValue,
-    TempMemoryPointer,
+    Offsets,
     MemoryAddress,
     MemoryValues,
-    Offsets,
-    TempValues,
-    Selector,
+    Cpu,
 }
 
 impl<'a, E: FieldElement> MainFrameSegment<'a, E> {
@@ -94,28 +92,24 @@ impl<'a, E: FieldElement> MainFrameSegment<'a, E> {
         Self { table, row_start }
     }
 
-    fn get(&self, pos: usize, data_type: DataSegment) -> E {
+    /// Returns the value at the given row of the step, in the column of the data type
+    fn get(&self, row: usize, data_type: DataSegment) -> E {
         // Should this function be inlined?
-        let offset = match data_type {
+        let column = match data_type {
             DataSegment::Flags => FLAG_TRACE_OFFSET,
-            DataSegment::ResValue => RES_TRACE_OFFSET,
-            DataSegment::TempMemoryPointer => MEM_P_TRACE_OFFSET,
+            DataSegment::Offsets => OFF_X_TRACE_OFFSET,
             DataSegment::MemoryAddress => MEM_A_TRACE_OFFSET,
             DataSegment::MemoryValues => MEM_V_TRACE_OFFSET,
-            DataSegment::Offsets => OFF_X_TRACE_OFFSET,
-            DataSegment::TempValues => DERIVED_TRACE_OFFSET,
-            DataSegment::Selector => SELECTOR_TRACE_OFFSET,
+            DataSegment::Cpu => CPU_TRACE_OFFSET,
         };
-        self.table.get_row(self.row_start)[offset + pos]
+        self.table.get_row(self.row_start + row)[column]
     }
 
-    fn get_virtual(&self, idx: usize, offset: usize, width: usize) -> E {
-        if (0..width).contains(&idx) {
-            self.table.get_row(0)[offset + idx]
-        } else if (width..width * 2).contains(&idx) {
-            self.table.get_row(1)[offset + idx - width]
-        } else {
-            panic!()
+    /// Returns the value of a virtual column at the current (`idx` 0) or next (`idx` 1) row
+    fn get_virtual(&self, idx: usize, column: usize) -> E {
+        match idx {
+            0 | 1 => self.table.get_row(idx)[column],
+            _ => panic!(),
         }
     }
 }
@@ -123,17 +117,17 @@ impl<'a, E: FieldElement> MainFrameSegment<'a, E> {
 impl<'a, E: FieldElement + From<Felt>> MainFrameSegment<'a, E> {
     /// Result
     pub fn res(&self) -> E {
-        self.get(0, DataSegment::ResValue)
+        self.get(RES_ROW, DataSegment::Cpu)
     }
     /// Registers
     pub fn pc(&self) -> E {
-        self.get(0, DataSegment::MemoryAddress)
+        self.get(PC.1, DataSegment::MemoryAddress)
     }
     pub fn ap(&self) -> E {
-        self.get(0, DataSegment::TempMemoryPointer)
+        self.get(AP.1, DataSegment::Cpu)
     }
     pub fn fp(&self) -> E {
-        self.get(1, DataSegment::TempMemoryPointer)
+        self.get(FP.1, DataSegment::Cpu)
     }
     /// Memory addresses
     pub fn dst_addr(&self) -> E {
@@ -158,34 +152,49 @@ impl<'a, E: FieldElement + From<Felt>> MainFrameSegment<'a, E> {
     pub fn op1(&self) -> E {
         self.get(3, DataSegment::MemoryValues)
     }
+    pub fn filler_value(&self, idx: usize) -> E {
+        self.get(STEP_MEM_WIDTH + idx, DataSegment::MemoryValues)
+    }
     /// Instruction size
     pub fn inst_size(&self) -> E {
         self.f_op1_val() + Felt::ONE.into()
     }
     /// Derived trace values
     pub fn t0(&self) -> E {
-        self.get(0, DataSegment::TempValues)
+        self.get(T0_ROW, DataSegment::Cpu)
     }
     pub fn t1(&self) -> E {
-        self.get(1, DataSegment::TempValues)
+        self.get(T1_ROW, DataSegment::Cpu)
     }
     pub fn mul(&self) -> E {
-        self.get(2, DataSegment::TempValues)
+        self.get(MUL_ROW, DataSegment::Cpu)
     }
     /// Virtual columns of memory addreses and values
     pub fn a_m(&self, idx: usize) -> E {
-        self.get_virtual(idx, MEM_A_TRACE_OFFSET, MEM_A_TRACE_WIDTH)
+        self.get_virtual(idx, MEM_A_TRACE_OFFSET)
     }
     pub fn v_m(&self, idx: usize) -> E {
-        self.get_virtual(idx, MEM_V_TRACE_OFFSET, MEM_V_TRACE_WIDTH)
+        self.get_virtual(idx, MEM_V_TRACE_OFFSET)
     }
     /// Virtual columns of offsets
     pub fn a_rc(&self, idx: usize) -> E {
-        self.get_virtual(idx, OFF_X_TRACE_OFFSET, OFF_X_TRACE_WIDTH)
+        self.get_virtual(idx, OFF_X_TRACE_OFFSET)
+    }
+    /// LogUp tables of memory cells and range check values
+    pub fn mem_table_a(&self, idx: usize) -> E {
+        self.get_virtual(idx, MEM_TABLE_A_OFFSET)
+    }
+    pub fn mem_table_v(&self, idx: usize) -> E {
+        self.get_virtual(idx, MEM_TABLE_V_OFFSET)
     }
-    /// Selector
-    pub fn selector(&self) -> E {
-        self.get(0, DataSegment::Selector)
+    pub fn mem_table_m(&self, idx: usize) -> E {
+        self.get_virtual(idx, MEM_TABLE_M_OFFSET)
+    }
+    pub fn rc_table(&self, idx: usize) -> E {
+        self.get_virtual(idx, RC_TABLE_OFFSET)
+    }
+    pub fn rc_table_m(&self, idx: usize) -> E {
+        self.get_virtual(idx, RC_TABLE_M_OFFSET)
     }
 }
 
@@ -287,32 +296,45 @@ impl<'a, E: FieldElement> AuxFrameSegment<'a, E> {
         Self { curr_row, next_row }
     }
 
-    fn get_virtual(&self, idx: usize, offset: usize, width: usize) -> E {
-        if (0..width).contains(&idx) {
-            self.curr_row[offset + idx]
-        } else if (width..width * 2).contains(&idx) {
-            self.next_row[offset + idx - width]
-        } else {
-            panic!()
+    /// Returns the value of a column at the current (`idx` 0) or next (`idx` 1) row
+    fn get_virtual(&self, idx: usize, column: usize) -> E {
+        match idx {
+            0 => self.curr_row[column],
+            1 => self.next_row[column],
+            _ => panic!(),
         }
     }
 
     /// Memory
     pub fn a_m_prime(&self, idx: usize) -> E {
-        self.get_virtual(idx, A_M_PRIME_OFFSET, A_M_PRIME_WIDTH)
+        self.get_virtual(idx, A_M_PRIME_OFFSET)
     }
     pub fn v_m_prime(&self, idx: usize) -> E {
-        self.get_virtual(idx, V_M_PRIME_OFFSET, V_M_PRIME_WIDTH)
+        self.get_virtual(idx, V_M_PRIME_OFFSET)
     }
     pub fn p_m(&self, idx: usize) -> E {
-        self.get_virtual(idx, P_M_OFFSET, P_M_WIDTH)
+        self.get_virtual(idx, P_M_OFFSET)
     }
 
     /// Permutation range check
     pub fn a_rc_prime(&self, idx: usize) -> E {
-        self.get_virtual(idx, A_RC_PRIME_OFFSET, A_RC_PRIME_WIDTH)
+        self.get_virtual(idx, A_RC_PRIME_OFFSET)
     }
     pub fn p_rc(&self, idx: usize) -> E {
-        self.get_virtual(idx, P_RC_OFFSET, P_RC_WIDTH)
+        self.get_virtual(idx, P_RC_OFFSET)
+    }
+
+    /// LogUp running sums and inverses
+    pub fn s_m(&self, idx: usize) -> E {
+        self.get_virtual(idx, S_M_OFFSET)
+    }
+    pub fn w_m(&self, idx: usize) -> E {
+        self.get_virtual(idx, W_M_OFFSET)
+    }
+    pub fn s_rc(&self, idx: usize) -> E {
+        self.get_virtual(idx, S_RC_OFFSET)
+    }
+    pub fn w_rc(&self, idx: usize) -> E {
+        self.get_virtual(idx, W_RC_OFFSET)
     }
 }
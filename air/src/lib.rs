@@ -1,8 +1,12 @@
 #![feature(generic_associated_types)]
 
+use core::fmt;
 use giza_core::{
-    Builtin, ExtensionOf, Felt, FieldElement, RegisterState, Word, A_RC_PRIME_FIRST,
-    A_RC_PRIME_LAST, MEM_A_TRACE_OFFSET, MEM_P_TRACE_OFFSET, P_M_LAST,
+    AuxArgument, Builtin, ExtensionOf, Felt, FieldElement, RegisterState, SegmentTable, Word, AP,
+    AUX_LOGUP_MEM_TRACE_WIDTH, AUX_LOGUP_RC_TRACE_WIDTH, AUX_MEM_TRACE_WIDTH, AUX_RC_TRACE_WIDTH,
+    A_RC_PRIME_FIRST, A_RC_PRIME_LAST, FP, LOGUP_TRACE_WIDTH, MEM_A_TRACE_OFFSET,
+    MEM_TABLE_M_OFFSET, MEM_V_TRACE_OFFSET, OFF_X_TRACE_OFFSET, PC, P_M_LAST, RC_TABLE_M_OFFSET,
+    RC_TABLE_OFFSET, STEP_HEIGHT, STEP_MEM_WIDTH, S_M_OFFSET, S_RC_OFFSET, TRACE_WIDTH,
 };
 use winter_air::{
     Air, AirContext, Assertion, AuxTraceRandElements, ProofOptions as WinterProofOptions,
@@ -13,6 +17,7 @@ use winter_utils::{ByteReader, ByteWriter, Deserializable, DeserializationError,
 // EXPORTS
 // ================================================================================================
 
+pub use giza_core::AuxArgument;
 pub use winter_air::{EvaluationFrame, FieldExtension, HashFunction};
 
 mod options;
@@ -24,12 +29,19 @@ use constraints::{AuxEvaluationResult, EvaluationResult};
 mod frame;
 pub use frame::{AuxEvaluationFrame, MainEvaluationFrame};
 
+mod debug;
+pub use debug::ConstraintFailure;
+
 // PROCESSOR AIR
 // ================================================================================================
 
 pub struct ProcessorAir {
     context: AirContext<Felt>,
     pub_inputs: PublicInputs,
+    num_blinding_rows: usize,
+    argument: AuxArgument,
+    main_exemptions: Vec<usize>,
+    aux_exemptions: Vec<usize>,
 }
 
 impl Air for ProcessorAir {
@@ -39,123 +51,222 @@ impl Air for ProcessorAir {
     type AuxFrame<E: FieldElement> = AuxEvaluationFrame<E>;
 
     fn new(trace_info: TraceInfo, pub_inputs: PublicInputs, options: WinterProofOptions) -> Self {
+        // Constraints of a step only hold at its first row, which is selected by a periodic
+        // column
+        let degree = |d| TransitionConstraintDegree::with_cycles(d, vec![STEP_HEIGHT]);
+
         let mut main_degrees = vec![];
         // Instruction constraints
         for _ in 0..=14 {
-            main_degrees.push(TransitionConstraintDegree::new(2)); // F0-F14
+            main_degrees.push(degree(2)); // F0-F14
         }
-        main_degrees.push(TransitionConstraintDegree::new(1)); // F15
+        main_degrees.push(degree(1)); // F15
 
         // Operand constraints
-        main_degrees.push(TransitionConstraintDegree::new(4)); // INST
-        main_degrees.push(TransitionConstraintDegree::new(4)); // DST_ADDR
-        main_degrees.push(TransitionConstraintDegree::new(4)); // OP0_ADDR
-        main_degrees.push(TransitionConstraintDegree::new(4)); // OP1_ADDR
+        main_degrees.push(degree(1)); // INST
+        main_degrees.push(degree(2)); // DST_ADDR
+        main_degrees.push(degree(2)); // OP0_ADDR
+        main_degrees.push(degree(2)); // OP1_ADDR
 
         // Register constraints
-        main_degrees.push(TransitionConstraintDegree::new(4)); // NEXT_AP
-        main_degrees.push(TransitionConstraintDegree::new(4)); // NEXT_FP
-        main_degrees.push(TransitionConstraintDegree::new(4)); // NEXT_PC_1
-        main_degrees.push(TransitionConstraintDegree::new(4)); // NEXT_PC_2
-        main_degrees.push(TransitionConstraintDegree::new(4)); // T0
-        main_degrees.push(TransitionConstraintDegree::new(4)); // T1
+        main_degrees.push(degree(2)); // NEXT_AP
+        main_degrees.push(degree(2)); // NEXT_FP
+        main_degrees.push(degree(2)); // NEXT_PC_1
+        main_degrees.push(degree(2)); // NEXT_PC_2
+        main_degrees.push(degree(2)); // T0
+        main_degrees.push(degree(2)); // T1
 
         // Opcode constraints
-        main_degrees.push(TransitionConstraintDegree::new(4)); // MUL_1
-        main_degrees.push(TransitionConstraintDegree::new(4)); // MUL_2
-        main_degrees.push(TransitionConstraintDegree::new(4)); // CALL_1
-        main_degrees.push(TransitionConstraintDegree::new(4)); // CALL_2
-        main_degrees.push(TransitionConstraintDegree::new(4)); // ASSERT_EQ
-
-        let aux_degrees = vec![
-            // Memory constraints
-            TransitionConstraintDegree::new(2), // A_M_PRIME 0
-            TransitionConstraintDegree::new(2), //     "     1
-            TransitionConstraintDegree::new(2), //     "     2
-            TransitionConstraintDegree::new(2), //     "     3
-            TransitionConstraintDegree::new(2), // V_M_PRIME 0
-            TransitionConstraintDegree::new(2), //     "     1
-            TransitionConstraintDegree::new(2), //     "     2
-            TransitionConstraintDegree::new(2), //     "     3
-            TransitionConstraintDegree::new(2), //    P_M    0
-            TransitionConstraintDegree::new(2), //     "     1
-            TransitionConstraintDegree::new(2), //     "     2
-            TransitionConstraintDegree::new(2), //     "     3
-            // Range check constraints
-            TransitionConstraintDegree::new(2), // A_RC_PRIME 0
-            TransitionConstraintDegree::new(2), //     "      1
-            TransitionConstraintDegree::new(2), //     "      2
-            TransitionConstraintDegree::new(2), //    P_RC    0
-            TransitionConstraintDegree::new(2), //     "      1
-            TransitionConstraintDegree::new(2), //     "      2
-        ];
+        main_degrees.push(degree(2)); // MUL_1
+        main_degrees.push(degree(2)); // MUL_2
+        main_degrees.push(degree(2)); // CALL_1
+        main_degrees.push(degree(2)); // CALL_2
+        main_degrees.push(degree(2)); // ASSERT_EQ
+
+        // Filler constraints
+        let num_filler_constraints = STEP_HEIGHT - STEP_MEM_WIDTH;
+        for _ in 0..num_filler_constraints {
+            main_degrees.push(degree(1)); // FILLER_VALUE
+        }
+        let num_step_constraints = main_degrees.len();
 
-        let mut transition_exemptions = vec![];
-        transition_exemptions.extend(vec![1; main_degrees.len()]);
-        transition_exemptions.extend(vec![1; aux_degrees.len()]);
+        // The layout of the trace is read from the public inputs rather than from the trace
+        // metadata, which is only checked against them (see PublicInputs::check_trace_info).
+        // The number of random rows is capped so that an unchecked trace can't underflow the
+        // last step.
+        let num_blinding_rows = pub_inputs.num_blinding_rows.min(trace_info.length() / 2);
+        let argument = pub_inputs.argument;
+        let (aux_degrees, num_assertions, num_aux_assertions) = match argument {
+            AuxArgument::Permutation => {
+                let aux_degrees = vec![
+                    // Memory constraints
+                    TransitionConstraintDegree::new(2), // A_M_PRIME
+                    TransitionConstraintDegree::new(2), // V_M_PRIME
+                    TransitionConstraintDegree::new(2), //    P_M
+                    // Range check constraints
+                    TransitionConstraintDegree::new(2), // A_RC_PRIME
+                    TransitionConstraintDegree::new(2), //    P_RC
+                ];
+                (aux_degrees, 6, 3)
+            }
+            AuxArgument::LogUp => {
+                // Table constraints
+                main_degrees.push(TransitionConstraintDegree::new(2)); // MEM_TABLE_A
+                main_degrees.push(TransitionConstraintDegree::new(2)); // MEM_TABLE_V
+                main_degrees.push(TransitionConstraintDegree::new(2)); // RC_TABLE
 
-        let mut context =
-            AirContext::new_multi_segment(trace_info, main_degrees, aux_degrees, 4, 3, options);
-        context.set_transition_exemptions(transition_exemptions);
+                let aux_degrees = vec![
+                    // Memory constraints
+                    TransitionConstraintDegree::new(2), // S_M
+                    TransitionConstraintDegree::new(2), // W_M
+                    // Range check constraints
+                    TransitionConstraintDegree::new(2), // S_RC
+                    TransitionConstraintDegree::new(2), // W_RC
+                ];
+                (aux_degrees, 13, 4)
+            }
+        };
+
+        // The transitions of the last step are exempted, since they would wrap around to the
+        // first step. When blinded, the trace ends with random rows following the
+        // last step. Filler constraints don't involve the next step, and hold at the last step.
+        let num_cpu_exemptions = num_blinding_rows + STEP_HEIGHT;
+        let num_filler_exemptions = num_blinding_rows.max(1);
+        let num_row_exemptions = num_blinding_rows + 1;
+        let mut main_exemptions = vec![];
+        main_exemptions.extend(vec![
+            num_cpu_exemptions;
+            num_step_constraints - num_filler_constraints
+        ]);
+        main_exemptions.extend(vec![num_filler_exemptions; num_filler_constraints]);
+        main_exemptions.extend(vec![
+            num_row_exemptions;
+            main_degrees.len() - num_step_constraints
+        ]);
+        let aux_exemptions = vec![num_row_exemptions; aux_degrees.len()];
+
+        let mut context = AirContext::new_multi_segment(
+            trace_info,
+            main_degrees,
+            aux_degrees,
+            num_assertions,
+            num_aux_assertions,
+            options,
+        );
+        context.set_transition_exemptions([&main_exemptions[..], &aux_exemptions[..]].concat());
 
         Self {
             context,
             pub_inputs,
+            num_blinding_rows,
+            argument,
+            main_exemptions,
+            aux_exemptions,
         }
     }
 
     fn get_assertions(&self) -> Vec<Assertion<Felt>> {
-        let last_step = self.pub_inputs.num_steps - 1;
-        vec![
+        // The final register state is that of the end loop, which is repeated up to the
+        // last step of the trace
+        let last_step_row = self.last_step() * STEP_HEIGHT;
+        let mut assertions = vec![
             // Initial and final 'pc' register
-            Assertion::single(MEM_A_TRACE_OFFSET, 0, self.pub_inputs.init.pc),
-            Assertion::single(MEM_A_TRACE_OFFSET, last_step, self.pub_inputs.fin.pc),
+            Assertion::single(PC.0, PC.1, self.pub_inputs.init.pc),
+            Assertion::single(PC.0, last_step_row + PC.1, self.pub_inputs.fin.pc),
             // Initial and final 'ap' register
-            Assertion::single(MEM_P_TRACE_OFFSET, 0, self.pub_inputs.init.ap),
-            Assertion::single(MEM_P_TRACE_OFFSET, last_step, self.pub_inputs.fin.ap),
-        ]
+            Assertion::single(AP.0, AP.1, self.pub_inputs.init.ap),
+            Assertion::single(AP.0, last_step_row + AP.1, self.pub_inputs.fin.ap),
+            // Initial and final 'fp' register
+            Assertion::single(FP.0, FP.1, self.pub_inputs.init.fp),
+            Assertion::single(FP.0, last_step_row + FP.1, self.pub_inputs.fin.fp),
+        ];
+
+        if self.argument == AuxArgument::LogUp {
+            // The range check table spans the range of offsets. The last row of the trace
+            // holds a free filler access and offset, and the padding of the tables, so that
+            // its terms in the running sums are known.
+            let last_row = self.last_row();
+            let rc_min = Felt::from(self.pub_inputs.rc_min);
+            let rc_max = Felt::from(self.pub_inputs.rc_max);
+            assertions.extend([
+                Assertion::single(RC_TABLE_OFFSET, 0, rc_min),
+                Assertion::single(RC_TABLE_OFFSET, last_row, rc_max),
+                Assertion::single(MEM_A_TRACE_OFFSET, last_row, Felt::ZERO),
+                Assertion::single(MEM_V_TRACE_OFFSET, last_row, Felt::ZERO),
+                Assertion::single(MEM_TABLE_M_OFFSET, last_row, Felt::ZERO),
+                Assertion::single(OFF_X_TRACE_OFFSET, last_row, rc_min),
+                Assertion::single(RC_TABLE_M_OFFSET, last_row, Felt::ZERO),
+            ]);
+        }
+        assertions
     }
 
     fn get_aux_assertions<E: FieldElement + From<Self::BaseField>>(
         &self,
         aux_rand_elements: &AuxTraceRandElements<E>,
     ) -> Vec<Assertion<E>> {
-        let last_step = self.trace_length() - 1;
+        let last_row = self.last_row();
         let random_elements = aux_rand_elements.get_segment_elements(0);
         let mem = &self.pub_inputs.mem;
         let z = random_elements[0];
         let alpha = random_elements[1];
-        let num = z.exp((mem.0.len() as u64).into());
+        let rc_min = E::from(self.pub_inputs.rc_min);
+        let rc_max = E::from(self.pub_inputs.rc_max);
 
-        let den = mem
+        let terms = mem
             .0
             .iter()
             .zip(&mem.1)
-            .map(|(a, v)| z - (E::from(*a as u64) + alpha * E::from(v.unwrap().word())))
-            .reduce(|a, b| a * b)
-            .unwrap();
-
-        vec![
-            // Public memory
-            Assertion::single(P_M_LAST, last_step, num / den),
-            // Minimum range check value
-            Assertion::single(A_RC_PRIME_FIRST, 0, E::from(self.pub_inputs.rc_min)),
-            // Maximum range check value
-            Assertion::single(A_RC_PRIME_LAST, last_step, E::from(self.pub_inputs.rc_max)),
-        ]
+            .map(|(a, v)| z - (E::from(*a as u64) + alpha * E::from(v.unwrap().word())));
+
+        match self.argument {
+            AuxArgument::Permutation => {
+                let num = z.exp((mem.0.len() as u64).into());
+                let den = terms.reduce(|a, b| a * b).unwrap();
+                vec![
+                    // Public memory
+                    Assertion::single(P_M_LAST, last_row, num / den),
+                    // Minimum range check value
+                    Assertion::single(A_RC_PRIME_FIRST, 0, rc_min),
+                    // Maximum range check value
+                    Assertion::single(A_RC_PRIME_LAST, last_row, rc_max),
+                ]
+            }
+            AuxArgument::LogUp => {
+                // The table multiplicities include the public memory, which is missing from
+                // the accesses. The running sums exclude the last row, which holds a (0,0)
+                // access and an offset of rc_min.
+                let public = terms.fold(E::ZERO, |acc, term| acc + term.inv());
+                let s_m_last = -public - z.inv();
+                let z_rc = aux_rand_elements.get_segment_elements(1)[0];
+                let s_rc_last = -(z_rc - rc_min).inv();
+                vec![
+                    // Memory
+                    Assertion::single(S_M_OFFSET, 0, E::ZERO),
+                    Assertion::single(S_M_OFFSET, last_row, s_m_last),
+                    // Range check
+                    Assertion::single(S_RC_OFFSET, 0, E::ZERO),
+                    Assertion::single(S_RC_OFFSET, last_row, s_rc_last),
+                ]
+            }
+        }
     }
 
     fn evaluate_transition<E: FieldElement + From<Felt>>(
         &self,
         frame: &MainEvaluationFrame<E>,
-        _periodic_values: &[E],
+        periodic_values: &[E],
         result: &mut [E],
     ) {
         result.evaluate_instr_constraints(frame);
         result.evaluate_operand_constraints(frame);
         result.evaluate_register_constraints(frame);
         result.evaluate_opcode_constraints(frame);
-        result.enforce_selector(frame);
+        result.evaluate_filler_constraints(frame);
+        result.enforce_step(periodic_values[0]);
+        if self.argument == AuxArgument::LogUp {
+            result.evaluate_table_constraints(frame);
+        }
     }
 
     fn evaluate_aux_transition<
@@ -169,8 +280,27 @@ impl Air for ProcessorAir {
         aux_rand_elements: &AuxTraceRandElements<F>,
         result: &mut [F],
     ) {
-        result.evaluate_memory_constraints(main_frame, aux_frame, aux_rand_elements);
-        result.evaluate_range_check_constraints(main_frame, aux_frame, aux_rand_elements);
+        match self.argument {
+            AuxArgument::Permutation => {
+                result.evaluate_memory_constraints(main_frame, aux_frame, aux_rand_elements);
+                result.evaluate_range_check_constraints(main_frame, aux_frame, aux_rand_elements);
+            }
+            AuxArgument::LogUp => {
+                result.evaluate_logup_memory_constraints(main_frame, aux_frame, aux_rand_elements);
+                result.evaluate_logup_range_check_constraints(
+                    main_frame,
+                    aux_frame,
+                    aux_rand_elements,
+                );
+            }
+        }
+    }
+
+    fn get_periodic_column_values(&self) -> Vec<Vec<Felt>> {
+        // Selects the first row of each step
+        let mut step_mask = vec![Felt::ZERO; STEP_HEIGHT];
+        step_mask[0] = Felt::ONE;
+        vec![step_mask]
     }
 
     fn context(&self) -> &AirContext<Felt> {
@@ -178,6 +308,68 @@ impl Air for ProcessorAir {
     }
 }
 
+impl ProcessorAir {
+    /// Returns the last step of the trace, which precedes the random rows of a
+    /// blinded trace
+    fn last_step(&self) -> usize {
+        ((self.trace_length() - self.num_blinding_rows) / STEP_HEIGHT).saturating_sub(1)
+    }
+
+    /// Returns the last row of the last step
+    fn last_row(&self) -> usize {
+        (self.last_step() + 1) * STEP_HEIGHT - 1
+    }
+}
+
+// TRACE LAYOUT
+// ================================================================================================
+
+/// Encodes the number of random rows of a blinded trace and the argument as trace
+/// metadata, which is left empty for traces using neither
+pub fn trace_meta(num_blinding_rows: usize, argument: AuxArgument) -> Vec<u8> {
+    match (num_blinding_rows, argument) {
+        (0, AuxArgument::Permutation) => vec![],
+        (num_rows, argument) => {
+            let mut meta = (num_rows as u64).to_le_bytes().to_vec();
+            meta.push(argument as u8);
+            meta
+        }
+    }
+}
+
+/// The layout of a proven trace doesn't match the public inputs, which the AIR is built from
+#[derive(Debug, PartialEq)]
+pub enum TraceLayoutError {
+    /// The trace metadata doesn't hold the number of random rows and the argument of the
+    /// public inputs
+    MetadataMismatch,
+    /// The trace segments don't have the widths of the argument of the public inputs
+    WidthMismatch,
+    /// The number of random rows isn't a whole number of steps, or exceeds half of the
+    /// trace length
+    InvalidBlindingRows(usize),
+}
+
+impl fmt::Display for TraceLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MetadataMismatch => write!(
+                f,
+                "the trace metadata doesn't match the random rows and argument of the public inputs"
+            ),
+            Self::WidthMismatch => write!(
+                f,
+                "the trace segments don't match the argument of the public inputs"
+            ),
+            Self::InvalidBlindingRows(num_rows) => write!(
+                f,
+                "invalid number of random rows {} for the length of the trace",
+                num_rows
+            ),
+        }
+    }
+}
+
 // PUBLIC INPUTS
 // ================================================================================================
 
@@ -189,6 +381,9 @@ pub struct PublicInputs {
     pub mem: (Vec<u64>, Vec<Option<Word>>), // public memory
     pub num_steps: usize, // number of execution steps
     pub builtins: Vec<Builtin>, // list of builtins
+    pub segments: Option<SegmentTable>, // memory segments, if known
+    pub num_blinding_rows: usize, // number of random rows ending the trace
+    pub argument: AuxArgument, // argument proving memory consistency and range checks
 }
 
 impl PublicInputs {
@@ -200,6 +395,7 @@ impl PublicInputs {
         mem: (Vec<u64>, Vec<Option<Word>>),
         num_steps: usize,
         builtins: Vec<Builtin>,
+        segments: Option<SegmentTable>,
     ) -> Self {
         Self {
             init,
@@ -209,20 +405,59 @@ impl PublicInputs {
             mem,
             num_steps,
             builtins,
+            segments,
+            num_blinding_rows: 0,
+            argument: AuxArgument::Permutation,
+        }
+    }
+
+    /// Sets the number of random rows ending a blinded trace, and the argument
+    /// proving memory consistency and range checks, which determine the layout of the trace
+    pub fn with_trace_layout(mut self, num_blinding_rows: usize, argument: AuxArgument) -> Self {
+        self.num_blinding_rows = num_blinding_rows;
+        self.argument = argument;
+        self
+    }
+
+    /// Checks that a proven trace has the layout of the public inputs. The AIR is built from
+    /// the public inputs and can't evaluate the constraints over a trace of another layout,
+    /// so proofs must be checked before they are verified.
+    pub fn check_trace_info(&self, trace_info: &TraceInfo) -> Result<(), TraceLayoutError> {
+        let num_rows = self.num_blinding_rows;
+        if num_rows % STEP_HEIGHT != 0 || num_rows > trace_info.length() / 2 {
+            return Err(TraceLayoutError::InvalidBlindingRows(num_rows));
+        }
+        if trace_info.meta() != trace_meta(num_rows, self.argument) {
+            return Err(TraceLayoutError::MetadataMismatch);
+        }
+        let (main_width, aux_widths) = match self.argument {
+            AuxArgument::Permutation => (TRACE_WIDTH, [AUX_MEM_TRACE_WIDTH, AUX_RC_TRACE_WIDTH]),
+            AuxArgument::LogUp => (
+                LOGUP_TRACE_WIDTH,
+                [AUX_LOGUP_MEM_TRACE_WIDTH, AUX_LOGUP_RC_TRACE_WIDTH],
+            ),
+        };
+        let layout = trace_info.layout();
+        let aux_matches = (0..aux_widths.len()).all(|i| {
+            layout.get_aux_segment_width(i) == aux_widths[i]
+                && layout.get_aux_segment_rand_elements(i) == [2, 1][i]
+        });
+        if layout.main_trace_width() != main_width
+            || layout.num_aux_segments() != aux_widths.len()
+            || !aux_matches
+        {
+            return Err(TraceLayoutError::WidthMismatch);
         }
+        Ok(())
     }
 }
 
-// TODO: Implement Serializable/Deserializable traits in RegisterState and Memory
-// structs instead of manually managing it here
+// TODO: Implement Serializable/Deserializable traits in Memory struct instead of
+// manually managing it here
 impl Serializable for PublicInputs {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
-        target.write(self.init.pc);
-        target.write(self.init.ap);
-        target.write(self.init.fp);
-        target.write(self.fin.pc);
-        target.write(self.fin.ap);
-        target.write(self.fin.fp);
+        self.init.write_into(target);
+        self.fin.write_into(target);
         target.write_u16(self.rc_min);
         target.write_u16(self.rc_max);
         target.write_u64(self.mem.1.len() as u64);
@@ -238,28 +473,27 @@ impl Serializable for PublicInputs {
         );
         target.write_u64(self.num_steps as u64);
         // TODO: Use bit representation once multiple builtins are supported
-        for builtin in self.builtins.iter() {
-            if let Builtin::Output(_) = builtin {
+        let has_output = self
+            .builtins
+            .iter()
+            .any(|builtin| matches!(builtin, Builtin::Output(_)));
+        target.write_u8(has_output as u8);
+        match &self.segments {
+            Some(segments) => {
                 target.write_u8(1);
-            } else {
-                target.write_u8(0);
+                segments.write_into(target);
             }
+            None => target.write_u8(0),
         }
+        target.write_u64(self.num_blinding_rows as u64);
+        target.write_u8(self.argument as u8);
     }
 }
 
 impl Deserializable for PublicInputs {
     fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
-        let init = RegisterState::new(
-            Felt::read_from(source)?,
-            Felt::read_from(source)?,
-            Felt::read_from(source)?,
-        );
-        let fin = RegisterState::new(
-            Felt::read_from(source)?,
-            Felt::read_from(source)?,
-            Felt::read_from(source)?,
-        );
+        let init = RegisterState::read_from(source)?;
+        let fin = RegisterState::read_from(source)?;
         let rc_min = source.read_u16()?;
         let rc_max = source.read_u16()?;
         let mem_len = source.read_u64()?;
@@ -273,9 +507,29 @@ impl Deserializable for PublicInputs {
             .collect::<Vec<_>>();
         let num_steps = source.read_u64()?;
         // TODO: Interpret as bits once multiple builtins are supported
-        let builtins = match source.read_u8()? {
-            1 => vec![Builtin::Output(0)],
-            _ => vec![],
+        let has_output = source.read_u8()? == 1;
+        let segments = match source.read_u8()? {
+            1 => Some(SegmentTable::read_from(source)?),
+            _ => None,
+        };
+        let num_blinding_rows = source.read_u64()? as usize;
+        let argument = match source.read_u8()? {
+            0 => AuxArgument::Permutation,
+            1 => AuxArgument::LogUp,
+            argument => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "invalid argument {}",
+                    argument
+                )))
+            }
+        };
+        // The output length is only known from the output segment
+        let builtins = match has_output {
+            true => {
+                let output = segments.as_ref().and_then(|s| s.builtin("output"));
+                vec![Builtin::Output(output.map_or(0, |output| output.size()))]
+            }
+            false => vec![],
         };
         Ok(PublicInputs::new(
             init,
@@ -285,6 +539,8 @@ impl Deserializable for PublicInputs {
             (mem_a, mem_v),
             num_steps as usize,
             builtins,
-        ))
+            segments,
+        )
+        .with_trace_layout(num_blinding_rows, argument))
     }
 }
@@ -1,9 +1,17 @@
+use super::MainEvaluationFrame;
 use core::ops::Deref;
-use winter_air::{FieldExtension, HashFunction, ProofOptions as WinterProofOptions};
+use giza_core::{AuxArgument, Felt};
+use winter_air::{
+    EvaluationFrame, FieldExtension, HashFunction, ProofOptions as WinterProofOptions,
+};
 
 /// TODO: add docs
 #[derive(Clone)]
-pub struct ProofOptions(WinterProofOptions);
+pub struct ProofOptions {
+    options: WinterProofOptions,
+    blind_trace: bool,
+    argument: AuxArgument,
+}
 
 impl ProofOptions {
     pub fn new(
@@ -15,15 +23,19 @@ impl ProofOptions {
         fri_folding_factor: usize,
         fri_max_remainder_size: usize,
     ) -> Self {
-        Self(WinterProofOptions::new(
-            num_queries,
-            blowup_factor,
-            grinding_factor,
-            hash_fn,
-            field_extension,
-            fri_folding_factor,
-            fri_max_remainder_size,
-        ))
+        Self {
+            options: WinterProofOptions::new(
+                num_queries,
+                blowup_factor,
+                grinding_factor,
+                hash_fn,
+                field_extension,
+                fri_folding_factor,
+                fri_max_remainder_size,
+            ),
+            blind_trace: false,
+            argument: AuxArgument::default(),
+        }
     }
 
     pub fn with_proof_options(
@@ -32,26 +44,70 @@ impl ProofOptions {
         grinding_factor: Option<u32>,
         fri_folding_factor: Option<usize>,
         fri_max_remainder_size: Option<usize>,
+        field_extension: Option<FieldExtension>,
     ) -> Self {
-        Self(WinterProofOptions::new(
-            num_queries.unwrap_or(54),  // 27
-            blowup_factor.unwrap_or(4), //8,
-            grinding_factor.unwrap_or(16),
-            HashFunction::Blake3_192,
-            FieldExtension::None,
-            fri_folding_factor.unwrap_or(8),
-            fri_max_remainder_size.unwrap_or(256),
-        ))
+        Self {
+            options: WinterProofOptions::new(
+                num_queries.unwrap_or(54),  // 27
+                blowup_factor.unwrap_or(4), //8,
+                grinding_factor.unwrap_or(16),
+                HashFunction::Blake3_192,
+                field_extension.unwrap_or(FieldExtension::None),
+                fri_folding_factor.unwrap_or(8),
+                fri_max_remainder_size.unwrap_or(256),
+            ),
+            blind_trace: false,
+            argument: AuxArgument::default(),
+        }
+    }
+
+    /// Enables or disables blinding of the trace. Random rows are appended to the trace (and
+    /// to its auxiliary segments), so that the trace polynomials opened by a proof are
+    /// randomized.
+    ///
+    /// Proofs of blinded traces are not zero-knowledge: the composition polynomial is not
+    /// masked, and its evaluations opened at the query positions are combinations of trace
+    /// values.
+    pub fn with_trace_blinding(mut self, blind_trace: bool) -> Self {
+        self.blind_trace = blind_trace;
+        self
+    }
+
+    pub fn is_trace_blinded(&self) -> bool {
+        self.blind_trace
+    }
+
+    /// Returns the number of random rows appended to the trace. Each query opens every
+    /// trace polynomial at one point, and the out-of-domain frame at one point per row of
+    /// the main evaluation frame (18), so the trace polynomials need as many random
+    /// evaluations to hide the rest of the trace. The trace rounds this up to whole steps.
+    pub fn num_blinding_rows(&self) -> usize {
+        match self.blind_trace {
+            true => self.options.num_queries() + MainEvaluationFrame::<Felt>::num_rows(),
+            false => 0,
+        }
+    }
+
+    /// Selects the argument proving memory consistency and range checks. The LogUp argument
+    /// replaces the sorted copies of the grand product argument with tables and their
+    /// multiplicities in the main trace.
+    pub fn with_argument(mut self, argument: AuxArgument) -> Self {
+        self.argument = argument;
+        self
+    }
+
+    pub fn argument(&self) -> AuxArgument {
+        self.argument
     }
 
     pub fn into_inner(self) -> WinterProofOptions {
-        self.0
+        self.options
     }
 }
 
 impl Default for ProofOptions {
     fn default() -> Self {
-        Self::with_proof_options(None, None, None, None, None)
+        Self::with_proof_options(None, None, None, None, None, None)
     }
 }
 
@@ -59,6 +115,26 @@ impl Deref for ProofOptions {
     type Target = WinterProofOptions;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_num_blinding_rows() {
+        let options = ProofOptions::default();
+        assert_eq!(options.num_blinding_rows(), 0);
+
+        // Every query and every row of the out-of-domain frame opens one evaluation
+        let frame_rows = MainEvaluationFrame::<Felt>::offsets().len();
+        assert_eq!(frame_rows, 18);
+        let options = options.with_trace_blinding(true);
+        assert_eq!(
+            options.num_blinding_rows(),
+            options.num_queries() + frame_rows
+        );
     }
 }
@@ -0,0 +1,55 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+use super::ProofData;
+use crate::utils::Cmd;
+use air::PublicInputs;
+use clap::{Parser, ValueHint};
+use runner::{write_air_public_input, AirPublicInput};
+use winter_utils::{Deserializable, SliceReader};
+
+pub struct ExportPublicInputOutput {}
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct ExportPublicInputArgs {
+    #[clap(
+        help = "Path to the STARK proof",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub proof: PathBuf,
+
+    #[clap(
+        help = "Path to write the AIR public input JSON file",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub output: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(std::io::Error),
+}
+
+impl Cmd for ExportPublicInputArgs {
+    type Output = Result<ExportPublicInputOutput, Error>;
+
+    fn run(self) -> Self::Output {
+        // Load public inputs from the proof file
+        let mut b = Vec::new();
+        let mut f = File::open(self.proof).map_err(Error::Io)?;
+        f.read_to_end(&mut b).map_err(Error::Io)?;
+        let data: ProofData = bincode::deserialize(&b).unwrap();
+        let pub_inputs =
+            PublicInputs::read_from(&mut SliceReader::new(&data.input_bytes[..])).unwrap();
+
+        // Write them in the format of the cairo-runner
+        write_air_public_input(&self.output, &AirPublicInput::from(&pub_inputs))
+            .map_err(Error::Io)?;
+
+        Ok(ExportPublicInputOutput {})
+    }
+}
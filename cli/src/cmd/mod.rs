@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 
+pub mod export_public_input;
 pub mod prove;
+pub mod run;
+pub mod trace;
 pub mod verify;
 
 #[derive(Serialize, Deserialize)]
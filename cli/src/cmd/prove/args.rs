@@ -1,3 +1,4 @@
+use air::FieldExtension;
 use clap::{Error, ErrorKind, Parser, ValueHint};
 use std::path::PathBuf;
 
@@ -7,23 +8,51 @@ pub struct ProveArgs {
     #[clap(
         help = "Path to the compiled Cairo program JSON file",
         long,
+        required_unless_present_any = &["air_public_input", "pie"],
         value_hint = ValueHint::FilePath
     )]
-    pub program: PathBuf,
+    pub program: Option<PathBuf>,
 
     #[clap(
         help = "Path to the execution trace output file",
         long,
+        required_unless_present_any = &["air_public_input", "pie"],
         value_hint = ValueHint::FilePath
     )]
-    pub trace: PathBuf,
+    pub trace: Option<PathBuf>,
 
     #[clap(
         help = "Path to the memory output file",
         long,
+        required_unless_present_any = &["air_public_input", "pie"],
         value_hint = ValueHint::FilePath
     )]
-    pub memory: PathBuf,
+    pub memory: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to the AIR public input file written by cairo-run (--air_public_input)",
+        long,
+        requires = "air_private_input",
+        conflicts_with_all = &["program", "trace", "memory", "num_outputs"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub air_public_input: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to the AIR private input file written by cairo-run (--air_private_input)",
+        long,
+        requires = "air_public_input",
+        value_hint = ValueHint::FilePath
+    )]
+    pub air_private_input: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to a Cairo PIE archive, whose trace is reconstructed by re-execution",
+        long,
+        conflicts_with_all = &["program", "trace", "memory", "num_outputs", "air_public_input"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub pie: Option<PathBuf>,
 
     #[clap(
         help = "Path to write the STARK proof",
@@ -32,6 +61,24 @@ pub struct ProveArgs {
     )]
     pub output: PathBuf,
 
+    #[clap(
+        help = "Append random rows to the trace (this does not make the proof zero-knowledge)",
+        long
+    )]
+    pub blind_trace: bool,
+
+    #[clap(
+        help = "Prove memory consistency and range checks with LogUp instead of grand products",
+        long
+    )]
+    pub logup: bool,
+
+    #[clap(
+        help = "Evaluate the constraints at every row of the trace and the boundary assertions before proving, and report the ones that fail",
+        long
+    )]
+    pub check_constraints: bool,
+
     #[clap(help = "Number of serialized outputs", long)]
     pub num_outputs: Option<u64>,
 
@@ -69,6 +116,13 @@ pub struct ProveArgs {
         value_parser(clap::builder::ValueParser::new(parse_fri_max_remainder_size))
     )]
     pub fri_max_remainder_size: Option<usize>,
+
+    #[clap(
+        help = "Degree of the field extension used for out-of-domain sampling (1 or 2)",
+        long,
+        value_parser(clap::builder::ValueParser::new(parse_field_extension))
+    )]
+    pub field_extension: Option<FieldExtension>,
 }
 
 fn parse_num_queries(value: &str) -> Result<usize, Error> {
@@ -147,3 +201,15 @@ fn parse_fri_max_remainder_size(value: &str) -> Result<usize, Error> {
         _ => Ok(value),
     }
 }
+
+fn parse_field_extension(value: &str) -> Result<FieldExtension, Error> {
+    let value = value
+        .parse::<usize>()
+        .map_err(|e| Error::raw(ErrorKind::InvalidValue, format!("{}", e)))?;
+
+    match value {
+        1 => Ok(FieldExtension::None),
+        2 => Ok(FieldExtension::Quadratic),
+        _ => Err(Error::raw(ErrorKind::ValueValidation, "must be 1 or 2")),
+    }
+}
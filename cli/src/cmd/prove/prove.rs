@@ -3,32 +3,68 @@ use std::io::Write;
 
 use super::ProveArgs;
 use crate::{cmd::ProofData, utils::Cmd};
-use air::ProofOptions;
-use runner::ExecutionTrace;
+use air::{AuxArgument, ProofOptions};
+use runner::{ExecutionError, ExecutionTrace, ReadError};
 use winter_utils::Serializable;
 
 pub struct ProveOutput {}
 
 #[derive(Debug)]
-pub enum Error {}
+pub enum Error {
+    Read(ReadError),
+    Execution(ExecutionError),
+    UnsatisfiedConstraints(usize),
+}
 
 impl Cmd for ProveArgs {
     type Output = Result<ProveOutput, Error>;
 
     fn run(self) -> Self::Output {
         // Load trace from file
-        let trace =
-            ExecutionTrace::from_file(self.program, self.trace, self.memory, self.num_outputs);
+        let trace = match (self.pie, self.air_public_input, self.air_private_input) {
+            (Some(pie), _, _) => ExecutionTrace::from_pie(pie),
+            (None, Some(public_input), Some(private_input)) => {
+                ExecutionTrace::from_air_inputs(public_input, private_input)
+            }
+            _ => ExecutionTrace::from_file(
+                self.program.unwrap(),
+                self.trace.unwrap(),
+                self.memory.unwrap(),
+                self.num_outputs,
+            ),
+        }
+        .map_err(Error::Read)?;
 
-        // Generate proof
         let proof_options = ProofOptions::with_proof_options(
             self.num_queries,
             self.blowup_factor,
             self.grinding_factor,
             self.fri_folding_factor,
             self.fri_max_remainder_size,
-        );
-        let (proof, pub_inputs) = prover::prove_trace(trace, &proof_options).unwrap();
+            self.field_extension,
+        )
+        .with_trace_blinding(self.blind_trace)
+        .with_argument(match self.logup {
+            true => AuxArgument::LogUp,
+            false => AuxArgument::Permutation,
+        });
+
+        // Name the constraints that fail, which the prover can't tell apart
+        if self.check_constraints {
+            let failures =
+                prover::check_constraints(&trace, &proof_options).map_err(Error::Execution)?;
+            for failure in failures.iter() {
+                println!("{}", failure);
+            }
+            if !failures.is_empty() {
+                return Err(Error::UnsatisfiedConstraints(failures.len()));
+            }
+            println!("All constraints hold");
+        }
+
+        // Generate proof
+        let (proof, pub_inputs) =
+            prover::prove_trace(trace, &proof_options).map_err(Error::Execution)?;
         let input_bytes = pub_inputs.to_bytes();
         let proof_bytes = proof.to_bytes();
         println!("Proof size: {:.1} KB", proof_bytes.len() as f64 / 1024f64);
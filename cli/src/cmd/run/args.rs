@@ -0,0 +1,70 @@
+use clap::{Parser, ValueHint};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct RunArgs {
+    #[clap(
+        help = "Path to the compiled Cairo program JSON file",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub program: PathBuf,
+
+    #[cfg(feature = "hints")]
+    #[clap(
+        help = "Path to a JSON file with the input made available to hints as program_input",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub program_input: Option<PathBuf>,
+
+    #[cfg(feature = "hints")]
+    #[clap(
+        help = "Path to a JSON whitelist of the hints the program may run",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub hint_policy: Option<PathBuf>,
+
+    #[cfg(feature = "hints")]
+    #[clap(
+        help = "Path to record the memory writes and register changes made by hints",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub record_tape: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to a recorded hint tape to replay instead of executing hints",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub replay_tape: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to write the run as a Cairo PIE archive",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub pie_output: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to write the STARK proof",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub output: PathBuf,
+
+    #[clap(
+        help = "Append random rows to the trace (this does not make the proof zero-knowledge)",
+        long
+    )]
+    pub blind_trace: bool,
+
+    #[clap(
+        help = "Prove memory consistency and range checks with LogUp instead of grand products",
+        long
+    )]
+    pub logup: bool,
+}
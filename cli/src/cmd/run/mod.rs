@@ -0,0 +1,4 @@
+mod args;
+mod run;
+
+pub use args::RunArgs;
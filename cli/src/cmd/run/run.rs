@@ -0,0 +1,112 @@
+use std::fs::File;
+use std::io::Write;
+
+use super::RunArgs;
+use crate::{cmd::ProofData, utils::Cmd};
+use air::{AuxArgument, ProofOptions};
+use runner::{load_program, ExecutionError, HintTape, Program, ReadError};
+use winter_utils::Serializable;
+
+#[cfg(feature = "hints")]
+use giza_core::ProgramInputs;
+#[cfg(feature = "hints")]
+use runner::{
+    hints::{policy::HintPolicy, HintError},
+    read_hints,
+};
+
+pub struct RunOutput {}
+
+#[derive(Debug)]
+pub enum Error {
+    Program(ReadError),
+    #[cfg(feature = "hints")]
+    ProgramInput(std::io::Error),
+    #[cfg(feature = "hints")]
+    HintPolicy(std::io::Error),
+    Tape(std::io::Error),
+    Pie(std::io::Error),
+    Execution(ExecutionError),
+}
+
+impl Cmd for RunArgs {
+    type Output = Result<RunOutput, Error>;
+
+    fn run(self) -> Self::Output {
+        // Execute the program natively, running its hints or replaying a recorded tape
+        let (mut mem, pc, ap) = load_program(&self.program).map_err(Error::Program)?;
+        #[cfg(feature = "hints")]
+        let hints = read_hints(&self.program).map_err(Error::Program)?;
+        #[cfg(feature = "hints")]
+        let mut program = Program::new(&mut mem, pc, ap, Some(hints));
+        #[cfg(not(feature = "hints"))]
+        let mut program = Program::new(&mut mem, pc, ap);
+        #[cfg(feature = "hints")]
+        {
+            if let Some(path) = &self.program_input {
+                program.set_program_input(
+                    ProgramInputs::from_file(path).map_err(Error::ProgramInput)?,
+                );
+            }
+            if let Some(path) = &self.hint_policy {
+                program.set_hint_policy(HintPolicy::from_file(path).map_err(Error::HintPolicy)?);
+            }
+            if self.record_tape.is_some() {
+                program.record_tape();
+            }
+        }
+        if let Some(path) = &self.replay_tape {
+            program.set_tape(HintTape::read_file(path).map_err(Error::Tape)?);
+        }
+        if self.pie_output.is_some() {
+            program.record_pie();
+        }
+        let trace = match program.execute() {
+            Ok(trace) => trace,
+            #[cfg(feature = "hints")]
+            Err(ExecutionError::HintError(HintError::PolicyViolation(violations))) => {
+                for v in violations.iter() {
+                    println!(
+                        "Hint at pc {} is not allowed (hash {}):\n{}",
+                        v.pc, v.hash, v.code
+                    );
+                }
+                return Err(Error::Execution(ExecutionError::HintError(
+                    HintError::PolicyViolation(violations),
+                )));
+            }
+            Err(err) => return Err(Error::Execution(err)),
+        };
+        #[cfg(feature = "hints")]
+        if let (Some(path), Some(tape)) = (&self.record_tape, program.get_tape()) {
+            tape.write_file(path).map_err(Error::Tape)?;
+        }
+        if let (Some(path), Some(pie)) = (&self.pie_output, program.get_pie()) {
+            pie.write_file(path).map_err(Error::Pie)?;
+        }
+
+        // Generate proof
+        let proof_options = ProofOptions::with_proof_options(None, None, None, None, None, None)
+            .with_trace_blinding(self.blind_trace)
+            .with_argument(match self.logup {
+                true => AuxArgument::LogUp,
+                false => AuxArgument::Permutation,
+            });
+        let (proof, pub_inputs) =
+            prover::prove_trace(trace, &proof_options).map_err(Error::Execution)?;
+        let input_bytes = pub_inputs.to_bytes();
+        let proof_bytes = proof.to_bytes();
+        println!("Proof size: {:.1} KB", proof_bytes.len() as f64 / 1024f64);
+
+        // Write proof to disk
+        let data = ProofData {
+            input_bytes,
+            proof_bytes,
+        };
+        let b = bincode::serialize(&data).unwrap();
+        let mut f = File::create(self.output).unwrap();
+        f.write_all(&b).unwrap();
+
+        Ok(RunOutput {})
+    }
+}
@@ -0,0 +1,98 @@
+use clap::{Parser, Subcommand, ValueEnum, ValueHint};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct TraceArgs {
+    #[clap(subcommand)]
+    pub sub: TraceSubcommands,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TraceSubcommands {
+    Export(TraceExportArgs),
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Format {
+    Csv,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+pub struct TraceExportArgs {
+    #[clap(
+        help = "Path to the compiled Cairo program JSON file",
+        long,
+        required_unless_present_any = &["air_public_input", "pie"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub program: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to the execution trace output file",
+        long,
+        required_unless_present_any = &["air_public_input", "pie"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub trace: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to the memory output file",
+        long,
+        required_unless_present_any = &["air_public_input", "pie"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub memory: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to the AIR public input file written by cairo-run (--air_public_input)",
+        long,
+        requires = "air_private_input",
+        conflicts_with_all = &["program", "trace", "memory", "num_outputs"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub air_public_input: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to the AIR private input file written by cairo-run (--air_private_input)",
+        long,
+        requires = "air_public_input",
+        value_hint = ValueHint::FilePath
+    )]
+    pub air_private_input: Option<PathBuf>,
+
+    #[clap(
+        help = "Path to a Cairo PIE archive, whose trace is reconstructed by re-execution",
+        long,
+        conflicts_with_all = &["program", "trace", "memory", "num_outputs", "air_public_input"],
+        value_hint = ValueHint::FilePath
+    )]
+    pub pie: Option<PathBuf>,
+
+    #[clap(help = "Number of serialized outputs", long)]
+    pub num_outputs: Option<u64>,
+
+    #[clap(
+        help = "Path to write the exported trace",
+        long,
+        value_hint = ValueHint::FilePath
+    )]
+    pub output: PathBuf,
+
+    #[clap(help = "Format of the exported trace", long, value_enum, default_value_t = Format::Csv)]
+    pub format: Format,
+
+    #[clap(help = "Also export the memory and range check aux segments", long)]
+    pub aux: bool,
+
+    #[clap(
+        help = "Seed of the random elements used to build the aux segments",
+        long,
+        requires = "aux",
+        default_value_t = 0
+    )]
+    pub seed: u64,
+}
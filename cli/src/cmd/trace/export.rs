@@ -0,0 +1,49 @@
+use super::args::Format;
+use super::TraceExportArgs;
+use crate::utils::Cmd;
+use runner::{ExecutionTrace, ReadError, TraceFormat};
+
+pub struct TraceExportOutput {}
+
+#[derive(Debug)]
+pub enum Error {
+    Read(ReadError),
+    Io(std::io::Error),
+}
+
+impl Cmd for TraceExportArgs {
+    type Output = Result<TraceExportOutput, Error>;
+
+    fn run(self) -> Self::Output {
+        // Load trace from file
+        let mut trace = match (self.pie, self.air_public_input, self.air_private_input) {
+            (Some(pie), _, _) => ExecutionTrace::from_pie(pie),
+            (None, Some(public_input), Some(private_input)) => {
+                ExecutionTrace::from_air_inputs(public_input, private_input)
+            }
+            _ => ExecutionTrace::from_file(
+                self.program.unwrap(),
+                self.trace.unwrap(),
+                self.memory.unwrap(),
+                self.num_outputs,
+            ),
+        }
+        .map_err(Error::Read)?;
+
+        // Write the named trace columns
+        let format = match self.format {
+            Format::Csv => TraceFormat::Csv,
+            #[cfg(feature = "parquet")]
+            Format::Parquet => TraceFormat::Parquet,
+        };
+        let table = trace.to_table(self.aux.then_some(self.seed));
+        table.write_file(&self.output, format).map_err(Error::Io)?;
+        println!(
+            "Exported {} columns of {} rows",
+            table.names.len(),
+            table.num_rows()
+        );
+
+        Ok(TraceExportOutput {})
+    }
+}
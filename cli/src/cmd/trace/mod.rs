@@ -0,0 +1,4 @@
+mod args;
+mod export;
+
+pub use args::{TraceArgs, TraceExportArgs, TraceSubcommands};
@@ -38,7 +38,11 @@ impl Cmd for VerifyArgs {
             PublicInputs::read_from(&mut SliceReader::new(&data.input_bytes[..])).unwrap();
         let proof = StarkProof::from_bytes(&data.proof_bytes).unwrap();
 
-        // Verify execution
+        // Verify execution, once the trace is known to have the layout of the public inputs
+        if let Err(err) = pub_inputs.check_trace_info(&proof.get_trace_info()) {
+            println!("Failed to verify execution: {}", err);
+            return Ok(VerifyOutput {});
+        }
         match winterfell::verify::<ProcessorAir>(proof, pub_inputs) {
             Ok(_) => println!("Execution verified"),
             Err(err) => println!("Failed to verify execution: {}", err),
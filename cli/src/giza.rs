@@ -3,7 +3,13 @@ mod utils;
 
 use crate::utils::Cmd;
 use clap::{Parser, Subcommand};
-use cmd::{prove::ProveArgs, verify::VerifyArgs};
+use cmd::{
+    export_public_input::ExportPublicInputArgs,
+    prove::ProveArgs,
+    run::RunArgs,
+    trace::{TraceArgs, TraceSubcommands},
+    verify::VerifyArgs,
+};
 
 #[derive(Debug, Parser)]
 #[clap(name = "giza")]
@@ -17,6 +23,9 @@ pub struct Opts {
 pub enum Subcommands {
     Prove(ProveArgs),
     Verify(VerifyArgs),
+    Run(RunArgs),
+    ExportPublicInput(ExportPublicInputArgs),
+    Trace(TraceArgs),
 }
 
 fn main() {
@@ -28,6 +37,17 @@ fn main() {
         Subcommands::Verify(cmd) => {
             cmd.run().unwrap();
         }
+        Subcommands::Run(cmd) => {
+            cmd.run().unwrap();
+        }
+        Subcommands::ExportPublicInput(cmd) => {
+            cmd.run().unwrap();
+        }
+        Subcommands::Trace(cmd) => match cmd.sub {
+            TraceSubcommands::Export(cmd) => {
+                cmd.run().unwrap();
+            }
+        },
     }
 
     // TODO: consider returning Result<T,E> for error codes.
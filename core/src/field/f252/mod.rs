@@ -225,13 +225,18 @@ impl Neg for BaseElement {
 // ================================================================================================
 
 /// Defines a quadratic extension of the base field over an irreducible polynomial x<sup>2</sup> -
-/// x - 1. Thus, an extension element is defined as α + β * φ, where φ is a root of this polynomial,
-/// and α and β are base field elements.
+/// 3 (3 generates the multiplicative group of the base field, so it is not a square). Thus, an
+/// extension element is defined as α + β * φ, where φ is a root of this polynomial, and α and β
+/// are base field elements.
+///
+/// Note that x<sup>2</sup> - x - 1, which is used by the 64-bit fields of Winterfell, splits
+/// over this field, as 5 is a square modulo the field modulus.
 impl ExtensibleField<2> for BaseElement {
     #[inline(always)]
     fn mul(a: [Self; 2], b: [Self; 2]) -> [Self; 2] {
-        let z = a[0] * b[0];
-        [z + a[1] * b[1], (a[0] + a[1]) * (b[0] + b[1]) - z]
+        let z0 = a[0] * b[0];
+        let z1 = a[1] * b[1];
+        [z0 + z1 + z1 + z1, (a[0] + a[1]) * (b[0] + b[1]) - z0 - z1]
     }
 
     #[inline(always)]
@@ -241,7 +246,8 @@ impl ExtensibleField<2> for BaseElement {
 
     #[inline(always)]
     fn frobenius(x: [Self; 2]) -> [Self; 2] {
-        [x[0] + x[1], Self::ZERO - x[1]]
+        // φ^p = φ * 3^((p - 1) / 2) = -φ
+        [x[0], -x[1]]
     }
 }
 
@@ -0,0 +1,22 @@
+use super::{BaseElement, FieldElement, StarkField};
+use math::fields::QuadExtension;
+
+// QUADRATIC EXTENSION
+// ------------------------------------------------------------------------------------------------
+
+#[test]
+fn quad_mul_inv() {
+    let a = QuadExtension::new(BaseElement::from(3u64), BaseElement::from(5u64));
+    let b = QuadExtension::new(BaseElement::from(7u64), -BaseElement::from(11u64));
+    assert_eq!((a * b) * b.inv(), a);
+    assert_eq!(a * a.inv(), QuadExtension::ONE);
+}
+
+#[test]
+fn quad_frobenius() {
+    // The extension is a field only if the polynomial defining it is irreducible, in which
+    // case raising to the power of the modulus is a non-trivial automorphism
+    let a = QuadExtension::new(BaseElement::from(3u64), BaseElement::from(5u64));
+    let expected = QuadExtension::new(BaseElement::from(3u64), -BaseElement::from(5u64));
+    assert_eq!(a.exp(BaseElement::MODULUS), expected);
+}
@@ -1,2 +1,56 @@
-#[derive(Default)]
-pub struct ProgramInputs {}
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// Input supplied to a Cairo program at runtime (e.g. `--program_input` in cairo-run).
+/// Hints access it as `program_input`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProgramInputs {
+    data: Value,
+}
+
+impl Default for ProgramInputs {
+    fn default() -> Self {
+        Self {
+            data: Value::Object(Map::new()),
+        }
+    }
+}
+
+impl ProgramInputs {
+    /// Reads program input from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Returns the top-level entry with the given key, as in `program_input[key]`
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.data.get(key)
+    }
+
+    /// Returns the program input as a JSON value
+    pub fn as_json(&self) -> &Value {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_program_input() {
+        let input: ProgramInputs =
+            serde_json::from_str(r#"{"n": 10, "values": [1, 2, 3]}"#).unwrap();
+        assert_eq!(input.get("n"), Some(&Value::from(10)));
+        assert_eq!(input.get("values").unwrap().as_array().unwrap().len(), 3);
+        assert_eq!(
+            ProgramInputs::default().as_json(),
+            &Value::Object(Map::new())
+        );
+    }
+}
@@ -3,6 +3,8 @@
 pub use core::ops::Range;
 
 pub use math::{ExtensionOf, FieldElement, StarkField};
+use serde::{Deserialize, Serialize};
+use winter_utils::{ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable};
 
 pub mod word;
 pub use word::{
@@ -21,94 +23,197 @@ pub mod flags;
 
 // MAIN TRACE LAYOUT
 // -----------------------------------------------------------------------------------------
-//  A.  flags   (16) : Decoded instruction flags
-//  B.  res     (1)  : Res value
-//  C.  mem_p   (2)  : Temporary memory pointers (ap and fp)
-//  D.  mem_a   (4)  : Memory addresses (pc, dst_addr, op0_addr, op1_addr)
-//  E.  mem_v   (4)  : Memory values (inst, dst, op0, op1)
-//  F.  offsets (3)  : (off_dst, off_op0, off_op1)
-//  G.  derived (3)  : (t0, t1, mul)
+//  A.  flags   : Decoded instruction flags (f0 to f15)
+//  B.  offsets : Offsets (off_dst, off_op0, off_op1), followed by filler offsets
+//  C.  mem_a   : Memory addresses (pc, dst_addr, op0_addr, op1_addr), followed by filler
+//                addresses
+//  D.  mem_v   : Memory values (inst, dst, op0, op1), followed by filler values
+//  E.  cpu     : Registers and derived values (ap, fp, res, t0, t1, mul)
 //
-//  A                B C  D    E    F   G
-// ├xxxxxxxxxxxxxxxx|x|xx|xxxx|xxxx|xxx|xxx┤
+//       A   B        C         D       E
+//  0   ├f0 |off_dst |pc       |inst   |ap ┤
+//  1   ├f1 |off_op0 |dst_addr |dst    |fp ┤
+//  2   ├f2 |off_op1 |op0_addr |op0    |res┤
+//  3   ├f3 |filler  |op1_addr |op1    |t0 ┤
+//  4   ├f4 |filler  |filler   |filler |t1 ┤
+//  5   ├f5 |filler  |filler   |filler |mul┤
+//  6   ├f6 |filler  |filler   |filler |   ┤
+//  ...
+//  15  ├f15|filler  |filler   |filler |   ┤
 //
+// Every step of the execution spans STEP_HEIGHT rows, as in Starkware's CPU component. Once
+// the execution is over, steps repeat the end loop (`jmp rel 0`) that it runs into. The
+// filler memory accesses and offsets of a step are used to fill memory holes and hold
+// public memory, and to fill gaps between offsets. The memory and offset columns are read
+// as single virtual columns, row by row.
 
-pub const FLAG_TRACE_OFFSET: usize = 0;
-pub const FLAG_TRACE_WIDTH: usize = 16;
-pub const FLAG_TRACE_RANGE: Range<usize> = range(FLAG_TRACE_OFFSET, FLAG_TRACE_WIDTH);
-
-pub const RES_TRACE_OFFSET: usize = 16;
-pub const RES_TRACE_WIDTH: usize = 1;
-pub const RES_TRACE_RANGE: Range<usize> = range(RES_TRACE_OFFSET, RES_TRACE_WIDTH);
-
-pub const MEM_P_TRACE_OFFSET: usize = 17;
-pub const MEM_P_TRACE_WIDTH: usize = 2;
-pub const MEM_P_TRACE_RANGE: Range<usize> = range(MEM_P_TRACE_OFFSET, MEM_P_TRACE_WIDTH);
-
-pub const MEM_A_TRACE_OFFSET: usize = 19;
-pub const MEM_A_TRACE_WIDTH: usize = 4;
-pub const MEM_A_TRACE_RANGE: Range<usize> = range(MEM_A_TRACE_OFFSET, MEM_A_TRACE_WIDTH);
-
-pub const MEM_V_TRACE_OFFSET: usize = 23;
-pub const MEM_V_TRACE_WIDTH: usize = 4;
-pub const MEM_V_TRACE_RANGE: Range<usize> = range(MEM_V_TRACE_OFFSET, MEM_V_TRACE_WIDTH);
-
-pub const OFF_X_TRACE_OFFSET: usize = 27;
-pub const OFF_X_TRACE_WIDTH: usize = 3;
-pub const OFF_X_TRACE_RANGE: Range<usize> = range(OFF_X_TRACE_OFFSET, OFF_X_TRACE_WIDTH);
-
-pub const DERIVED_TRACE_OFFSET: usize = 30;
-pub const DERIVED_TRACE_WIDTH: usize = 3;
-pub const DERIVED_TRACE_RANGE: Range<usize> = range(DERIVED_TRACE_OFFSET, DERIVED_TRACE_WIDTH);
+/// Number of rows spanned by a step
+pub const STEP_HEIGHT: usize = 16;
 
-pub const SELECTOR_TRACE_OFFSET: usize = 33;
-pub const SELECTOR_TRACE_WIDTH: usize = 1;
-pub const SELECTOR_TRACE_RANGE: Range<usize> = range(SELECTOR_TRACE_OFFSET, SELECTOR_TRACE_WIDTH);
-
-pub const TRACE_WIDTH: usize = 34;
+pub const FLAG_TRACE_OFFSET: usize = 0;
+pub const OFF_X_TRACE_OFFSET: usize = 1;
+pub const MEM_A_TRACE_OFFSET: usize = 2;
+pub const MEM_V_TRACE_OFFSET: usize = 3;
+pub const CPU_TRACE_OFFSET: usize = 4;
+
+pub const TRACE_WIDTH: usize = 5;
+
+/// Number of memory accesses and offsets of a step; the filler accesses and offsets of the
+/// step follow them
+pub const STEP_MEM_WIDTH: usize = 4;
+pub const STEP_OFF_WIDTH: usize = 3;
+
+/// Rows of the registers and derived values in the cpu column of a step
+pub const AP_ROW: usize = 0;
+pub const FP_ROW: usize = 1;
+pub const RES_ROW: usize = 2;
+pub const T0_ROW: usize = 3;
+pub const T1_ROW: usize = 4;
+pub const MUL_ROW: usize = 5;
+
+/// Number of used rows in the cpu column of a step
+pub const CPU_HEIGHT: usize = 6;
+
+/// Names of the main trace columns, in layout order
+pub const TRACE_COLUMN_NAMES: [&str; TRACE_WIDTH] = ["flags", "offsets", "mem_a", "mem_v", "cpu"];
+
+/// Names of the cells of a step, by column and row. The rows that follow the named cells
+/// of a column hold filler values, or are unused in the cpu column.
+pub const STEP_CELL_NAMES: [&[&str]; TRACE_WIDTH] = [
+    &[
+        "f_dst_fp",
+        "f_op0_fp",
+        "f_op1_val",
+        "f_op1_fp",
+        "f_op1_ap",
+        "f_res_add",
+        "f_res_mul",
+        "f_pc_abs",
+        "f_pc_rel",
+        "f_pc_jnz",
+        "f_ap_add",
+        "f_ap_one",
+        "f_opc_call",
+        "f_opc_ret",
+        "f_opc_aeq",
+        "f15",
+    ],
+    &["off_dst", "off_op0", "off_op1"],
+    &["pc", "dst_addr", "op0_addr", "op1_addr"],
+    &["inst", "dst", "op0", "op1"],
+    &["ap", "fp", "res", "t0", "t1", "mul"],
+];
 
 // AUX TRACE LAYOUT (Memory)
 // -----------------------------------------------------------------------------------------
-//  A.  a_m_prime  (4) : Sorted memory address
-//  B.  v_m_prime  (4) : Sorted memory values
-//  C.  p_m        (4) : Permutation product (memory)
+//  A.  a_m_prime : Sorted memory address
+//  B.  v_m_prime : Sorted memory values
+//  C.  p_m       : Permutation product (memory)
 //
-//  A    B    C
-// ├xxxx|xxxx|xxxx┤
+//  A B C
+// ├x|x|x┤
 
 pub const A_M_PRIME_OFFSET: usize = 0;
-pub const A_M_PRIME_WIDTH: usize = 4;
+pub const V_M_PRIME_OFFSET: usize = 1;
+pub const P_M_OFFSET: usize = 2;
 
-pub const V_M_PRIME_OFFSET: usize = 4;
-pub const V_M_PRIME_WIDTH: usize = 4;
+pub const AUX_MEM_TRACE_WIDTH: usize = 3;
 
-pub const P_M_OFFSET: usize = 8;
-pub const P_M_WIDTH: usize = 4;
+/// Names of the memory aux segment columns, in layout order
+pub const AUX_MEM_COLUMN_NAMES: [&str; AUX_MEM_TRACE_WIDTH] = ["a_m_prime", "v_m_prime", "p_m"];
 
 // AUX TRACE LAYOUT (Range check)
 // -----------------------------------------------------------------------------------------
-//  D.  a_rc_prime (3) : Sorted offset values
-//  E.  p_rc       (3) : Permutation product (range check)
+//  D.  a_rc_prime : Sorted offset values
+//  E.  p_rc       : Permutation product (range check)
 //
-//  D   E
-// ├xxx|xxx┤
+//  D E
+// ├x|x┤
 //
 
-pub const A_RC_PRIME_OFFSET: usize = 12;
-pub const A_RC_PRIME_WIDTH: usize = 3;
+pub const A_RC_PRIME_OFFSET: usize = 3;
+pub const P_RC_OFFSET: usize = 4;
 
-pub const P_RC_OFFSET: usize = 15;
-pub const P_RC_WIDTH: usize = 3;
+pub const AUX_RC_TRACE_WIDTH: usize = 2;
 
-// Main column indices
+/// Names of the range check aux segment columns, in layout order
+pub const AUX_RC_COLUMN_NAMES: [&str; AUX_RC_TRACE_WIDTH] = ["a_rc_prime", "p_rc"];
 
-pub const AP: usize = MEM_P_TRACE_OFFSET;
+// MAIN TRACE LAYOUT (LogUp tables)
+// -----------------------------------------------------------------------------------------
+//  F.  mem_table_a : Memory addresses, in increasing order
+//  G.  mem_table_v : Memory values
+//  H.  mem_table_m : Number of accesses of each memory cell
+//  I.  rc_table    : Range check values, from rc_min to rc_max
+//  J.  rc_table_m  : Number of offsets equal to each range check value
+//
+// With the LogUp argument, the main trace is extended with tables of the memory cells and
+// of the range check values, followed by padding rows of zero multiplicity. Tables are not
+// read by steps, and hold one entry per row.
+
+pub const MEM_TABLE_A_OFFSET: usize = 5;
+pub const MEM_TABLE_V_OFFSET: usize = 6;
+pub const MEM_TABLE_M_OFFSET: usize = 7;
+pub const RC_TABLE_OFFSET: usize = 8;
+pub const RC_TABLE_M_OFFSET: usize = 9;
+
+pub const LOGUP_TRACE_WIDTH: usize = 10;
+
+/// Names of the LogUp table columns, in layout order
+pub const LOGUP_TABLE_COLUMN_NAMES: [&str; LOGUP_TRACE_WIDTH - TRACE_WIDTH] = [
+    "mem_table_a",
+    "mem_table_v",
+    "mem_table_m",
+    "rc_table",
+    "rc_table_m",
+];
+
+// AUX TRACE LAYOUT (LogUp)
+// -----------------------------------------------------------------------------------------
+//  A.  s_m  : Running sum of logarithmic derivatives (memory)
+//  B.  w_m  : Inverse of the memory access term of each row
+//  C.  s_rc : Running sum of logarithmic derivatives (range check)
+//  D.  w_rc : Inverse of the offset term of each row
+//
+//  A B   C D
+// ├x|x┤ ├x|x┤
+//
+// The running sums exclude the current row, and start from zero. The inverses keep the
+// degree of the constraints at two.
+
+pub const S_M_OFFSET: usize = 0;
+pub const W_M_OFFSET: usize = 1;
+pub const S_RC_OFFSET: usize = 2;
+pub const W_RC_OFFSET: usize = 3;
+
+pub const AUX_LOGUP_MEM_TRACE_WIDTH: usize = 2;
+pub const AUX_LOGUP_RC_TRACE_WIDTH: usize = 2;
+
+pub const AUX_LOGUP_MEM_COLUMN_NAMES: [&str; AUX_LOGUP_MEM_TRACE_WIDTH] = ["s_m", "w_m"];
+pub const AUX_LOGUP_RC_COLUMN_NAMES: [&str; AUX_LOGUP_RC_TRACE_WIDTH] = ["s_rc", "w_rc"];
+
+/// Argument proving memory consistency and the range checks of offsets
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AuxArgument {
+    /// Grand products of the memory accesses and offsets over sorted copies of them
+    #[default]
+    Permutation,
+    /// Sums of logarithmic derivatives of the memory accesses and offsets over tables of
+    /// memory cells and range check values, weighted by multiplicities (LogUp)
+    LogUp,
+}
+
+// Main column indices and rows of the registers, relative to the first row of a step
+
+pub const PC: (usize, usize) = (MEM_A_TRACE_OFFSET, 0);
+pub const AP: (usize, usize) = (CPU_TRACE_OFFSET, AP_ROW);
+pub const FP: (usize, usize) = (CPU_TRACE_OFFSET, FP_ROW);
 
 // Aux column indices
 
-pub const P_M_LAST: usize = P_M_OFFSET + P_M_WIDTH - 1;
+pub const P_M_LAST: usize = P_M_OFFSET;
 pub const A_RC_PRIME_FIRST: usize = A_RC_PRIME_OFFSET;
-pub const A_RC_PRIME_LAST: usize = A_RC_PRIME_OFFSET + 2;
+pub const A_RC_PRIME_LAST: usize = A_RC_PRIME_OFFSET;
 
 /// Returns a [Range] initialized with the specified `start` and with `end` set to `start` + `len`.
 pub const fn range(start: usize, len: usize) -> Range<usize> {
@@ -188,3 +293,123 @@ pub enum Builtin {
     Output(u64),
     RangeCheck,
 }
+
+/// A relocated memory segment, spanning addresses `begin_addr..stop_ptr`
+#[derive(Serialize, Deserialize, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct MemorySegment {
+    pub begin_addr: u64,
+    pub stop_ptr: u64,
+}
+
+impl MemorySegment {
+    pub fn new(begin_addr: u64, stop_ptr: u64) -> Self {
+        Self {
+            begin_addr,
+            stop_ptr,
+        }
+    }
+
+    /// Number of cells in the segment
+    pub fn size(&self) -> u64 {
+        self.stop_ptr - self.begin_addr
+    }
+
+    pub fn contains(&self, addr: u64) -> bool {
+        (self.begin_addr..self.stop_ptr).contains(&addr)
+    }
+}
+
+/// The memory segments of a run, as relocated by the runner
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SegmentTable {
+    pub program: MemorySegment,
+    pub execution: MemorySegment,
+    /// Builtin segments by builtin name, in the order of the program's builtin list
+    pub builtins: Vec<(String, MemorySegment)>,
+}
+
+impl SegmentTable {
+    /// Returns the segment of the given builtin (e.g. "output")
+    pub fn builtin(&self, name: &str) -> Option<MemorySegment> {
+        self.builtins
+            .iter()
+            .find(|(builtin, _)| builtin == name)
+            .map(|(_, segment)| *segment)
+    }
+
+    /// Iterates over all segments along with their names
+    pub fn iter(&self) -> impl Iterator<Item = (&str, MemorySegment)> {
+        [("program", self.program), ("execution", self.execution)]
+            .into_iter()
+            .chain(
+                self.builtins
+                    .iter()
+                    .map(|(name, segment)| (name.as_str(), *segment)),
+            )
+    }
+}
+
+impl Serializable for RegisterState {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write(self.pc);
+        target.write(self.ap);
+        target.write(self.fp);
+    }
+}
+
+impl Deserializable for RegisterState {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        Ok(Self::new(
+            Felt::read_from(source)?,
+            Felt::read_from(source)?,
+            Felt::read_from(source)?,
+        ))
+    }
+}
+
+impl Serializable for MemorySegment {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u64(self.begin_addr);
+        target.write_u64(self.stop_ptr);
+    }
+}
+
+impl Deserializable for MemorySegment {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        Ok(Self::new(source.read_u64()?, source.read_u64()?))
+    }
+}
+
+impl Serializable for SegmentTable {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        self.program.write_into(target);
+        self.execution.write_into(target);
+        target.write_u8(self.builtins.len() as u8);
+        for (name, segment) in self.builtins.iter() {
+            target.write_u8(name.len() as u8);
+            target.write_u8_slice(name.as_bytes());
+            segment.write_into(target);
+        }
+    }
+}
+
+impl Deserializable for SegmentTable {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let program = MemorySegment::read_from(source)?;
+        let execution = MemorySegment::read_from(source)?;
+        let num_builtins = source.read_u8()?;
+        let mut builtins = Vec::with_capacity(num_builtins as usize);
+        for _ in 0..num_builtins {
+            let len = source.read_u8()? as usize;
+            let name = String::from_utf8(source.read_u8_vec(len)?).map_err(|_| {
+                DeserializationError::InvalidValue("builtin name is not valid UTF-8".to_string())
+            })?;
+            builtins.push((name, MemorySegment::read_from(source)?));
+        }
+        Ok(Self {
+            program,
+            execution,
+            builtins,
+        })
+    }
+}
@@ -35,25 +35,30 @@ fn main() {
         Felt::from(0x48127ff67fff8000u64),
         Felt::from(0x1104800180018000u64),
         -Felt::from(17u64),
-        //Felt::from(0x208b7fff7fff7ffeu64),
-        Felt::from(0x10780017fff7fffu64), // infinite loop
+        Felt::from(0x10780017fff7fffu64), // end loop (jmp rel 0)
+        Felt::from(0u64),
     ];
     let mut mem = Memory::new(instrs);
-    mem.write_pub(Felt::from(21u32), Felt::from(41u32)); // beginning of output
-    mem.write_pub(Felt::from(22u32), Felt::from(44u32)); // end of output
-    mem.write_pub(Felt::from(23u32), Felt::from(44u32)); // end of program
+    mem.write_pub(Felt::from(22u32), Felt::from(42u32)); // beginning of output
+    mem.write_pub(Felt::from(23u32), Felt::from(25u32)); // return fp
+    mem.write_pub(Felt::from(24u32), Felt::from(20u32)); // return pc (end loop)
 
     // run the program to create an execution trace
-    let mut program = Program::new(&mut mem, 5, 24);
+    let mut program = Program::new(&mut mem, 5, 25);
     let trace = program.execute().unwrap();
 
     // generate the proof of execution
-    let proof_options = ProofOptions::with_proof_options(None, None, None, None, None);
+    let proof_options = ProofOptions::with_proof_options(None, None, None, None, None, None);
     let (proof, pub_inputs) = prover::prove_trace(trace, &proof_options).unwrap();
     let proof_bytes = proof.to_bytes();
     println!("Proof size: {:.1} KB", proof_bytes.len() as f64 / 1024f64);
 
-    // verify correct program execution
+    // verify correct program execution, once the trace is known to have the layout of the
+    // public inputs
+    if let Err(err) = pub_inputs.check_trace_info(&proof.get_trace_info()) {
+        println!("Failed to verify execution: {}", err);
+        return;
+    }
     match winterfell::verify::<ProcessorAir>(proof, pub_inputs) {
         Ok(_) => println!("Execution verified"),
         Err(err) => println!("Failed to verify execution: {}", err),
@@ -1,29 +1,82 @@
 use air::{ProcessorAir, PublicInputs};
-use giza_core::{Felt, RegisterState, MEM_A_TRACE_OFFSET, MEM_P_TRACE_OFFSET};
-use prover::{Prover, Trace};
+use giza_core::Felt;
+use prover::crypto::{hashers::Blake3_256, RandomCoin};
+use prover::{Air, Matrix, Prover, Trace};
 use runner::{ExecutionError, ExecutionTrace};
 
 // EXPORTS
 // ================================================================================================
 
-pub use air::{FieldExtension, HashFunction, ProofOptions};
+pub use air::{AuxArgument, ConstraintFailure, FieldExtension, HashFunction, ProofOptions};
 pub use prover::StarkProof;
 
 // EXECUTOR
 // ================================================================================================
 
 /// Proves an execution trace and returns the result together with a STARK-based proof
-/// of execution.
+/// of execution. If trace blinding is enabled, random rows are appended to the trace first.
+/// The trace is then extended with the tables of the selected argument, if any.
 pub fn prove_trace(
-    trace: ExecutionTrace,
+    mut trace: ExecutionTrace,
     options: &ProofOptions,
 ) -> Result<(StarkProof, PublicInputs), ExecutionError> {
+    trace.blind(options.num_blinding_rows());
+    trace.set_argument(options.argument())?;
     let prover = ExecutionProver::new(options.clone());
     let public_inputs = prover.get_pub_inputs(&trace);
     let proof = prover.prove(trace).map_err(ExecutionError::ProverError)?;
     Ok((proof, public_inputs))
 }
 
+// CONSTRAINT DEBUGGER
+// ================================================================================================
+
+/// Evaluates the transition constraints of the AIR at every row of an execution trace,
+/// prepared as for proving with the given options, and its boundary assertions against the
+/// public inputs the prover derives from the trace. Returns the ones that don't hold.
+/// The auxiliary segments are built with random elements drawn from a fixed seed, since the
+/// constraints of a valid trace hold for any of them.
+pub fn check_constraints(
+    trace: &ExecutionTrace,
+    options: &ProofOptions,
+) -> Result<Vec<ConstraintFailure>, ExecutionError> {
+    let mut trace = trace.clone();
+    trace.blind(options.num_blinding_rows());
+    trace.set_argument(options.argument())?;
+    let prover = ExecutionProver::new(options.clone());
+    let public_inputs = prover.get_pub_inputs(&trace);
+    let air = ProcessorAir::new(trace.get_info(), public_inputs, prover.options().clone());
+
+    let mut coin = RandomCoin::<Felt, Blake3_256<Felt>>::new(&[]);
+    let mut aux_segments: Vec<Matrix<Felt>> = vec![];
+    let mut aux_rand_elements = vec![];
+    for i in 0..trace.layout().num_aux_segments() {
+        let rand_elements = (0..trace.layout().get_aux_segment_rand_elements(i))
+            .map(|_| coin.draw().expect("failed to draw random element"))
+            .collect::<Vec<Felt>>();
+        let segment = trace
+            .build_aux_segment(&aux_segments, &rand_elements)
+            .expect("failed to build aux segment");
+        aux_segments.push(segment);
+        aux_rand_elements.push(rand_elements);
+    }
+
+    let main = matrix_columns(trace.main_segment());
+    let aux = aux_segments
+        .iter()
+        .flat_map(matrix_columns)
+        .collect::<Vec<_>>();
+    let mut failures = air.check_transitions(&main, &aux, aux_rand_elements.clone());
+    failures.extend(air.check_assertions(&main, &aux, aux_rand_elements));
+    Ok(failures)
+}
+
+fn matrix_columns(matrix: &Matrix<Felt>) -> Vec<&[Felt]> {
+    (0..matrix.num_cols())
+        .map(|i| matrix.get_column(i))
+        .collect()
+}
+
 // PROVER
 // ================================================================================================
 
@@ -47,15 +100,9 @@ impl Prover for ExecutionProver {
     }
 
     fn get_pub_inputs(&self, trace: &ExecutionTrace) -> PublicInputs {
-        let last_step = trace.num_steps - 1;
-
-        let pc_init = trace.main_segment().get(MEM_A_TRACE_OFFSET, 0);
-        let ap_init = trace.main_segment().get(MEM_P_TRACE_OFFSET, 0);
-        let init = RegisterState::new(pc_init, ap_init, ap_init);
-
-        let pc_fin = trace.main_segment().get(MEM_A_TRACE_OFFSET, last_step);
-        let ap_fin = trace.main_segment().get(MEM_P_TRACE_OFFSET, last_step);
-        let fin = RegisterState::new(pc_fin, ap_fin, ap_fin);
+        // The final register state is that of the end loop padding the trace
+        let init = trace.register_state(0);
+        let fin = trace.register_state(trace.last_step());
 
         let rc_min = trace.rc_min;
         let rc_max = trace.rc_max;
@@ -70,6 +117,129 @@ impl Prover for ExecutionProver {
             mem,
             trace.num_steps,
             trace.builtins.clone(),
+            trace.segments().cloned(),
         )
+        .with_trace_layout(trace.num_blinding_rows(), trace.argument())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use air::TraceLayoutError;
+    use prover::Serializable;
+    use runner::{Memory, Program};
+
+    /// Verifies a proof as the CLI does, checking the layout of the trace first
+    fn verify(proof: StarkProof, pub_inputs: PublicInputs) -> bool {
+        pub_inputs.check_trace_info(&proof.get_trace_info()).is_ok()
+            && verifier::verify::<ProcessorAir>(proof, pub_inputs).is_ok()
+    }
+
+    fn output_trace() -> ExecutionTrace {
+        // The output program of the examples, writing 10, 20 and 410 to the output
+        let instrs = vec![
+            Felt::from(0x400380007ffc7ffdu64),
+            Felt::from(0x482680017ffc8000u64),
+            Felt::from(1u64),
+            Felt::from(0x208b7fff7fff7ffeu64),
+            Felt::from(0x480680017fff8000u64),
+            Felt::from(10u64),
+            Felt::from(0x48307fff7fff8000u64),
+            Felt::from(0x48507fff7fff8000u64),
+            Felt::from(0x48307ffd7fff8000u64),
+            Felt::from(0x480a7ffd7fff8000u64),
+            Felt::from(0x48127ffb7fff8000u64),
+            Felt::from(0x1104800180018000u64),
+            -Felt::from(11u64),
+            Felt::from(0x48127ff87fff8000u64),
+            Felt::from(0x1104800180018000u64),
+            -Felt::from(14u64),
+            Felt::from(0x48127ff67fff8000u64),
+            Felt::from(0x1104800180018000u64),
+            -Felt::from(17u64),
+            Felt::from(0x10780017fff7fffu64),
+            Felt::from(0u64),
+        ];
+        let mut mem = Memory::new(instrs);
+        mem.write_pub(Felt::from(22u32), Felt::from(42u32));
+        mem.write_pub(Felt::from(23u32), Felt::from(25u32));
+        mem.write_pub(Felt::from(24u32), Felt::from(20u32));
+        let mut program = Program::new(&mut mem, 5, 25);
+        program.execute().unwrap()
+    }
+
+    #[test]
+    fn test_trace_blinding() {
+        let options = ProofOptions::with_proof_options(Some(8), None, Some(0), None, None, None);
+        let (proof, _) = prove_trace(output_trace(), &options).unwrap();
+        let trace_len = proof.trace_length();
+
+        // Without blinding, the openings of a proof are determined by the trace
+        let (other, _) = prove_trace(output_trace(), &options).unwrap();
+        assert_eq!(proof.to_bytes(), other.to_bytes());
+
+        // Blinding extends the trace with random rows, so proofs of the same execution open
+        // other values, while the public inputs are unchanged
+        let options = options.with_trace_blinding(true);
+        let (proof, pub_inputs) = prove_trace(output_trace(), &options).unwrap();
+        let (other, other_inputs) = prove_trace(output_trace(), &options).unwrap();
+        assert!(proof.trace_length() > trace_len);
+        assert_eq!(pub_inputs.to_bytes(), other_inputs.to_bytes());
+        assert_ne!(proof.to_bytes(), other.to_bytes());
+        assert!(verify(proof, pub_inputs));
+        assert!(verify(other, other_inputs));
+    }
+
+    #[test]
+    fn test_logup() {
+        let options = ProofOptions::with_proof_options(Some(8), None, Some(0), None, None, None)
+            .with_argument(AuxArgument::LogUp);
+        for options in [options.clone(), options.with_trace_blinding(true)] {
+            assert!(check_constraints(&output_trace(), &options)
+                .unwrap()
+                .is_empty());
+            let (proof, pub_inputs) = prove_trace(output_trace(), &options).unwrap();
+            assert!(verify(proof, pub_inputs));
+        }
+    }
+
+    #[test]
+    fn test_trace_layout() {
+        let options = ProofOptions::with_proof_options(Some(8), None, Some(0), None, None, None)
+            .with_trace_blinding(true);
+        let (proof, mut pub_inputs) = prove_trace(output_trace(), &options).unwrap();
+        let trace_info = proof.get_trace_info();
+        assert!(pub_inputs.check_trace_info(&trace_info).is_ok());
+
+        // The layout of the trace must be the one the public inputs commit to
+        let num_rows = pub_inputs.num_blinding_rows;
+        let layouts = [
+            (
+                0,
+                AuxArgument::Permutation,
+                TraceLayoutError::MetadataMismatch,
+            ),
+            (
+                num_rows,
+                AuxArgument::LogUp,
+                TraceLayoutError::MetadataMismatch,
+            ),
+            (
+                num_rows + 1,
+                AuxArgument::Permutation,
+                TraceLayoutError::InvalidBlindingRows(num_rows + 1),
+            ),
+            (
+                trace_info.length(),
+                AuxArgument::Permutation,
+                TraceLayoutError::InvalidBlindingRows(trace_info.length()),
+            ),
+        ];
+        for (num_rows, argument, err) in layouts {
+            pub_inputs.num_blinding_rows = num_rows;
+            pub_inputs.argument = argument;
+            assert_eq!(pub_inputs.check_trace_info(&trace_info), Err(err));
+        }
     }
 }
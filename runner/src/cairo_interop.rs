@@ -4,99 +4,640 @@
 /// - Cairo serializes field elements as 32 bytes (the program
 ///   prime is assumed to be equal to the 252-bit Starkware prime).
 ///
+use crate::errors::ReadError;
+#[cfg(feature = "hints")]
+use crate::hints::{Hint, HintManager, ProgramHints, PROGRAM_BASE};
 use crate::memory::Memory;
-use giza_core::{Builtin, Felt, RegisterState, Word};
-use serde::{Deserialize, Serialize};
-use std::fs::{metadata, File};
-use std::io::{BufReader, Read};
-use std::path::PathBuf;
+use crate::runner::END_LOOP;
+use air::PublicInputs;
+use giza_core::{
+    Builtin, Felt, FieldElement, FieldHelpers, MemorySegment, RegisterState, SegmentTable, Word,
+};
+use serde::{de, de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Serialize, Deserialize)]
 struct CompiledProgram {
     builtins: Vec<String>,
     data: Vec<String>,
     prime: String,
+    #[serde(default)]
+    identifiers: HashMap<String, serde_json::Value>,
+}
+
+/// Size in bytes of a register state in trace.bin (ap, fp and pc)
+const TRACE_RECORD_SIZE: usize = 24;
+
+/// Size in bytes of a memory cell in memory.bin (address and value)
+pub(crate) const MEMORY_RECORD_SIZE: usize = 40;
+
+/// Buffered reader over the fixed-size records of a cairo-runner output file. Records are
+/// streamed from disk, so that large files are never held in memory in their raw form.
+pub(crate) struct RecordReader<R: Read, const N: usize> {
+    path: PathBuf,
+    reader: R,
+    /// byte offset of the next record
+    offset: u64,
+    /// size of the file in bytes
+    len: u64,
+}
+
+impl<const N: usize> RecordReader<BufReader<File>, N> {
+    fn open(path: &Path) -> Result<Self, ReadError> {
+        let io_err = |source: io::Error| ReadError::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        let file = File::open(path).map_err(io_err)?;
+        let len = file.metadata().map_err(io_err)?.len();
+        Self::new(path, BufReader::with_capacity(1 << 20, file), len)
+    }
+}
+
+impl<R: Read, const N: usize> RecordReader<R, N> {
+    /// Creates a reader over `len` bytes of records. The path is only used to report
+    /// errors.
+    pub(crate) fn new(path: &Path, reader: R, len: u64) -> Result<Self, ReadError> {
+        if len % N as u64 != 0 {
+            return Err(ReadError::Misaligned {
+                path: path.to_path_buf(),
+                offset: len - len % N as u64,
+                record_size: N,
+            });
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader,
+            offset: 0,
+            len,
+        })
+    }
+
+    pub(crate) fn num_records(&self) -> usize {
+        (self.len / N as u64) as usize
+    }
+
+    /// Reads the next record, or returns None once all records have been read
+    pub(crate) fn next_record(&mut self) -> Result<Option<[u8; N]>, ReadError> {
+        if self.offset == self.len {
+            return Ok(None);
+        }
+        let mut record = [0u8; N];
+        self.reader
+            .read_exact(&mut record)
+            .map_err(|source| match source.kind() {
+                io::ErrorKind::UnexpectedEof => ReadError::Truncated {
+                    path: self.path.clone(),
+                    offset: self.offset,
+                },
+                _ => ReadError::Io {
+                    path: self.path.clone(),
+                    source,
+                },
+            })?;
+        self.offset += N as u64;
+        Ok(Some(record))
+    }
+}
+
+pub(crate) fn le_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes.try_into().unwrap())
 }
 
 /// Parses an execution trace outputted by the cairo-runner.
 /// e.g. cairo-runner --trace_file out/trace.bin
-pub fn read_trace_bin(path: &PathBuf) -> Vec<RegisterState> {
-    let mut f = File::open(&path).expect("no file found");
-    let metadata = metadata(&path).expect("unable to read metadata");
-    let length = metadata.len() as usize;
-
-    // Buffer for register values
-    let mut pc: [u8; 8] = Default::default();
-    let mut ap: [u8; 8] = Default::default();
-    let mut fp: [u8; 8] = Default::default();
-
-    let mut ptrs: Vec<RegisterState> = vec![];
-    let mut bytes_read = 0;
-    while bytes_read < length {
-        bytes_read += f.read(&mut ap).unwrap();
-        bytes_read += f.read(&mut fp).unwrap();
-        bytes_read += f.read(&mut pc).unwrap();
-        let reg = RegisterState::new(
-            u64::from_le_bytes(pc),
-            u64::from_le_bytes(ap),
-            u64::from_le_bytes(fp),
+pub fn read_trace_bin(path: &Path) -> Result<Vec<RegisterState>, ReadError> {
+    let mut reader = RecordReader::<_, TRACE_RECORD_SIZE>::open(path)?;
+    let mut ptrs = Vec::with_capacity(reader.num_records());
+    while let Some(record) = reader.next_record()? {
+        let (ap, fp, pc) = (
+            le_u64(&record[0..8]),
+            le_u64(&record[8..16]),
+            le_u64(&record[16..24]),
         );
-        ptrs.push(reg);
+        ptrs.push(RegisterState::new(pc, ap, fp));
     }
 
     //print_registers(&ptrs);
 
-    ptrs
+    Ok(ptrs)
 }
 
 /// Parses a memory dump outputted by the cairo-runner.
 /// e.g. cairo-runner --memory_file out/memory.bin
-pub fn read_memory_bin(mem_path: &PathBuf, program_path: &PathBuf) -> Memory {
-    // Read memory trace
-    let mut f = File::open(&mem_path).expect("Memory trace file not found");
-    let metadata = metadata(&mem_path).expect("Unable to read metadata");
-    let length = metadata.len() as usize;
-
-    // Buffer for memory accesses
-    let mut address: [u8; 8] = Default::default();
-    let mut value: [u8; 32] = Default::default();
-
-    let mut mem = Memory::new(vec![]).clone();
-    let mut bytes_read = 0;
-    while bytes_read < length {
-        bytes_read += f.read(&mut address).unwrap();
-        bytes_read += f.read(&mut value).unwrap();
-        mem.write(
-            Felt::try_from(u64::from_le_bytes(address)).unwrap(),
-            Felt::try_from(value).unwrap(),
-        );
-    }
+pub fn read_memory_bin(mem_path: &Path, program_path: &Path) -> Result<Memory, ReadError> {
+    let mut mem = read_memory_dump(mem_path)?;
 
     // Read compiled program and set memory codelen (the length of the public memory)
-    let file = File::open(&program_path).expect("Compiled program file not found");
-    let reader = BufReader::new(file);
-    let p: CompiledProgram = serde_json::from_reader(reader).unwrap();
-    mem.set_codelen(p.data.len());
+    let p = read_program(program_path)?;
+    mem.set_codelen(p.bytecode().len());
 
     //print_memory(&mem);
 
-    mem
+    Ok(mem)
 }
 
-pub fn read_builtins(program_path: &PathBuf, output_len: Option<u64>) -> Vec<Builtin> {
-    // Read compiled program and set memory codelen (the length of the public memory)
-    let file = File::open(&program_path).expect("Compiled program file not found");
-    let reader = BufReader::new(file);
-    let p: CompiledProgram = serde_json::from_reader(reader).unwrap();
+/// Parses the memory cells of a memory dump outputted by the cairo-runner
+pub fn read_memory_dump(mem_path: &Path) -> Result<Memory, ReadError> {
+    let mut reader = RecordReader::<_, MEMORY_RECORD_SIZE>::open(mem_path)?;
+    let mut mem = Memory::new(vec![]);
+    while let Some(record) = reader.next_record()? {
+        let address = le_u64(&record[0..8]);
+        let value: [u8; 32] = record[8..].try_into().unwrap();
+        mem.write(Felt::from(address), Felt::from(value));
+    }
+    Ok(mem)
+}
+
+/// Infers the memory segments of a cairo-runner execution. The runner relocates segments
+/// contiguously from address 1, in the order program, execution, builtins (followed by
+/// any segment allocated by hints). Main is called with the base pointers of the builtins
+/// on the stack, followed by the return fp and pc, and returns their stop pointers, so
+/// the builtin segments are read from the initial and final stack.
+fn infer_segments(
+    mem: &Memory,
+    program_len: u64,
+    builtins: &[String],
+    init: RegisterState,
+    fin: RegisterState,
+) -> Result<SegmentTable, String> {
+    let num_builtins = builtins.len() as u64;
+    let (init_ap, fin_ap) = (init.ap.to_u64(), fin.ap.to_u64());
+    let execution_base = init_ap
+        .checked_sub(num_builtins + 2)
+        .ok_or_else(|| format!("initial ap {} is below the initial stack", init_ap))?;
+    let read_ptr = |addr: u64| {
+        mem.read(Felt::from(addr))
+            .map(|ptr| ptr.to_u64())
+            .ok_or_else(|| format!("missing builtin pointer at address {}", addr))
+    };
+
+    let mut segments = SegmentTable {
+        program: MemorySegment::new(1, 1 + program_len),
+        execution: MemorySegment::new(execution_base, fin_ap),
+        builtins: vec![],
+    };
+    for (i, name) in builtins.iter().enumerate() {
+        let begin = read_ptr(execution_base + i as u64)?;
+        let stop = read_ptr(fin_ap - num_builtins + i as u64)?;
+        segments
+            .builtins
+            .push((name.clone(), MemorySegment::new(begin, stop)));
+    }
+    validate_segments(&segments, mem)?;
+    Ok(segments)
+}
+
+/// Infers the memory segments of a cairo-runner execution of the given program, from its
+/// memory dump and register trace
+pub(crate) fn read_segments(
+    program_path: &Path,
+    mem_path: &Path,
+    mem: &Memory,
+    registers: &[RegisterState],
+) -> Result<SegmentTable, ReadError> {
+    let p = read_program(program_path)?;
+    let invalid = |message| ReadError::InvalidSegments {
+        path: mem_path.to_path_buf(),
+        message,
+    };
+    let (init, fin) = match (registers.first(), registers.last()) {
+        (Some(init), Some(fin)) => (*init, *fin),
+        _ => return Err(invalid("the execution trace is empty".to_string())),
+    };
+    infer_segments(mem, p.bytecode().len() as u64, p.builtins(), init, fin).map_err(invalid)
+}
+
+/// Checks that the segments of a run are consistent with its memory: segments are ordered
+/// and disjoint, the program segment is fully assigned, and no memory cell lies between
+/// two segments.
+pub(crate) fn validate_segments(segments: &SegmentTable, mem: &Memory) -> Result<(), String> {
+    let all = segments.iter().collect::<Vec<_>>();
+    for (name, segment) in all.iter() {
+        if segment.stop_ptr < segment.begin_addr {
+            return Err(format!(
+                "{} segment stops at {} before its start {}",
+                name, segment.stop_ptr, segment.begin_addr
+            ));
+        }
+    }
+    for pair in all.windows(2) {
+        let ((prev_name, prev), (name, next)) = (pair[0], pair[1]);
+        if prev.stop_ptr > next.begin_addr {
+            return Err(format!(
+                "{} segment overlaps {} segment at address {}",
+                prev_name, name, next.begin_addr
+            ));
+        }
+        if let Some(addr) =
+            (prev.stop_ptr..next.begin_addr).find(|&addr| mem.read(Felt::from(addr)).is_some())
+        {
+            return Err(format!(
+                "memory cell {} lies between the {} and {} segments",
+                addr, prev_name, name
+            ));
+        }
+    }
+    let program = segments.program;
+    if let Some(addr) =
+        (program.begin_addr..program.stop_ptr).find(|&addr| mem.read(Felt::from(addr)).is_none())
+    {
+        return Err(format!(
+            "program segment is not contiguous at address {}",
+            addr
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, ReadError> {
+    let file = File::open(path).map_err(|source| ReadError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|source| ReadError::InvalidJson {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Parses a hex encoded field element (e.g. a word of program bytecode)
+pub(crate) fn parse_hex_felt(word: &str) -> Option<Felt> {
+    let mut bytes = [0u8; 32];
+    let word = hex::decode(format!("{:0>64}", word.trim_start_matches("0x"))).ok()?;
+    if word.len() != 32 {
+        return None;
+    }
+    for (b, w) in bytes.iter_mut().zip(word.iter().rev()) {
+        *b = *w;
+    }
+    Some(Felt::from(bytes))
+}
+
+/// Encodes a field element as a hex string without leading zeros, as the cairo-runner does
+pub(crate) fn felt_to_hex(value: Felt) -> String {
+    let hex = value.to_hex_le();
+    match hex.trim_start_matches('0') {
+        "" => "0x0".to_string(),
+        digits => format!("0x{}", digits),
+    }
+}
+
+fn serialize_hex_felt<S: Serializer>(value: &Felt, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&felt_to_hex(*value))
+}
+
+fn deserialize_hex_felt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Felt, D::Error> {
+    let word = String::deserialize(deserializer)?;
+    parse_hex_felt(&word)
+        .ok_or_else(|| de::Error::custom(format!("invalid field element {}", word)))
+}
+
+// AIR INPUTS
+// ================================================================================================
+
+/// Public memory cell, as reported by the cairo-runner
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PublicMemoryEntry {
+    pub address: u64,
+    #[serde(
+        serialize_with = "serialize_hex_felt",
+        deserialize_with = "deserialize_hex_felt"
+    )]
+    pub value: Felt,
+    pub page: u64,
+}
+
+/// AIR public input outputted by the cairo-runner.
+/// e.g. cairo-runner --air_public_input out/air_public_input.json
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AirPublicInput {
+    pub layout: String,
+    pub rc_min: u64,
+    pub rc_max: u64,
+    pub n_steps: u64,
+    pub memory_segments: BTreeMap<String, MemorySegment>,
+    pub public_memory: Vec<PublicMemoryEntry>,
+    #[serde(default)]
+    pub dynamic_params: Option<serde_json::Value>,
+}
+
+impl AirPublicInput {
+    /// Returns the builtins used by the program. The length of the output builtin is
+    /// given by the bounds of the output segment.
+    pub fn builtins(&self) -> Vec<Builtin> {
+        match self.memory_segments.get("output") {
+            Some(output) => vec![Builtin::Output(output.stop_ptr - output.begin_addr)],
+            None => vec![],
+        }
+    }
+
+    /// Returns the public memory cells (addresses and values). Memory pages are not
+    /// supported, so the cells of all pages are combined.
+    pub fn public_mem(&self) -> (Vec<u64>, Vec<Option<Word>>) {
+        self.public_memory
+            .iter()
+            .map(|cell| (cell.address, Some(Word::new(cell.value))))
+            .unzip()
+    }
+
+    /// Returns the segment table of the run, with the segments other than program and
+    /// execution taken as builtin segments in address order
+    pub fn segments(&self) -> Option<SegmentTable> {
+        let mut builtins = self
+            .memory_segments
+            .iter()
+            .filter(|(name, _)| !matches!(name.as_str(), "program" | "execution"))
+            .map(|(name, segment)| (name.clone(), *segment))
+            .collect::<Vec<_>>();
+        builtins.sort_by_key(|(_, segment)| segment.begin_addr);
+        Some(SegmentTable {
+            program: *self.memory_segments.get("program")?,
+            execution: *self.memory_segments.get("execution")?,
+            builtins,
+        })
+    }
+}
+
+/// Exports the public inputs of a proof in the format of the cairo-runner, so that they
+/// can be inspected with Starkware tooling. Segments are taken from the segment table of
+/// the public inputs. Proofs without a segment table have their segments recovered as
+/// follows:
+/// - the program segment is the first run of consecutive public memory addresses
+/// - the output segment is the last run of consecutive public memory addresses, if the
+///   output builtin is used
+/// - the execution segment spans from the initial to the final ap
+///
+/// The layout is the smallest Starkware layout that supports the builtins used. All
+/// public memory is placed in page 0.
+impl From<&PublicInputs> for AirPublicInput {
+    fn from(inputs: &PublicInputs) -> Self {
+        let (addrs, values) = &inputs.mem;
+        let mut runs: Vec<MemorySegment> = vec![];
+        for &addr in addrs.iter() {
+            match runs.last_mut() {
+                Some(run) if run.stop_ptr == addr => run.stop_ptr += 1,
+                _ => runs.push(MemorySegment::new(addr, addr + 1)),
+            }
+        }
+
+        let has_output = inputs
+            .builtins
+            .iter()
+            .any(|builtin| matches!(builtin, Builtin::Output(_)));
+        let mut memory_segments = BTreeMap::new();
+        if let Some(segments) = &inputs.segments {
+            for (name, segment) in segments.iter() {
+                memory_segments.insert(name.to_string(), segment);
+            }
+        } else {
+            if let Some(program) = runs.first() {
+                memory_segments.insert("program".to_string(), *program);
+            }
+            memory_segments.insert(
+                "execution".to_string(),
+                MemorySegment::new(inputs.init.ap.to_u64(), inputs.fin.ap.to_u64()),
+            );
+            if let (true, [_, .., output]) = (has_output, &runs[..]) {
+                memory_segments.insert("output".to_string(), *output);
+            }
+        }
+
+        let public_memory = addrs
+            .iter()
+            .zip(values.iter())
+            .map(|(&address, value)| PublicMemoryEntry {
+                address,
+                value: value.map_or(Felt::ZERO, |word| word.word()),
+                page: 0,
+            })
+            .collect();
+
+        Self {
+            layout: if has_output { "small" } else { "plain" }.to_string(),
+            rc_min: inputs.rc_min as u64,
+            rc_max: inputs.rc_max as u64,
+            n_steps: inputs.num_steps as u64,
+            memory_segments,
+            public_memory,
+            dynamic_params: None,
+        }
+    }
+}
+
+/// Writes the AIR public input in the JSON format of the cairo-runner
+pub fn write_air_public_input(path: &Path, input: &AirPublicInput) -> io::Result<()> {
+    let mut f = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut f, input)?;
+    f.flush()
+}
+
+/// AIR private input outputted by the cairo-runner.
+/// e.g. cairo-runner --air_private_input out/air_private_input.json
+///
+/// Only the paths of the trace and memory files are read. The inputs of builtins other
+/// than output are ignored, as these builtins are not yet supported.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AirPrivateInput {
+    pub trace_path: PathBuf,
+    pub memory_path: PathBuf,
+}
+
+/// Reads the AIR public input outputted by the cairo-runner
+pub fn read_air_public_input(path: &Path) -> Result<AirPublicInput, ReadError> {
+    read_json(path)
+}
+
+/// Reads the AIR private input outputted by the cairo-runner. Relative trace and memory
+/// paths are resolved against the directory of the private input file.
+pub fn read_air_private_input(path: &Path) -> Result<AirPrivateInput, ReadError> {
+    let mut input: AirPrivateInput = read_json(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    input.trace_path = dir.join(&input.trace_path);
+    input.memory_path = dir.join(&input.memory_path);
+    Ok(input)
+}
+
+// PROGRAMS
+// ================================================================================================
+
+/// Cairo 1 program compiled to CASM (e.g. with starknet-sierra-compile)
+#[derive(Deserialize)]
+pub struct CasmProgram {
+    pub prime: String,
+    #[serde(default)]
+    pub compiler_version: Option<String>,
+    pub bytecode: Vec<String>,
+    /// Hints attached to each bytecode offset, in their JSON encoding
+    #[serde(default)]
+    pub hints: Vec<(u64, Vec<serde_json::Value>)>,
+    #[serde(default)]
+    pub entry_points_by_type: EntryPoints,
+}
+
+#[derive(Default, Deserialize)]
+pub struct EntryPoints {
+    #[serde(rename = "EXTERNAL", default)]
+    pub external: Vec<EntryPoint>,
+    #[serde(rename = "L1_HANDLER", default)]
+    pub l1_handler: Vec<EntryPoint>,
+    #[serde(rename = "CONSTRUCTOR", default)]
+    pub constructor: Vec<EntryPoint>,
+}
+
+#[derive(Deserialize)]
+pub struct EntryPoint {
+    pub selector: String,
+    pub offset: u64,
+    #[serde(default)]
+    pub builtins: Vec<String>,
+}
+
+impl CasmProgram {
+    /// The entry point that is run natively and whose builtins are those of the program:
+    /// the first external entry point, if the program has any
+    pub fn main_entry_point(&self) -> Option<&EntryPoint> {
+        self.entry_points_by_type.external.first()
+    }
+}
+
+/// A compiled program, in either of the formats giza can load
+enum ProgramFile {
+    Cairo0(CompiledProgram),
+    Casm(CasmProgram),
+}
+
+/// CASM files are told apart from Cairo 0 programs by their bytecode field
+fn is_casm(program: &serde_json::Value) -> bool {
+    program.get("bytecode").is_some()
+}
+
+fn read_program(path: &Path) -> Result<ProgramFile, ReadError> {
+    let program: serde_json::Value = read_json(path)?;
+    let program = if is_casm(&program) {
+        serde_json::from_value(program).map(ProgramFile::Casm)
+    } else {
+        serde_json::from_value(program).map(ProgramFile::Cairo0)
+    };
+    program.map_err(|source| ReadError::InvalidJson {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+impl ProgramFile {
+    fn bytecode(&self) -> &[String] {
+        match self {
+            ProgramFile::Cairo0(p) => &p.data,
+            ProgramFile::Casm(p) => &p.bytecode,
+        }
+    }
+
+    fn builtins(&self) -> &[String] {
+        match self {
+            ProgramFile::Cairo0(p) => &p.builtins,
+            ProgramFile::Casm(p) => p
+                .main_entry_point()
+                .map_or(&[][..], |entry| &entry.builtins[..]),
+        }
+    }
+
+    /// Offset of the function run natively
+    fn main(&self) -> Option<u64> {
+        match self {
+            ProgramFile::Cairo0(p) => p
+                .identifiers
+                .get("__main__.main")
+                .and_then(|main| main["pc"].as_u64()),
+            ProgramFile::Casm(p) => Some(p.main_entry_point().map_or(0, |entry| entry.offset)),
+        }
+    }
+}
+
+/// Loads a compiled program into memory for a native run of its main function. The
+/// bytecode is followed by an end loop, which main returns to: the initial stack holds
+/// the return frame pointer (the initial frame pointer) and return address (the end loop)
+/// of main. Returns the memory along with the initial pc and ap.
+///
+/// Cairo 1 CASM files are run from their first external entry point, or from the start of
+/// the bytecode if they have none.
+pub fn load_program(program_path: &PathBuf) -> Result<(Memory, u64, u64), ReadError> {
+    let p = read_program(program_path)?;
+    let invalid = |message| ReadError::InvalidProgram {
+        path: program_path.to_path_buf(),
+        message,
+    };
+    if !p.builtins().is_empty() {
+        return Err(invalid(format!(
+            "builtins are not yet supported in native runs (uses {})",
+            p.builtins().join(", ")
+        )));
+    }
+
+    let data = p
+        .bytecode()
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            parse_hex_felt(word)
+                .ok_or_else(|| invalid(format!("invalid bytecode word {} at offset {}", word, i)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let main = p
+        .main()
+        .ok_or_else(|| invalid("the program has no main function".to_string()))?;
+    let end_loop = 1 + data.len() as u64;
+
+    let mut mem = Memory::new(data);
+    for word in END_LOOP {
+        mem.write_pub(Felt::from(mem.size()), Felt::from(word));
+    }
+    let execution_base = mem.size();
+    mem.write_pub(Felt::from(execution_base), Felt::from(execution_base + 2));
+    mem.write_pub(Felt::from(execution_base + 1), Felt::from(end_loop));
+    Ok((mem, 1 + main, execution_base + 2))
+}
+
+pub fn read_builtins(
+    program_path: &Path,
+    output_len: Option<u64>,
+) -> Result<Vec<Builtin>, ReadError> {
+    let p = read_program(program_path)?;
     let builtins = p
-        .builtins
+        .builtins()
         .iter()
         .filter_map(|b| match b.as_str() {
             "output" => Some(Builtin::Output(output_len.unwrap())),
             _ => None,
         })
         .collect::<Vec<_>>();
-    builtins
+    Ok(builtins)
+}
+
+/// Reads the hints of a compiled program, along with the reference data needed to
+/// resolve their `ids`. The structured hints of CASM files need no reference data.
+#[cfg(feature = "hints")]
+pub fn read_hints(program_path: &PathBuf) -> Result<HintManager, ReadError> {
+    let p: serde_json::Value = read_json(program_path)?;
+    let invalid_json = |source| ReadError::InvalidJson {
+        path: program_path.to_path_buf(),
+        source,
+    };
+    if !is_casm(&p) {
+        let p: ProgramHints = serde_json::from_value(p).map_err(invalid_json)?;
+        return Ok(HintManager::from(p));
+    }
+    let p: CasmProgram = serde_json::from_value(p).map_err(invalid_json)?;
+    let mut manager = HintManager::default();
+    for (offset, hints) in &p.hints {
+        for hint in hints {
+            manager.push_hint(PROGRAM_BASE + offset, Hint::from_casm(hint));
+        }
+    }
+    Ok(manager)
 }
 
 fn print_registers(reg: &[RegisterState]) {
@@ -118,19 +659,282 @@ fn print_memory(mem: &Memory) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runner::is_end_loop;
+    use giza_core::{flags::*, FlagGroupDecomposition, OffsetDecomposition};
+    use std::io::Write;
+    use winterfell::{Deserializable, Serializable, SliceReader};
+
+    fn write_tmp(name: &str, bytes: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        File::create(&path).unwrap().write_all(bytes).unwrap();
+        path
+    }
 
     #[test]
     fn test_trace_bin() {
-        let trace = read_trace_bin(&PathBuf::from("../tmp/trace.bin"));
-        println!("{:?}", trace);
+        let mut bytes = vec![];
+        for (ap, fp, pc) in [(30u64, 28u64, 5u64), (31, 28, 7)] {
+            bytes.extend(ap.to_le_bytes());
+            bytes.extend(fp.to_le_bytes());
+            bytes.extend(pc.to_le_bytes());
+        }
+        let path = write_tmp("giza_test_trace.bin", &bytes);
+        let trace = read_trace_bin(&path).unwrap();
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[1].pc, Felt::from(7u64));
+        assert_eq!(trace[1].ap, Felt::from(31u64));
+        assert_eq!(trace[1].fp, Felt::from(28u64));
+
+        // A partial record at the end of the file is reported with its offset
+        let path = write_tmp("giza_test_trace_misaligned.bin", &bytes[..40]);
+        assert!(matches!(
+            read_trace_bin(&path),
+            Err(ReadError::Misaligned { offset: 24, .. })
+        ));
     }
 
     #[test]
     fn test_memory_bin() {
-        let mem = read_memory_bin(
-            &PathBuf::from("../tmp/memory.bin"),
-            &PathBuf::from("../tmp/program.json"),
+        let mut bytes = vec![];
+        for (address, value) in [(1u64, 5u8), (3, 7)] {
+            bytes.extend(address.to_le_bytes());
+            let mut felt = [0u8; 32];
+            felt[0] = value;
+            bytes.extend(felt);
+        }
+        let mem_path = write_tmp("giza_test_memory.bin", &bytes);
+        let program_path = write_tmp(
+            "giza_test_program.json",
+            br#"{"builtins": [], "data": ["0x5"], "prime": "0x0"}"#,
+        );
+        let mem = read_memory_bin(&mem_path, &program_path).unwrap();
+        assert_eq!(mem.read(Felt::from(1u64)), Some(Felt::from(5u64)));
+        assert_eq!(mem.read(Felt::from(2u64)), None);
+        assert_eq!(mem.read(Felt::from(3u64)), Some(Felt::from(7u64)));
+
+        let mem_path = write_tmp("giza_test_memory_misaligned.bin", &bytes[..60]);
+        assert!(matches!(
+            read_memory_bin(&mem_path, &program_path),
+            Err(ReadError::Misaligned { offset: 40, .. })
+        ));
+        assert!(matches!(
+            read_memory_bin(&PathBuf::from("missing.bin"), &program_path),
+            Err(ReadError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_air_inputs() {
+        let public_path = write_tmp(
+            "giza_test_air_public_input.json",
+            br#"{
+                "layout": "plain",
+                "rc_min": 32762,
+                "rc_max": 32769,
+                "n_steps": 8,
+                "memory_segments": {
+                    "program": {"begin_addr": 1, "stop_ptr": 3},
+                    "execution": {"begin_addr": 4, "stop_ptr": 12},
+                    "output": {"begin_addr": 12, "stop_ptr": 13}
+                },
+                "public_memory": [
+                    {"address": 1, "value": "0x40780017fff7fff", "page": 0},
+                    {"address": 2, "value": "0x1", "page": 0},
+                    {"address": 12, "value": "0x5", "page": 1}
+                ],
+                "dynamic_params": null
+            }"#,
+        );
+        let public_input = read_air_public_input(&public_path).unwrap();
+        assert_eq!(public_input.n_steps, 8);
+        assert!(matches!(public_input.builtins()[..], [Builtin::Output(1)]));
+        let (addrs, values) = public_input.public_mem();
+        assert_eq!(addrs, vec![1, 2, 12]);
+        assert_eq!(values[2].unwrap().word(), Felt::from(5u64));
+
+        let private_path = write_tmp(
+            "giza_test_air_private_input.json",
+            br#"{"trace_path": "trace.bin", "memory_path": "/out/memory.bin", "pedersen": []}"#,
         );
-        println!("{:?}", mem.data);
+        let private_input = read_air_private_input(&private_path).unwrap();
+        assert_eq!(
+            private_input.trace_path,
+            std::env::temp_dir().join("trace.bin")
+        );
+        assert_eq!(private_input.memory_path, PathBuf::from("/out/memory.bin"));
+    }
+
+    #[test]
+    fn test_export_air_public_input() {
+        let (addrs, values): (Vec<u64>, Vec<Option<Word>>) =
+            [(1u64, 0x40780017fff7fffu64), (2, 1), (3, 0), (20, 5)]
+                .iter()
+                .map(|&(addr, value)| (addr, Some(Word::new(Felt::from(value)))))
+                .unzip();
+        let inputs = PublicInputs::new(
+            RegisterState::new(1u64, 6u64, 6u64),
+            RegisterState::new(5u64, 20u64, 18u64),
+            32762,
+            32769,
+            (addrs.clone(), values),
+            8,
+            vec![Builtin::Output(1)],
+            None,
+        );
+        let exported = AirPublicInput::from(&inputs);
+        assert_eq!(exported.layout, "small");
+        assert_eq!(exported.memory_segments["program"].stop_ptr, 4);
+        assert_eq!(exported.memory_segments["execution"].begin_addr, 6);
+        assert_eq!(exported.memory_segments["output"].begin_addr, 20);
+
+        // The exported JSON can be read back by the importer
+        let path = std::env::temp_dir().join("giza_test_exported_public_input.json");
+        write_air_public_input(&path, &exported).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(json.contains(r#""value": "0x40780017fff7fff""#));
+        assert!(json.contains(r#""value": "0x0""#));
+        let imported = read_air_public_input(&path).unwrap();
+        assert_eq!(imported.public_mem().0, addrs);
+        assert_eq!(imported.public_memory[3].value, Felt::from(5u64));
+        assert!(matches!(imported.builtins()[..], [Builtin::Output(1)]));
+
+        // The full register state survives serialization
+        let bytes = inputs.to_bytes();
+        let decoded = PublicInputs::read_from(&mut SliceReader::new(&bytes)).unwrap();
+        assert_eq!(decoded.fin.fp, Felt::from(18u64));
+        assert_eq!(decoded.init.fp, inputs.init.fp);
+    }
+
+    #[test]
+    fn test_infer_segments() {
+        // Program [1, 4), execution [4, 9) with the output base, return fp and return pc
+        // on the initial stack, and the output stop pointer returned by main
+        let mut mem = Memory::new(vec![Felt::ONE; 3]);
+        for (addr, value) in [(4u64, 9u64), (5, 0), (6, 0), (7, 3), (8, 10), (9, 42)] {
+            mem.write(Felt::from(addr), Felt::from(value));
+        }
+        let init = RegisterState::new(1u64, 7u64, 7u64);
+        let fin = RegisterState::new(5u64, 9u64, 7u64);
+        let builtins = vec!["output".to_string()];
+        let segments = infer_segments(&mem, 3, &builtins, init, fin).unwrap();
+        assert_eq!(segments.program, MemorySegment::new(1, 4));
+        assert_eq!(segments.execution, MemorySegment::new(4, 9));
+        assert_eq!(segments.builtin("output"), Some(MemorySegment::new(9, 10)));
+
+        // A cell assigned outside of every segment is rejected
+        mem.write(Felt::from(4u64), Felt::from(10u64));
+        mem.write(Felt::from(8u64), Felt::from(11u64));
+        mem.write(Felt::from(10u64), Felt::from(42u64));
+        let err = infer_segments(&mem, 3, &builtins, init, fin).unwrap_err();
+        assert!(err.contains("memory cell 9 lies between"));
+    }
+
+    #[test]
+    fn test_casm_program() {
+        let path = write_tmp(
+            "giza_test_casm.json",
+            br#"{
+                "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+                "compiler_version": "2.0.0",
+                "bytecode": ["0x40780017fff7fff", "0x1", "0x208b7fff7fff7ffe"],
+                "hints": [[2, [{"AllocSegment": {"dst": {"register": "AP", "offset": 0}}}]]],
+                "entry_points_by_type": {
+                    "EXTERNAL": [{"selector": "0x1", "offset": 2, "builtins": []}],
+                    "L1_HANDLER": [],
+                    "CONSTRUCTOR": []
+                }
+            }"#,
+        );
+        let (mem, pc, ap) = load_program(&path).unwrap();
+        assert_eq!(pc, 3);
+        assert_eq!(ap, 8);
+        assert_eq!(mem.read(Felt::from(2u64)), Some(Felt::ONE));
+        assert!(is_end_loop(&mem, Felt::from(4u64)));
+        assert_eq!(mem.read(Felt::from(6u64)), Some(Felt::from(8u64)));
+        assert_eq!(mem.read(Felt::from(7u64)), Some(Felt::from(4u64)));
+        assert!(read_builtins(&path, None).unwrap().is_empty());
+
+        #[cfg(feature = "hints")]
+        assert_eq!(
+            read_hints(&path)
+                .unwrap()
+                .get_hints(Felt::from(3u64))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let mem_path = write_tmp("giza_test_casm_memory.bin", &[]);
+        let mem = read_memory_bin(&mem_path, &path).unwrap();
+        assert_eq!(mem.get_codelen(), 3);
+    }
+
+    #[test]
+    fn test_casm_immediates() {
+        // [ap] = 7, ap++; [ap] = -1, ap++; ret
+        let path = write_tmp(
+            "giza_test_casm_immediates.json",
+            br#"{
+                "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+                "bytecode": [
+                    "0x480680017fff8000",
+                    "0x7",
+                    "0x480680017fff8000",
+                    "0x800000000000011000000000000000000000000000000000000000000000000",
+                    "0x208b7fff7fff7ffe"
+                ],
+                "hints": []
+            }"#,
+        );
+        let (mem, pc, _) = load_program(&path).unwrap();
+        assert_eq!(pc, 1);
+        for (pc, imm) in [(1u64, Felt::from(7u64)), (3, -Felt::ONE)] {
+            let inst = Word::new(mem.read(Felt::from(pc)).unwrap());
+            assert_eq!(inst.opcode(), OPC_AEQ);
+            assert_eq!(inst.op1_src(), OP1_VAL);
+            assert_eq!(inst.ap_up(), AP_ONE);
+            assert_eq!(inst.off_dst(), Felt::ZERO);
+            assert_eq!(inst.off_op1(), Felt::ONE);
+            assert_eq!(mem.read(Felt::from(pc + 1)), Some(imm));
+        }
+        let ret = Word::new(mem.read(Felt::from(5u64)).unwrap());
+        assert_eq!(ret.opcode(), OPC_RET);
+        assert_eq!(ret.pc_up(), PC_ABS);
+    }
+
+    #[test]
+    fn test_invalid_program() {
+        let path = write_tmp(
+            "giza_test_casm_invalid_word.json",
+            br#"{"prime": "0x0", "bytecode": ["0x480680017fff8000", "0xzz"]}"#,
+        );
+        match load_program(&path) {
+            Err(ReadError::InvalidProgram { message, .. }) => {
+                assert!(message.contains("0xzz at offset 1"))
+            }
+            _ => panic!("expected an invalid program"),
+        }
+
+        let path = write_tmp(
+            "giza_test_casm_builtins.json",
+            br#"{
+                "prime": "0x0",
+                "bytecode": ["0x208b7fff7fff7ffe"],
+                "entry_points_by_type": {
+                    "EXTERNAL": [{"selector": "0x1", "offset": 0, "builtins": ["range_check"]}]
+                }
+            }"#,
+        );
+        assert!(matches!(
+            load_program(&path),
+            Err(ReadError::InvalidProgram { .. })
+        ));
+
+        // Neither a Cairo 0 program nor a CASM file
+        let path = write_tmp("giza_test_not_a_program.json", br#"{"data": 1}"#);
+        assert!(matches!(
+            load_program(&path),
+            Err(ReadError::InvalidJson { .. })
+        ));
     }
 }
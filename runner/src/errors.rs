@@ -1,6 +1,101 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
 use winterfell::ProverError;
 
+#[cfg(feature = "hints")]
+use crate::hints::HintError;
+
 #[derive(Debug)]
 pub enum ExecutionError {
     ProverError(ProverError),
+    /// The recorded hint effects of a step do not match the replayed run
+    TapeMismatch(usize),
+    /// The execution does not run into an end loop (`jmp rel 0`) after its last step, at
+    /// the given pc, so its trace can't be padded
+    MissingEndLoop(u64),
+    /// The LogUp tables can't be built from the trace, for the given reason (e.g. a memory
+    /// access out of memory)
+    InvalidTables(String),
+    #[cfg(feature = "hints")]
+    HintError(HintError),
+}
+
+/// Error encountered while reading the outputs of the cairo-runner
+#[derive(Debug)]
+pub enum ReadError {
+    /// The file could not be opened or read
+    Io { path: PathBuf, source: io::Error },
+    /// The file size is not a multiple of the record size. The offset is the start of the
+    /// incomplete record at the end of the file.
+    Misaligned {
+        path: PathBuf,
+        offset: u64,
+        record_size: usize,
+    },
+    /// The file ended in the middle of the record starting at the given offset
+    Truncated { path: PathBuf, offset: u64 },
+    /// A Cairo PIE archive is malformed
+    InvalidPie { path: PathBuf, message: String },
+    /// The segments of a memory dump are inconsistent with its contents
+    InvalidSegments { path: PathBuf, message: String },
+    /// The execution does not run into an end loop (`jmp rel 0`) after its last step, at
+    /// the given pc
+    MissingEndLoop { path: PathBuf, pc: u64 },
+    /// A compiled program can't be loaded for a native run
+    InvalidProgram { path: PathBuf, message: String },
+    /// A JSON input (e.g. the compiled program) could not be parsed
+    InvalidJson {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
 }
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Self::Misaligned {
+                path,
+                offset,
+                record_size,
+            } => write!(
+                f,
+                "{}: incomplete record at byte {} (records are {} bytes)",
+                path.display(),
+                offset,
+                record_size
+            ),
+            Self::Truncated { path, offset } => write!(
+                f,
+                "{}: file ends in the middle of the record at byte {}",
+                path.display(),
+                offset
+            ),
+            Self::InvalidPie { path, message } => {
+                write!(f, "{}: invalid Cairo PIE: {}", path.display(), message)
+            }
+            Self::InvalidSegments { path, message } => {
+                write!(
+                    f,
+                    "{}: invalid memory segments: {}",
+                    path.display(),
+                    message
+                )
+            }
+            Self::MissingEndLoop { path, pc } => write!(
+                f,
+                "{}: the execution does not end in an end loop (jmp rel 0) at pc {}; \
+                 run it in proof mode",
+                path.display(),
+                pc
+            ),
+            Self::InvalidProgram { path, message } => {
+                write!(f, "{}: invalid program: {}", path.display(), message)
+            }
+            Self::InvalidJson { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
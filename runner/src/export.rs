@@ -0,0 +1,273 @@
+//! Export of execution traces to CSV or Parquet, so that the columns of a trace can be
+//! inspected with standard tools when debugging a proof. Every exported row is a step of
+//! the execution, with the cells of the step named after the trace layout in `giza_core`,
+//! and field elements are written as hex strings.
+
+use crate::cairo_interop::felt_to_hex;
+use crate::trace::ExecutionTrace;
+use giza_core::{
+    AuxArgument, Felt, AUX_LOGUP_MEM_COLUMN_NAMES, AUX_LOGUP_RC_COLUMN_NAMES, AUX_MEM_COLUMN_NAMES,
+    AUX_RC_COLUMN_NAMES, LOGUP_TABLE_COLUMN_NAMES, STEP_CELL_NAMES, STEP_HEIGHT,
+    TRACE_COLUMN_NAMES,
+};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use winterfell::crypto::{hashers::Blake3_256, RandomCoin};
+use winterfell::{Matrix, Trace};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    Csv,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+/// Named columns of an execution trace
+pub struct TraceTable {
+    pub names: Vec<String>,
+    pub columns: Vec<Vec<Felt>>,
+}
+
+impl ExecutionTrace {
+    /// Collects the cells of the main segment, step by step. When a seed is given, the
+    /// columns of both auxiliary segments of the trace's argument are appended, built with
+    /// random elements drawn from the seed (the elements used by the prover depend on the
+    /// trace commitment, so aux values only match those of a proof in their structure).
+    pub fn to_table(&mut self, aux_seed: Option<u64>) -> TraceTable {
+        let (main_names, aux_names) = match self.argument() {
+            AuxArgument::Permutation => (
+                TRACE_COLUMN_NAMES.to_vec(),
+                [&AUX_MEM_COLUMN_NAMES[..], &AUX_RC_COLUMN_NAMES[..]],
+            ),
+            AuxArgument::LogUp => (
+                [&TRACE_COLUMN_NAMES[..], &LOGUP_TABLE_COLUMN_NAMES[..]].concat(),
+                [
+                    &AUX_LOGUP_MEM_COLUMN_NAMES[..],
+                    &AUX_LOGUP_RC_COLUMN_NAMES[..],
+                ],
+            ),
+        };
+        let mut table = TraceTable {
+            names: vec![],
+            columns: vec![],
+        };
+        table.extend(&main_names, &STEP_CELL_NAMES, self.main_segment());
+
+        if let Some(seed) = aux_seed {
+            let mut coin = RandomCoin::<Felt, Blake3_256<Felt>>::new(&seed.to_le_bytes());
+            let mut aux_segments: Vec<Matrix<Felt>> = vec![];
+            for (i, segment_names) in aux_names.iter().enumerate() {
+                let rand_elements = (0..self.layout().get_aux_segment_rand_elements(i))
+                    .map(|_| coin.draw().expect("failed to draw random element"))
+                    .collect::<Vec<Felt>>();
+                let segment = self
+                    .build_aux_segment(&aux_segments, &rand_elements)
+                    .expect("failed to build aux segment");
+                table.extend(segment_names, &[], &segment);
+                aux_segments.push(segment);
+            }
+        }
+
+        table
+    }
+}
+
+impl TraceTable {
+    /// Appends the columns of a trace segment, split by row of the step. Named cells of
+    /// the steps keep their name, and other cells are named after their column and row
+    /// (e.g. `mem_a_5` for a filler address, or `p_m_0` for the first row of an aux column).
+    fn extend(&mut self, names: &[&str], cell_names: &[&[&str]], segment: &Matrix<Felt>) {
+        assert_eq!(names.len(), segment.num_cols(), "a column has no name");
+        for (i, name) in names.iter().enumerate() {
+            let column = segment.get_column(i);
+            let cells = cell_names.get(i).copied().unwrap_or_default();
+            for row in 0..STEP_HEIGHT {
+                self.names.push(match cells.get(row) {
+                    Some(cell) => cell.to_string(),
+                    None => format!("{}_{}", name, row),
+                });
+                self.columns.push(
+                    column
+                        .iter()
+                        .skip(row)
+                        .step_by(STEP_HEIGHT)
+                        .copied()
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.columns.first().map_or(0, |column| column.len())
+    }
+
+    pub fn write_file(&self, path: &Path, format: TraceFormat) -> io::Result<()> {
+        let file = File::create(path)?;
+        match format {
+            TraceFormat::Csv => self.write_csv(BufWriter::new(file)),
+            #[cfg(feature = "parquet")]
+            TraceFormat::Parquet => self.write_parquet(file),
+        }
+    }
+
+    /// Writes the table as CSV, with a header row of column names
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", self.names.join(","))?;
+        for row in 0..self.num_rows() {
+            let values = self
+                .columns
+                .iter()
+                .map(|column| felt_to_hex(column[row]))
+                .collect::<Vec<_>>();
+            writeln!(writer, "{}", values.join(","))?;
+        }
+        writer.flush()
+    }
+
+    /// Writes the table as a single Parquet row group of UTF-8 columns
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(&self, file: File) -> io::Result<()> {
+        use parquet::data_type::{ByteArray, ByteArrayType};
+        use parquet::file::{properties::WriterProperties, writer::SerializedFileWriter};
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let to_io = |e: parquet::errors::ParquetError| io::Error::new(io::ErrorKind::Other, e);
+        let fields = self
+            .names
+            .iter()
+            .map(|name| format!("REQUIRED BINARY {} (UTF8);", name))
+            .collect::<Vec<_>>();
+        let schema = parse_message_type(&format!("message trace {{ {} }}", fields.join(" ")))
+            .map_err(to_io)?;
+        let properties = WriterProperties::builder().build();
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))
+            .map_err(to_io)?;
+
+        let mut row_group = writer.next_row_group().map_err(to_io)?;
+        for column in self.columns.iter() {
+            let values = column
+                .iter()
+                .map(|value| ByteArray::from(felt_to_hex(*value).as_str()))
+                .collect::<Vec<_>>();
+            let mut column_writer = row_group
+                .next_column()
+                .map_err(to_io)?
+                .expect("schema has a field for every column");
+            column_writer
+                .typed::<ByteArrayType>()
+                .write_batch(&values, None, None)
+                .map_err(to_io)?;
+            column_writer.close().map_err(to_io)?;
+        }
+        row_group.close().map_err(to_io)?;
+        writer.close().map_err(to_io)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Memory;
+    use giza_core::{FieldElement, RegisterState, LOGUP_TRACE_WIDTH};
+
+    /// Builds the trace of `[ap] = 5, ap++; jmp rel 0`
+    fn trace() -> ExecutionTrace {
+        let mut mem = Memory::new(
+            [0x480680017fff8000u64, 5, 0x10780017fff7fff, 0]
+                .iter()
+                .map(|x| Felt::from(*x))
+                .collect(),
+        );
+        mem.write(Felt::from(6u64), Felt::from(5u64));
+        let registers = vec![
+            RegisterState::new(1u64, 6u64, 6u64),
+            RegisterState::new(3u64, 7u64, 6u64),
+        ];
+        ExecutionTrace::from_registers(registers, &mem, vec![], None, None).unwrap()
+    }
+
+    fn column<'a>(table: &'a TraceTable, name: &str) -> &'a [Felt] {
+        let i = table.names.iter().position(|n| n == name).unwrap();
+        &table.columns[i]
+    }
+
+    #[test]
+    fn test_to_table() {
+        let mut trace = trace();
+        let table = trace.to_table(None);
+        assert_eq!(table.names.len(), TRACE_COLUMN_NAMES.len() * STEP_HEIGHT);
+        assert_eq!(table.num_rows(), trace.length() / STEP_HEIGHT);
+        assert_eq!(table.names[0], "f_dst_fp");
+        assert_eq!(column(&table, "pc")[..2], [Felt::ONE, Felt::from(3u64)]);
+        assert_eq!(
+            column(&table, "ap")[..2],
+            [Felt::from(6u64), Felt::from(7u64)]
+        );
+        assert_eq!(column(&table, "dst")[0], Felt::from(5u64));
+        assert!(table.names.iter().any(|name| name == "mem_a_4"));
+        assert!(table.names.iter().any(|name| name == "cpu_15"));
+
+        let table = trace.to_table(Some(1));
+        assert_eq!(
+            table.names.len(),
+            (TRACE_COLUMN_NAMES.len() + 5) * STEP_HEIGHT
+        );
+        assert!(table.names.iter().any(|name| name == "p_rc_15"));
+
+        // The LogUp argument has its own tables and aux columns
+        trace.set_argument(AuxArgument::LogUp).unwrap();
+        let table = trace.to_table(Some(1));
+        assert_eq!(table.names.len(), (LOGUP_TRACE_WIDTH + 4) * STEP_HEIGHT);
+        assert_eq!(column(&table, "mem_table_a_1")[0], Felt::ONE);
+        assert!(table.names.iter().any(|name| name == "w_rc_15"));
+        assert!(!table.names.iter().any(|name| name.starts_with("p_m")));
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let table = TraceTable {
+            names: vec!["pc".to_string(), "ap".to_string()],
+            columns: vec![
+                vec![Felt::ONE, Felt::from(3u64)],
+                vec![Felt::from(10u64), Felt::from(255u64)],
+            ],
+        };
+        let mut csv = vec![];
+        table.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "pc,ap\n0x1,0xa\n0x3,0xff\n"
+        );
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_write_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::RowAccessor;
+
+        let table = trace().to_table(Some(1));
+        let path = std::env::temp_dir().join("giza_test_trace.parquet");
+        table.write_file(&path, TraceFormat::Parquet).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let names = schema
+            .columns()
+            .iter()
+            .map(|column| column.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, table.names);
+        let rows = reader.get_row_iter(None).unwrap().collect::<Vec<_>>();
+        assert_eq!(rows.len(), table.num_rows());
+        for (i, row) in rows.iter().enumerate() {
+            for (j, column) in table.columns.iter().enumerate() {
+                assert_eq!(row.get_string(j).unwrap(), &felt_to_hex(column[i]));
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! Hints of Cairo 1 programs. CASM files attach structured hints to the bytecode instead of
+//! Python code, so they are decoded into [CasmHint] and always executed natively. Operands
+//! follow the encoding of `cairo-lang-casm` (cells relative to ap or fp, double
+//! dereferences, immediates and binary operations).
+
+use super::native::felt_to_u128;
+use super::segments::Segments;
+use super::{write_cell, HintError};
+use crate::memory::Memory;
+use giza_core::{Felt, FieldElement, RegisterState, StarkField};
+use serde::{de, Deserialize, Deserializer};
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum Register {
+    AP,
+    FP,
+}
+
+/// A memory cell, given by its offset from ap or fp
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct CellRef {
+    pub register: Register,
+    pub offset: i16,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum Operation {
+    Add,
+    Mul,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum DerefOrImmediate {
+    Deref(CellRef),
+    Immediate(#[serde(deserialize_with = "deserialize_immediate")] Felt),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BinOpOperand {
+    pub op: Operation,
+    pub a: CellRef,
+    pub b: DerefOrImmediate,
+}
+
+/// An operand of a hint, evaluated against the registers and memory at the time the hint
+/// is executed
+#[derive(Clone, Debug, Deserialize)]
+pub enum ResOperand {
+    Deref(CellRef),
+    DoubleDeref(CellRef, i16),
+    Immediate(#[serde(deserialize_with = "deserialize_immediate")] Felt),
+    BinOp(BinOpOperand),
+}
+
+/// Core hints emitted by the Cairo 1 compiler that can be executed natively
+#[derive(Clone, Debug, Deserialize)]
+pub enum CoreHint {
+    AllocSegment {
+        dst: CellRef,
+    },
+    AllocConstantSize {
+        size: ResOperand,
+        dst: CellRef,
+    },
+    TestLessThan {
+        lhs: ResOperand,
+        rhs: ResOperand,
+        dst: CellRef,
+    },
+    TestLessThanOrEqual {
+        lhs: ResOperand,
+        rhs: ResOperand,
+        dst: CellRef,
+    },
+    WideMul128 {
+        lhs: ResOperand,
+        rhs: ResOperand,
+        high: CellRef,
+        low: CellRef,
+    },
+    DivMod {
+        lhs: ResOperand,
+        rhs: ResOperand,
+        quotient: CellRef,
+        remainder: CellRef,
+    },
+    SquareRoot {
+        value: ResOperand,
+        dst: CellRef,
+    },
+    DebugPrint {
+        start: ResOperand,
+        end: ResOperand,
+    },
+}
+
+/// A hint of a CASM program
+#[derive(Clone, Debug)]
+pub enum CasmHint {
+    Core(CoreHint),
+    /// A hint that is not implemented natively (e.g. a Starknet syscall), identified by
+    /// its variant name. Programs with such hints are rejected before they run, and
+    /// executing one fails with [HintError::UnsupportedHint].
+    Unsupported(String),
+}
+
+impl CasmHint {
+    /// Decodes a hint from its JSON encoding in a CASM file, e.g.
+    /// `{"AllocSegment": {"dst": {"register": "AP", "offset": 0}}}`
+    pub fn from_json(value: &serde_json::Value) -> Self {
+        match CoreHint::deserialize(value) {
+            Ok(hint) => CasmHint::Core(hint),
+            Err(_) => CasmHint::Unsupported(
+                value
+                    .as_object()
+                    .and_then(|hint| hint.keys().next().cloned())
+                    .unwrap_or_else(|| value.to_string()),
+            ),
+        }
+    }
+
+    /// Returns the name of the hint if it can't be executed natively
+    pub fn unsupported(&self) -> Option<&str> {
+        match self {
+            CasmHint::Core(_) => None,
+            CasmHint::Unsupported(name) => Some(name),
+        }
+    }
+
+    pub fn exec(
+        &self,
+        memory: &mut Memory,
+        registers: RegisterState,
+        segments: &mut Segments,
+    ) -> Result<(), HintError> {
+        let hint = match self {
+            CasmHint::Core(hint) => hint,
+            CasmHint::Unsupported(name) => return Err(HintError::UnsupportedHint(name.clone())),
+        };
+        let mut vm = Vm { memory, registers };
+        match hint {
+            CoreHint::AllocSegment { dst } => {
+                let base = segments.add(vm.memory);
+                vm.write(dst, base)
+            }
+            CoreHint::AllocConstantSize { size, dst } => {
+                // Segments grow as they are written to, so the size is only checked to be
+                // readable
+                vm.eval(size)?;
+                let base = segments.add(vm.memory);
+                vm.write(dst, base)
+            }
+            CoreHint::TestLessThan { lhs, rhs, dst } => {
+                let ordering = felt_cmp(vm.eval(lhs)?, vm.eval(rhs)?);
+                vm.write(dst, Felt::from((ordering == Ordering::Less) as u64))
+            }
+            CoreHint::TestLessThanOrEqual { lhs, rhs, dst } => {
+                let ordering = felt_cmp(vm.eval(lhs)?, vm.eval(rhs)?);
+                vm.write(dst, Felt::from((ordering != Ordering::Greater) as u64))
+            }
+            CoreHint::WideMul128 {
+                lhs,
+                rhs,
+                high,
+                low,
+            } => {
+                let lhs = felt_to_u128(vm.eval(lhs)?)?;
+                let rhs = felt_to_u128(vm.eval(rhs)?)?;
+                let (h, l) = wide_mul(lhs, rhs);
+                vm.write(high, Felt::from(h))?;
+                vm.write(low, Felt::from(l))
+            }
+            CoreHint::DivMod {
+                lhs,
+                rhs,
+                quotient,
+                remainder,
+            } => {
+                let lhs = felt_to_u128(vm.eval(lhs)?)?;
+                let rhs = felt_to_u128(vm.eval(rhs)?)?;
+                if rhs == 0 {
+                    return Err(HintError::AssertionFailed(String::from("Division by zero")));
+                }
+                vm.write(quotient, Felt::from(lhs / rhs))?;
+                vm.write(remainder, Felt::from(lhs % rhs))
+            }
+            CoreHint::SquareRoot { value, dst } => {
+                let value = felt_to_u128(vm.eval(value)?)?;
+                vm.write(dst, Felt::from(isqrt(value)))
+            }
+            CoreHint::DebugPrint { start, end } => {
+                let (mut addr, end) = (vm.eval(start)?, vm.eval(end)?);
+                while addr != end {
+                    println!("[DEBUG]\t{}", vm.read(addr)?);
+                    addr += Felt::ONE;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Memory and registers as seen by a CASM hint
+struct Vm<'a> {
+    memory: &'a mut Memory,
+    registers: RegisterState,
+}
+
+impl<'a> Vm<'a> {
+    fn addr(&self, cell: &CellRef) -> Felt {
+        let base = match cell.register {
+            Register::AP => self.registers.ap,
+            Register::FP => self.registers.fp,
+        };
+        offset(base, cell.offset)
+    }
+
+    fn read(&self, addr: Felt) -> Result<Felt, HintError> {
+        self.memory
+            .read(addr)
+            .ok_or(HintError::UnknownMemoryCell(addr))
+    }
+
+    /// Writes a cell, failing if it already holds a different value
+    fn write(&mut self, cell: &CellRef, value: Felt) -> Result<(), HintError> {
+        write_cell(self.memory, self.addr(cell), value)
+    }
+
+    fn eval(&self, operand: &ResOperand) -> Result<Felt, HintError> {
+        match operand {
+            ResOperand::Deref(cell) => self.read(self.addr(cell)),
+            ResOperand::DoubleDeref(cell, inner) => {
+                self.read(offset(self.read(self.addr(cell))?, *inner))
+            }
+            ResOperand::Immediate(value) => Ok(*value),
+            ResOperand::BinOp(BinOpOperand { op, a, b }) => {
+                let a = self.read(self.addr(a))?;
+                let b = match b {
+                    DerefOrImmediate::Deref(cell) => self.read(self.addr(cell))?,
+                    DerefOrImmediate::Immediate(value) => *value,
+                };
+                Ok(match op {
+                    Operation::Add => a + b,
+                    Operation::Mul => a * b,
+                })
+            }
+        }
+    }
+}
+
+fn offset(base: Felt, offset: i16) -> Felt {
+    match offset {
+        o if o < 0 => base - Felt::from(o.unsigned_abs() as u64),
+        o => base + Felt::from(o as u64),
+    }
+}
+
+/// Compares field elements as integers in [0, p)
+fn felt_cmp(a: Felt, b: Felt) -> Ordering {
+    a.as_int().0.iter().rev().cmp(b.as_int().0.iter().rev())
+}
+
+/// Full 256-bit product of two 128-bit values, as (high, low) halves
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+    let (mid, mid_carry) = (a0 * b1).overflowing_add(a1 * b0);
+    let (low, low_carry) = (a0 * b0).overflowing_add(mid << 64);
+    let high = a1 * b1 + (mid >> 64) + ((mid_carry as u128) << 64) + low_carry as u128;
+    (high, low)
+}
+
+/// Integer square root, rounded down
+fn isqrt(value: u128) -> u128 {
+    let mut root = (value as f64).sqrt() as u128;
+    while root.checked_mul(root).map_or(true, |square| square > value) {
+        root -= 1;
+    }
+    while (root + 1)
+        .checked_mul(root + 1)
+        .map_or(false, |square| square <= value)
+    {
+        root += 1;
+    }
+    root
+}
+
+/// Immediates are signed hex integers (e.g. "-0x1")
+fn deserialize_immediate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Felt, D::Error> {
+    let word = String::deserialize(deserializer)?;
+    let (negative, digits) = match word.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, word.as_str()),
+    };
+    let value = crate::cairo_interop::parse_hex_felt(digits)
+        .ok_or_else(|| de::Error::custom(format!("invalid immediate {}", word)))?;
+    Ok(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_casm_hints() {
+        let mut memory = Memory::new(vec![Felt::ZERO; 4]);
+        memory.write(Felt::from(10u64), Felt::from(7u64));
+        memory.write(Felt::from(11u64), -Felt::ONE);
+        let registers = RegisterState::new(1u64, 12u64, 12u64);
+        let mut segments = Segments::new();
+
+        let hints = [
+            json!({"TestLessThan": {
+                "lhs": {"Deref": {"register": "FP", "offset": -2}},
+                "rhs": {"Immediate": "0x8"},
+                "dst": {"register": "AP", "offset": 0}
+            }}),
+            json!({"TestLessThanOrEqual": {
+                "lhs": {"Deref": {"register": "FP", "offset": -1}},
+                "rhs": {"Immediate": "0x8"},
+                "dst": {"register": "AP", "offset": 1}
+            }}),
+            json!({"DivMod": {
+                "lhs": {"BinOp": {
+                    "op": "Mul",
+                    "a": {"register": "FP", "offset": -2},
+                    "b": {"Immediate": "0x3"}
+                }},
+                "rhs": {"Immediate": "0x4"},
+                "quotient": {"register": "AP", "offset": 2},
+                "remainder": {"register": "AP", "offset": 3}
+            }}),
+            json!({"WideMul128": {
+                "lhs": {"Immediate": "0xffffffffffffffffffffffffffffffff"},
+                "rhs": {"Immediate": "0x2"},
+                "high": {"register": "AP", "offset": 4},
+                "low": {"register": "AP", "offset": 5}
+            }}),
+            json!({"AllocSegment": {"dst": {"register": "AP", "offset": 6}}}),
+        ];
+        for hint in hints.iter().map(CasmHint::from_json) {
+            hint.exec(&mut memory, registers, &mut segments).unwrap();
+        }
+        let expected = [1u64, 0, 5, 1, 1];
+        for (i, value) in expected.iter().enumerate() {
+            assert_eq!(
+                memory.read(Felt::from(12 + i as u64)),
+                Some(Felt::from(*value))
+            );
+        }
+        assert_eq!(
+            memory.read(Felt::from(17u64)),
+            Some(Felt::from(u128::MAX - 1))
+        );
+        assert_eq!(memory.read(Felt::from(18u64)), Some(segments.bases()[0]));
+
+        let syscall = CasmHint::from_json(&json!({"SystemCall": {"system": {
+            "Deref": {"register": "FP", "offset": -3}
+        }}}));
+        assert_eq!(syscall.unsupported(), Some("SystemCall"));
+        assert!(matches!(
+            syscall.exec(&mut memory, registers, &mut segments),
+            Err(HintError::UnsupportedHint(name)) if name == "SystemCall"
+        ));
+    }
+
+    #[test]
+    fn test_wide_mul_and_isqrt() {
+        assert_eq!(wide_mul(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+        assert_eq!(wide_mul(1 << 64, 1 << 64), (1, 0));
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(isqrt(24), 4);
+        assert_eq!(isqrt(25), 5);
+    }
+}
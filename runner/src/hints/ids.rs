@@ -0,0 +1,697 @@
+//! Resolution of hint `ids` through the program's reference manager and identifiers.
+//!
+//! Every reference visible to a hint is recorded in the compiled program as a Cairo
+//! expression over the `ap` and `fp` registers (e.g. `[cast(fp + (-3), felt*)]`), along
+//! with the ap-tracking data at the point where the reference was defined. To read or
+//! write `ids.x`, the expression is evaluated against the current register state, after
+//! correcting `ap` by the number of cells allocated since the reference was created.
+
+use super::native::IdsAccess;
+use super::{write_cell, ApTracking, Hint, HintError};
+use crate::memory::Memory;
+use giza_core::{Felt, FieldElement, RegisterState};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+// PROGRAM DATA
+// ================================================================================================
+
+#[derive(Deserialize, Default)]
+pub struct ReferenceManager {
+    pub references: Vec<Reference>,
+}
+
+/// A reference as it appears in the compiled program
+#[derive(Deserialize)]
+pub struct Reference {
+    pub ap_tracking_data: ApTracking,
+    pub pc: u64,
+    pub value: String,
+}
+
+/// An entry of the compiled program's `identifiers` table. Only the fields needed to
+/// resolve constants, aliases and struct members are retained.
+#[derive(Deserialize)]
+pub struct Identifier {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+    #[serde(default)]
+    pub destination: Option<String>,
+    #[serde(default)]
+    pub members: Option<HashMap<String, Member>>,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct Member {
+    pub cairo_type: String,
+    pub offset: u64,
+}
+
+pub type Identifiers = HashMap<String, Identifier>;
+
+/// Follows alias identifiers until a non-alias identifier is found
+fn resolve_identifier<'a>(identifiers: &'a Identifiers, name: &str) -> Option<&'a Identifier> {
+    let mut identifier = identifiers.get(name)?;
+    // Bound the number of hops in case of (malformed) cyclic aliases
+    for _ in 0..identifiers.len() {
+        match (identifier.kind.as_str(), &identifier.destination) {
+            ("alias", Some(destination)) => identifier = identifiers.get(destination)?,
+            _ => return Some(identifier),
+        }
+    }
+    None
+}
+
+/// Returns the offset and type of a member of the given struct
+fn struct_member(
+    identifiers: &Identifiers,
+    struct_type: &str,
+    member: &str,
+) -> Result<(u64, String), HintError> {
+    resolve_identifier(identifiers, struct_type)
+        .and_then(|s| s.members.as_ref())
+        .and_then(|members| members.get(member))
+        .map(|m| (m.offset, m.cairo_type.clone()))
+        .ok_or_else(|| HintError::UnknownIdentifier(format!("{}.{}", struct_type, member)))
+}
+
+/// Returns the size of the given type in memory cells
+fn type_size(identifiers: &Identifiers, cairo_type: &str) -> Result<u64, HintError> {
+    if cairo_type == "felt" || cairo_type.ends_with('*') {
+        return Ok(1);
+    }
+    resolve_identifier(identifiers, cairo_type)
+        .and_then(|s| s.size)
+        .ok_or_else(|| HintError::UnknownIdentifier(cairo_type.to_string()))
+}
+
+/// Parses a decimal (optionally negative) or hexadecimal integer into a field element
+pub(crate) fn parse_felt(s: &str) -> Option<Felt> {
+    let s = s.trim();
+    if let Some(digits) = s.strip_prefix('-') {
+        return parse_felt(digits).map(|x| -x);
+    }
+    let (digits, radix) = match s.strip_prefix("0x") {
+        Some(hex) => (hex, 16),
+        None => (s, 10),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    let radix_felt = Felt::from(radix as u32);
+    digits.chars().try_fold(Felt::ZERO, |acc, c| {
+        c.to_digit(radix).map(|d| acc * radix_felt + Felt::from(d))
+    })
+}
+
+// REFERENCE EXPRESSIONS
+// ================================================================================================
+
+/// A Cairo expression used as the value of a reference
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Ap,
+    Fp,
+    Int(Felt),
+    Deref(Box<Expr>),
+    Cast(Box<Expr>, String),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+impl FromStr for Expr {
+    type Err = HintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = ExprParser { input: s, pos: 0 };
+        let expr = parser.parse_expr()?;
+        parser.skip_whitespace();
+        if parser.pos != s.len() {
+            return Err(parser.error());
+        }
+        Ok(expr)
+    }
+}
+
+impl Expr {
+    /// Returns true if the expression reads the `ap` register
+    fn uses_ap(&self) -> bool {
+        match self {
+            Expr::Ap => true,
+            Expr::Fp | Expr::Int(_) => false,
+            Expr::Deref(e) | Expr::Cast(e, _) | Expr::Neg(e) => e.uses_ap(),
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) => a.uses_ap() || b.uses_ap(),
+        }
+    }
+
+    /// Returns the Cairo type of the expression
+    fn cairo_type(&self) -> String {
+        match self {
+            Expr::Cast(_, t) => t.clone(),
+            Expr::Deref(e) => match e.cairo_type().strip_suffix('*') {
+                Some(t) => t.to_string(),
+                None => String::from("felt"),
+            },
+            _ => String::from("felt"),
+        }
+    }
+
+    fn eval(&self, registers: &RegisterState, memory: &Memory) -> Result<Felt, HintError> {
+        Ok(match self {
+            Expr::Ap => registers.ap,
+            Expr::Fp => registers.fp,
+            Expr::Int(x) => *x,
+            Expr::Deref(e) => {
+                let addr = e.eval(registers, memory)?;
+                memory
+                    .read(addr)
+                    .ok_or(HintError::UnknownMemoryCell(addr))?
+            }
+            Expr::Cast(e, _) => e.eval(registers, memory)?,
+            Expr::Neg(e) => -e.eval(registers, memory)?,
+            Expr::Add(a, b) => a.eval(registers, memory)? + b.eval(registers, memory)?,
+            Expr::Sub(a, b) => a.eval(registers, memory)? - b.eval(registers, memory)?,
+            Expr::Mul(a, b) => a.eval(registers, memory)? * b.eval(registers, memory)?,
+        })
+    }
+}
+
+struct ExprParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn error(&self) -> HintError {
+        HintError::InvalidReference(format!(
+            "cannot parse '{}' at position {}",
+            self.input, self.pos
+        ))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), HintError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    /// Consumes an identifier or number
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| self.rest().len());
+        let word = &self.rest()[..len];
+        self.pos += len;
+        word
+    }
+
+    /// Consumes a type up to (but excluding) the closing parenthesis of a cast
+    fn cairo_type(&mut self) -> Result<String, HintError> {
+        self.skip_whitespace();
+        let mut depth = 0;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    let cairo_type = self.rest()[..i].trim().to_string();
+                    self.pos += i;
+                    return Ok(cairo_type);
+                }
+                ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        Err(self.error())
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, HintError> {
+        let mut lhs = self.parse_product()?;
+        loop {
+            if self.eat("+") {
+                lhs = Expr::Add(Box::new(lhs), Box::new(self.parse_product()?));
+            } else if self.eat("-") {
+                lhs = Expr::Sub(Box::new(lhs), Box::new(self.parse_product()?));
+            } else {
+                return Ok(lhs);
+            }
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, HintError> {
+        let mut lhs = self.parse_unary()?;
+        while self.eat("*") {
+            lhs = Expr::Mul(Box::new(lhs), Box::new(self.parse_unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, HintError> {
+        if self.eat("-") {
+            Ok(Expr::Neg(Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_atom()
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, HintError> {
+        if self.eat("[") {
+            let inner = self.parse_expr()?;
+            self.expect("]")?;
+            return Ok(Expr::Deref(Box::new(inner)));
+        }
+        if self.eat("(") {
+            let inner = self.parse_expr()?;
+            self.expect(")")?;
+            return Ok(inner);
+        }
+        match self.word() {
+            "ap" => Ok(Expr::Ap),
+            "fp" => Ok(Expr::Fp),
+            "cast" => {
+                self.expect("(")?;
+                let inner = self.parse_expr()?;
+                self.expect(",")?;
+                let cairo_type = self.cairo_type()?;
+                self.expect(")")?;
+                Ok(Expr::Cast(Box::new(inner), cairo_type))
+            }
+            word => parse_felt(word).map(Expr::Int).ok_or_else(|| self.error()),
+        }
+    }
+}
+
+// IDS
+// ================================================================================================
+
+/// Location of a (possibly partially resolved) id
+enum Location {
+    /// The id is stored in memory at the given address
+    Addr(Felt),
+    /// The id is an rvalue that is not stored in memory
+    Value(Felt),
+}
+
+/// A reference visible to a hint, with `ap` already corrected for ap-tracking
+struct IdReference {
+    expr: Expr,
+    registers: RegisterState,
+}
+
+/// Resolves the `ids` of a single hint invocation
+pub struct IdsManager {
+    /// References by full name. References whose ap tracking was revoked are recorded
+    /// without a value, so that they hide same-named references of outer scopes.
+    references: HashMap<String, Option<IdReference>>,
+    identifiers: Rc<Identifiers>,
+    accessible_scopes: Vec<String>,
+}
+
+impl IdsManager {
+    /// Collects the references visible to the hint, evaluated at the given register state
+    pub fn new(
+        hint: &Hint,
+        references: &[Reference],
+        identifiers: &Rc<Identifiers>,
+        registers: RegisterState,
+    ) -> Result<Self, HintError> {
+        let mut resolved = HashMap::new();
+        if let Some(flow_tracking_data) = &hint.flow_tracking_data {
+            let hint_tracking = &flow_tracking_data.ap_tracking;
+            for (full_name, id) in flow_tracking_data.reference_ids.iter() {
+                let reference = references.get(*id as usize).ok_or_else(|| {
+                    HintError::InvalidReference(format!("unknown reference id {}", id))
+                })?;
+                let expr = reference.value.parse::<Expr>()?;
+
+                // The value of ap when the reference was defined, if it can be deduced.
+                // References whose ap tracking was revoked (or that are defined after the
+                // hint) cannot be accessed, as in cairo-run.
+                let ref_tracking = &reference.ap_tracking_data;
+                let ref_ap = match expr.uses_ap() {
+                    false => Some(registers.ap),
+                    true if ref_tracking.group != hint_tracking.group => None,
+                    true => hint_tracking
+                        .offset
+                        .checked_sub(ref_tracking.offset)
+                        .map(|diff| registers.ap - Felt::from(diff)),
+                };
+                let reference = ref_ap.map(|ap| IdReference {
+                    expr,
+                    registers: RegisterState { ap, ..registers },
+                });
+                resolved.insert(full_name.clone(), reference);
+            }
+        }
+
+        Ok(Self {
+            references: resolved,
+            identifiers: identifiers.clone(),
+            accessible_scopes: hint.accessible_scopes.clone(),
+        })
+    }
+
+    /// Looks up a constant (e.g. `ids.BLOCK_SIZE`) in the hint's accessible scopes,
+    /// innermost scope first
+    fn get_const(&self, name: &str) -> Option<Felt> {
+        self.accessible_scopes.iter().rev().find_map(|scope| {
+            let identifier = resolve_identifier(&self.identifiers, &format!("{}.{}", scope, name))?;
+            match (identifier.kind.as_str(), &identifier.value) {
+                ("const", Some(value)) => parse_felt(&value.to_string()),
+                _ => None,
+            }
+        })
+    }
+
+    /// Looks up a reference in the hint's accessible scopes, innermost scope first. The
+    /// innermost reference with the given name shadows the others, even if it was revoked.
+    fn reference(&self, name: &str) -> Option<&IdReference> {
+        self.accessible_scopes.iter().rev().find_map(|scope| {
+            self.references
+                .get(&format!("{}.{}", scope, name))
+                .map(Option::as_ref)
+        })?
+    }
+
+    /// Resolves a dotted path (e.g. `keccak_state.start_ptr`) to a location and type
+    fn locate(&self, name: &str, memory: &Memory) -> Result<(Location, String), HintError> {
+        let mut path = name.split('.');
+        let head = path.next().unwrap_or_default();
+        let reference = self
+            .reference(head)
+            .ok_or_else(|| HintError::UnknownIdentifier(name.to_string()))?;
+
+        let mut cairo_type = reference.expr.cairo_type();
+        let mut location = match &reference.expr {
+            Expr::Deref(addr) => Location::Addr(addr.eval(&reference.registers, memory)?),
+            expr => Location::Value(expr.eval(&reference.registers, memory)?),
+        };
+
+        for member in path {
+            // Member access through a pointer dereferences it first
+            let (base, struct_type) = match (cairo_type.strip_suffix('*'), location) {
+                (Some(pointee), Location::Addr(addr)) => (
+                    memory
+                        .read(addr)
+                        .ok_or(HintError::UnknownMemoryCell(addr))?,
+                    pointee.to_string(),
+                ),
+                (Some(pointee), Location::Value(ptr)) => (ptr, pointee.to_string()),
+                (None, Location::Addr(addr)) => (addr, cairo_type.clone()),
+                (None, Location::Value(_)) => {
+                    return Err(HintError::InvalidReference(format!(
+                        "cannot access member {} of an rvalue",
+                        member
+                    )))
+                }
+            };
+            let (offset, member_type) = struct_member(&self.identifiers, &struct_type, member)?;
+            location = Location::Addr(base + Felt::from(offset));
+            cairo_type = member_type;
+        }
+
+        Ok((location, cairo_type))
+    }
+
+    /// Returns the memory address of `ids.<name>`
+    pub fn get_addr(&self, name: &str, memory: &Memory) -> Result<Felt, HintError> {
+        match self.locate(name, memory)? {
+            (Location::Addr(addr), _) => Ok(addr),
+            (Location::Value(_), _) => Err(HintError::InvalidReference(format!(
+                "{} is not stored in memory",
+                name
+            ))),
+        }
+    }
+
+    /// Returns the address of the struct referred to by `ids.<name>`, if it is a struct
+    /// or a pointer to a struct (i.e. if its members can be accessed)
+    pub fn struct_addr(&self, name: &str, memory: &Memory) -> Result<Option<Felt>, HintError> {
+        if !self.is_reference(name) {
+            return Ok(None);
+        }
+        let (location, cairo_type) = self.locate(name, memory)?;
+        let is_struct = |t: &str| t != "felt" && !t.ends_with('*') && !t.starts_with('(');
+        match (cairo_type.strip_suffix('*'), location) {
+            (Some(pointee), Location::Addr(addr)) if is_struct(pointee) => memory
+                .read(addr)
+                .map(Some)
+                .ok_or(HintError::UnknownMemoryCell(addr)),
+            (Some(pointee), Location::Value(ptr)) if is_struct(pointee) => Ok(Some(ptr)),
+            (None, Location::Addr(addr)) if is_struct(&cairo_type) => Ok(Some(addr)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Whether the first component of a dotted name is a reference (rather than a constant)
+    fn is_reference(&self, name: &str) -> bool {
+        self.reference(name.split('.').next().unwrap_or_default())
+            .is_some()
+    }
+
+    /// Returns all cells of a struct-typed id, e.g. the two members of a `Uint256`
+    pub fn get_struct(&self, name: &str, memory: &Memory) -> Result<Vec<Felt>, HintError> {
+        let addr = self.get_addr(name, memory)?;
+        let (_, cairo_type) = self.locate(name, memory)?;
+        let size = type_size(&self.identifiers, &cairo_type)?;
+        (0..size)
+            .map(|i| {
+                let cell = addr + Felt::from(i);
+                memory.read(cell).ok_or(HintError::UnknownMemoryCell(cell))
+            })
+            .collect()
+    }
+
+    /// Writes all cells of a struct-typed id
+    pub fn set_struct(
+        &self,
+        name: &str,
+        values: &[Felt],
+        memory: &mut Memory,
+    ) -> Result<(), HintError> {
+        let addr = self.get_addr(name, memory)?;
+        for (i, value) in values.iter().enumerate() {
+            write_cell(memory, addr + Felt::from(i as u64), *value)?;
+        }
+        Ok(())
+    }
+}
+
+impl IdsAccess for IdsManager {
+    fn get(&self, name: &str, memory: &Memory) -> Result<Felt, HintError> {
+        if !self.is_reference(name) {
+            return self
+                .get_const(name)
+                .ok_or_else(|| HintError::UnknownIdentifier(name.to_string()));
+        }
+        match self.locate(name, memory)? {
+            (Location::Value(value), _) => Ok(value),
+            (Location::Addr(addr), _) => {
+                memory.read(addr).ok_or(HintError::UnknownMemoryCell(addr))
+            }
+        }
+    }
+
+    fn set(&self, name: &str, value: Felt, memory: &mut Memory) -> Result<(), HintError> {
+        let addr = self.get_addr(name, memory)?;
+        write_cell(memory, addr, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hints::FlowTrackingData;
+
+    fn identifiers() -> Identifiers {
+        serde_json::from_str(
+            r#"{
+                "__main__.Pair": {
+                    "type": "struct",
+                    "size": 2,
+                    "members": {
+                        "a": {"cairo_type": "felt", "offset": 0},
+                        "b": {"cairo_type": "felt", "offset": 1}
+                    }
+                },
+                "__main__.main.SIZE": {"type": "const", "value": 16},
+                "__main__.main.Alias": {"type": "alias", "destination": "__main__.Pair"}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn reference(value: &str, offset: u64) -> Reference {
+        Reference {
+            ap_tracking_data: ApTracking { group: 0, offset },
+            pc: 0,
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_expr() {
+        let expr = "[cast([fp + (-4)] + 2, felt*)]".parse::<Expr>().unwrap();
+        assert_eq!(
+            expr,
+            Expr::Deref(Box::new(Expr::Cast(
+                Box::new(Expr::Add(
+                    Box::new(Expr::Deref(Box::new(Expr::Add(
+                        Box::new(Expr::Fp),
+                        Box::new(Expr::Neg(Box::new(Expr::Int(Felt::from(4u8)))))
+                    )))),
+                    Box::new(Expr::Int(Felt::from(2u8)))
+                )),
+                String::from("felt*")
+            )))
+        );
+        assert_eq!(expr.cairo_type(), "felt");
+        assert!("cast(ap, (felt, felt))".parse::<Expr>().is_ok());
+        assert!("[cast(fp, felt*)".parse::<Expr>().is_err());
+    }
+
+    #[test]
+    fn test_ids_access() {
+        let identifiers = Rc::new(identifiers());
+        let references = vec![
+            // x is the felt at [fp - 3]
+            reference("[cast(fp + (-3), felt*)]", 0),
+            // p is a Pair* stored at [ap - 1], defined two cells before the hint
+            reference("[cast(ap + (-1), __main__.Pair**)]", 2),
+            // q is a Pair struct located at fp
+            reference("[cast(fp, __main__.Pair*)]", 0),
+        ];
+        let hint = Hint::new(
+            String::new(),
+            vec![String::from("__main__"), String::from("__main__.main")],
+            Some(FlowTrackingData {
+                ap_tracking: ApTracking {
+                    group: 0,
+                    offset: 4,
+                },
+                reference_ids: HashMap::from([
+                    (String::from("__main__.main.x"), 0),
+                    (String::from("__main__.main.p"), 1),
+                    (String::from("__main__.main.q"), 2),
+                ]),
+            }),
+        );
+
+        // fp = 10, ap = 14 (so the reference to p resolves to [12 - 1])
+        let mut memory = Memory::new(vec![]);
+        memory.write(Felt::from(7u64), Felt::from(42u64));
+        memory.write(Felt::from(11u64), Felt::from(20u64));
+        memory.write(Felt::from(21u64), Felt::from(5u64));
+        memory.write(Felt::from(10u64), Felt::from(1u64));
+        let registers = RegisterState::new(Felt::ONE, Felt::from(14u64), Felt::from(10u64));
+        let ids = IdsManager::new(&hint, &references, &identifiers, registers).unwrap();
+
+        assert_eq!(ids.get("x", &memory).unwrap(), Felt::from(42u64));
+        assert_eq!(ids.get("p.b", &memory).unwrap(), Felt::from(5u64));
+        assert_eq!(ids.get("q.a", &memory).unwrap(), Felt::from(1u64));
+        assert_eq!(
+            ids.get_struct("q", &memory).unwrap(),
+            vec![Felt::from(1u64), Felt::from(20u64)]
+        );
+        assert_eq!(ids.get("SIZE", &memory).unwrap(), Felt::from(16u64));
+        assert_eq!(
+            ids.struct_addr("p", &memory).unwrap(),
+            Some(Felt::from(20u64))
+        );
+        assert_eq!(
+            ids.struct_addr("q", &memory).unwrap(),
+            Some(Felt::from(10u64))
+        );
+        assert_eq!(ids.struct_addr("x", &memory).unwrap(), None);
+
+        ids.set("p.a", Felt::from(9u64), &mut memory).unwrap();
+        assert_eq!(memory.read(Felt::from(20u64)), Some(Felt::from(9u64)));
+        assert!(ids.set("p.a", Felt::from(9u64), &mut memory).is_ok());
+        assert!(matches!(
+            ids.set("x", Felt::from(43u64), &mut memory),
+            Err(HintError::InconsistentMemory { .. })
+        ));
+        assert!(ids.get("y", &memory).is_err());
+    }
+
+    #[test]
+    fn test_ids_scopes() {
+        let identifiers = Rc::new(identifiers());
+        let references = vec![
+            reference("[cast(fp + (-3), felt*)]", 0),
+            reference("[cast(fp + (-4), felt*)]", 0),
+            reference("[cast(ap + (-1), felt*)]", 0),
+        ];
+        let mut memory = Memory::new(vec![]);
+        memory.write(Felt::from(7u64), Felt::from(42u64));
+        memory.write(Felt::from(6u64), Felt::from(43u64));
+        let registers = RegisterState::new(Felt::ONE, Felt::from(14u64), Felt::from(10u64));
+
+        // x is defined both in the module and in main, and y in main with a revoked ap
+        let reference_ids = HashMap::from([
+            (String::from("__main__.x"), 0),
+            (String::from("__main__.main.x"), 1),
+            (String::from("__main__.y"), 0),
+            (String::from("__main__.main.y"), 2),
+        ]);
+        let hint = |scopes: &[&str]| {
+            Hint::new(
+                String::new(),
+                scopes.iter().map(|scope| scope.to_string()).collect(),
+                Some(FlowTrackingData {
+                    ap_tracking: ApTracking {
+                        group: 1,
+                        offset: 0,
+                    },
+                    reference_ids: reference_ids.clone(),
+                }),
+            )
+        };
+
+        // The innermost accessible scope wins, whatever the order of the references
+        let ids = IdsManager::new(
+            &hint(&["__main__", "__main__.main"]),
+            &references,
+            &identifiers,
+            registers,
+        )
+        .unwrap();
+        assert_eq!(ids.get("x", &memory).unwrap(), Felt::from(43u64));
+        assert!(ids.get("y", &memory).is_err());
+
+        let ids =
+            IdsManager::new(&hint(&["__main__"]), &references, &identifiers, registers).unwrap();
+        assert_eq!(ids.get("x", &memory).unwrap(), Felt::from(42u64));
+        assert_eq!(ids.get("y", &memory).unwrap(), Felt::from(42u64));
+    }
+}
@@ -0,0 +1,767 @@
+use crate::memory::Memory;
+use giza_core::{Felt, ProgramInputs, RegisterState, StarkField, Word};
+
+use pyo3::conversion::FromPyObject;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::rc::Rc;
+
+pub mod native;
+use native::{HintContext, NativeHintFn};
+
+pub mod casm;
+use casm::CasmHint;
+
+pub mod ids;
+use ids::{Identifiers, IdsManager, Reference, ReferenceManager};
+
+pub mod scopes;
+use scopes::{ExecutionScopes, Scope, ScopeValue};
+
+pub mod policy;
+use policy::PolicyViolation;
+
+pub mod segments;
+use segments::Segments;
+
+pub mod python;
+use python::{felt_to_py, prime, py_to_felt, IdsProxy, MemoryProxy, SegmentsProxy};
+
+/// Address at which the program bytecode is loaded (see [Memory::new])
+pub const PROGRAM_BASE: u64 = 1;
+
+#[derive(Default)]
+pub struct HintManager {
+    pub hints: HashMap<u64, Vec<Hint>>,
+    pub references: Vec<Reference>,
+    pub identifiers: Rc<Identifiers>,
+    pub program_input: ProgramInputs,
+}
+
+impl HintManager {
+    pub fn new(
+        hints: HashMap<u64, Vec<Hint>>,
+        references: Vec<Reference>,
+        identifiers: Identifiers,
+    ) -> Self {
+        Self {
+            hints,
+            references,
+            identifiers: Rc::new(identifiers),
+            program_input: ProgramInputs::default(),
+        }
+    }
+
+    /// Sets the input made available to hints as `program_input`
+    pub fn set_program_input(&mut self, program_input: ProgramInputs) {
+        self.program_input = program_input;
+    }
+
+    pub fn push_hint(&mut self, pc: u64, hint: Hint) {
+        self.hints.entry(pc).or_default().push(hint);
+    }
+
+    /// Checks that every CASM hint of the program can be executed natively, so that a
+    /// program relying on other hints is rejected before it runs
+    pub fn check_supported(&self) -> Result<(), HintError> {
+        let unsupported = self
+            .hints
+            .iter()
+            .flat_map(|(pc, hints)| hints.iter().map(move |hint| (*pc, hint)))
+            .filter_map(|(pc, hint)| Some((pc, hint.casm()?.unsupported()?)))
+            .min_by_key(|(pc, _)| *pc);
+        match unsupported {
+            Some((pc, name)) => Err(HintError::UnsupportedHint(format!("{} at pc {}", name, pc))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn get_hints(&self, pc: Felt) -> Option<&Vec<Hint>> {
+        let pc: u64 = pc.as_int().try_into().unwrap();
+        self.hints.get(&pc)
+    }
+
+    /// Executes all hints attached to the current pc, applying their effects to memory,
+    /// to the register state, to the execution scopes and to the allocated segments
+    pub fn exec_hints(
+        &self,
+        memory: &mut Memory,
+        registers: &mut RegisterState,
+        scopes: &mut ExecutionScopes,
+        segments: &mut Segments,
+    ) -> Result<(), HintError> {
+        for hint in self.get_hints(registers.pc).into_iter().flatten() {
+            if let Some(casm) = &hint.casm {
+                casm.exec(memory, *registers, segments)?;
+                continue;
+            }
+            let ids = IdsManager::new(hint, &self.references, &self.identifiers, *registers)?;
+            match hint.native {
+                Some(f) => f(&mut HintContext::new(memory, *registers, &ids, scopes))?,
+                None => hint
+                    .exec(
+                        memory,
+                        *registers,
+                        ids,
+                        scopes,
+                        segments,
+                        &self.program_input,
+                    )?
+                    .apply(memory, registers)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hint-related sections of a compiled Cairo program
+#[derive(Deserialize)]
+pub struct ProgramHints {
+    #[serde(default)]
+    hints: HashMap<String, Vec<Hint>>,
+    #[serde(default)]
+    reference_manager: ReferenceManager,
+    #[serde(default)]
+    identifiers: Identifiers,
+}
+
+impl From<ProgramHints> for HintManager {
+    fn from(program: ProgramHints) -> Self {
+        let mut manager = HintManager::new(
+            HashMap::new(),
+            program.reference_manager.references,
+            program.identifiers,
+        );
+        for (offset, hints) in program.hints {
+            let pc = PROGRAM_BASE + offset.parse::<u64>().expect("invalid hint pc");
+            for hint in hints {
+                // Rebuild the hint so that its native implementation is looked up
+                manager.push_hint(
+                    pc,
+                    Hint::new(hint.code, hint.accessible_scopes, hint.flow_tracking_data),
+                );
+            }
+        }
+        manager
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Hint {
+    code: String,
+    accessible_scopes: Vec<String>,
+    flow_tracking_data: Option<FlowTrackingData>,
+    /// Native implementation of the hint code, if one is available
+    #[serde(skip)]
+    native: Option<NativeHintFn>,
+    /// Structured hint of a Cairo 1 program, executed in place of the hint code
+    #[serde(skip)]
+    casm: Option<CasmHint>,
+}
+
+impl Hint {
+    pub fn new(
+        code: String,
+        accessible_scopes: Vec<String>,
+        flow_tracking_data: Option<FlowTrackingData>,
+    ) -> Self {
+        let native = native::lookup(&code);
+        Hint {
+            code,
+            accessible_scopes,
+            flow_tracking_data,
+            native,
+            casm: None,
+        }
+    }
+
+    /// Creates a hint from its JSON encoding in a CASM file, which is kept as the hint code
+    pub fn from_casm(value: &serde_json::Value) -> Self {
+        Hint {
+            code: value.to_string(),
+            accessible_scopes: vec![],
+            flow_tracking_data: None,
+            native: None,
+            casm: Some(CasmHint::from_json(value)),
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Returns the native implementation of this hint, if the hint belongs to one of the
+    /// library hint families implemented in [native]
+    pub fn native(&self) -> Option<NativeHintFn> {
+        self.native
+    }
+
+    /// Returns the structured hint of a CASM program, if this is one
+    pub fn casm(&self) -> Option<&CasmHint> {
+        self.casm.as_ref()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FlowTrackingData {
+    ap_tracking: ApTracking,
+    reference_ids: HashMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ApTracking {
+    group: u64,
+    offset: u64,
+}
+
+/// Errors raised while executing a hint
+#[derive(Debug)]
+pub enum HintError {
+    UnknownIdentifier(String),
+    UnknownMemoryCell(Felt),
+    ValueOutOfRange(Felt),
+    AssertionFailed(String),
+    InvalidReference(String),
+    InvalidScopeValue(String),
+    ExitMainScope,
+    UnbalancedScopes(usize),
+    PolicyViolation(Vec<PolicyViolation>),
+    UnsupportedHint(String),
+    InconsistentMemory {
+        addr: Felt,
+        current: Felt,
+        value: Felt,
+    },
+    PythonError(PyErr),
+}
+
+/// Writes a memory cell on behalf of a hint. Memory is write-once, as in cairo-run, so
+/// writing a different value to a cell that was already written is an error.
+pub fn write_cell(memory: &mut Memory, addr: Felt, value: Felt) -> Result<(), HintError> {
+    match memory.read(addr) {
+        Some(current) if current != value => Err(HintError::InconsistentMemory {
+            addr,
+            current,
+            value,
+        }),
+        _ => {
+            memory.write(addr, value);
+            Ok(())
+        }
+    }
+}
+
+impl From<PyErr> for HintError {
+    fn from(err: PyErr) -> Self {
+        HintError::PythonError(err)
+    }
+}
+
+/// Scope management functions made available to Python hints. Scope changes are
+/// recorded and applied once the hint has finished, as in cairo-run.
+const SCOPE_FUNCTIONS: &str = r#"
+__scope_ops = []
+def vm_enter_scope(new_scope_locals=None):
+    __scope_ops.append(dict(new_scope_locals or {}))
+def vm_exit_scope():
+    __scope_ops.append(None)
+"#;
+
+/// Variables of the hint environment that are not persisted in the execution scope
+const STATIC_LOCALS: &[&str] = &[
+    "__builtins__",
+    "__scope_ops",
+    "vm_enter_scope",
+    "vm_exit_scope",
+    "pc",
+    "ap",
+    "fp",
+    "memory",
+    "memory_updates",
+    "program_input",
+    "segments",
+    "ids",
+    "PRIME",
+];
+
+#[derive(Default, Debug)]
+pub struct MemoryUpdate(pub Vec<(u64, Word)>);
+
+/// Data structure containing all register and memory updates effected by hint execution
+#[derive(Default, Debug)]
+pub struct ExecutionEffect {
+    pub pc: Felt,
+    pub ap: Felt,
+    pub fp: Felt,
+    pub mem_updates: Option<MemoryUpdate>,
+}
+
+impl Hint {
+    /// Run hint code in a Python environment, and return the aggregated effect
+    /// on program state. Memory, segments and ids are exposed to the hint through proxy
+    /// objects, and variables assigned by the hint are stored in the current execution
+    /// scope.
+    pub fn exec(
+        &self,
+        memory: &mut Memory,
+        registers: RegisterState,
+        ids: IdsManager,
+        scopes: &mut ExecutionScopes,
+        segments: &mut Segments,
+        program_input: &ProgramInputs,
+    ) -> Result<ExecutionEffect, HintError> {
+        // Memory and segments are shared with the proxies while the hint runs
+        let shared_memory = Rc::new(RefCell::new(std::mem::replace(memory, Memory::new(vec![]))));
+        let shared_segments = Rc::new(RefCell::new(std::mem::take(segments)));
+        let result = Python::with_gil(|py| {
+            let globals = PyDict::new(py);
+            // Integers set by native hints are visible to Python hints as well
+            let mut natives = HashMap::new();
+            for (name, value) in scopes.current().iter() {
+                match value {
+                    ScopeValue::Python(value) => globals.set_item(name, value)?,
+                    ScopeValue::Native(value) => {
+                        if let Some(value) = scopes::native_to_felt(value.as_ref()) {
+                            globals.set_item(name, felt_to_py(py, value)?)?;
+                            natives.insert(name.clone(), value);
+                        }
+                    }
+                }
+            }
+            globals.set_item(
+                "pc",
+                TryInto::<u64>::try_into(registers.pc.as_int()).unwrap(),
+            )?;
+            globals.set_item(
+                "ap",
+                TryInto::<u64>::try_into(registers.ap.as_int()).unwrap(),
+            )?;
+            globals.set_item(
+                "fp",
+                TryInto::<u64>::try_into(registers.fp.as_int()).unwrap(),
+            )?;
+            globals.set_item("PRIME", prime(py)?)?;
+            globals.set_item(
+                "memory",
+                Py::new(py, MemoryProxy::new(shared_memory.clone()))?,
+            )?;
+            globals.set_item(
+                "segments",
+                Py::new(
+                    py,
+                    SegmentsProxy::new(shared_memory.clone(), shared_segments.clone()),
+                )?,
+            )?;
+            globals.set_item(
+                "ids",
+                Py::new(py, IdsProxy::new(Rc::new(ids), shared_memory.clone()))?,
+            )?;
+            globals.set_item("memory_updates", PyDict::new(py))?;
+            globals.set_item(
+                "program_input",
+                py.import("json")?
+                    .call_method1("loads", (program_input.as_json().to_string(),))?,
+            )?;
+            py.run(SCOPE_FUNCTIONS, Some(globals), None)?;
+            py.run(self.code.as_str(), Some(globals), None)?;
+            let effect = ExecutionEffect::from_locals(globals)?;
+
+            // Persist hint variables in the scope the hint was executed in
+            for (name, value) in globals.iter() {
+                let name = name.extract::<String>()?;
+                let unchanged_native = match natives.get(&name) {
+                    Some(native) => py_to_felt(value).ok() == Some(*native),
+                    None => false,
+                };
+                if !STATIC_LOCALS.contains(&name.as_str()) && !unchanged_native {
+                    scopes
+                        .current_mut()
+                        .insert(name, ScopeValue::Python(value.into()));
+                }
+            }
+
+            // Apply the scope changes requested by the hint
+            let ops = globals
+                .get_item("__scope_ops")
+                .unwrap()
+                .downcast::<PyList>()?;
+            for op in ops.iter() {
+                match op.extract::<Option<&PyDict>>()? {
+                    Some(locals) => {
+                        let mut scope = Scope::new();
+                        for (name, value) in locals.iter() {
+                            scope.insert(name.extract()?, ScopeValue::Python(value.into()));
+                        }
+                        scopes.enter_scope(scope);
+                    }
+                    None => scopes.exit_scope()?,
+                }
+            }
+            Ok(effect)
+        });
+
+        // Proxies kept alive by the hint (e.g. in scope variables) only see empty memory
+        *memory = shared_memory.replace(Memory::new(vec![]));
+        *segments = shared_segments.take();
+        result
+    }
+}
+
+impl ExecutionEffect {
+    /// Applies the register and memory updates to the current program state
+    pub fn apply(
+        self,
+        memory: &mut Memory,
+        registers: &mut RegisterState,
+    ) -> Result<(), HintError> {
+        registers.pc = self.pc;
+        registers.ap = self.ap;
+        registers.fp = self.fp;
+        if let Some(updates) = self.mem_updates {
+            for (addr, elem) in updates.0.iter() {
+                write_cell(memory, Felt::from(*addr), elem.word())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn from_locals(locals: &PyDict) -> PyResult<ExecutionEffect> {
+        let pc = locals.get_item("pc").unwrap().extract::<u64>()?;
+        let ap = locals.get_item("ap").unwrap().extract::<u64>()?;
+        let fp = locals.get_item("fp").unwrap().extract::<u64>()?;
+        let mem_updates: Option<MemoryUpdate> = locals
+            .get_item("memory_updates")
+            .unwrap()
+            .extract::<MemoryUpdate>()
+            .ok();
+        Ok(ExecutionEffect {
+            pc: Felt::from(pc),
+            ap: Felt::from(ap),
+            fp: Felt::from(fp),
+            mem_updates,
+        })
+    }
+}
+
+impl<'a> FromPyObject<'a> for MemoryUpdate {
+    fn extract(dict: &PyAny) -> PyResult<Self> {
+        let mut mem_update = MemoryUpdate::default();
+        for (key, val) in dict.downcast::<PyDict>()?.iter() {
+            mem_update.0.push((
+                key.extract::<u64>()?,
+                Word::new(Felt::from(val.extract::<u128>()?)),
+            ));
+        }
+        Ok(mem_update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use giza_core::FieldElement;
+
+    /// Executes a hint without ids
+    fn exec(
+        hint: &Hint,
+        memory: &mut Memory,
+        registers: RegisterState,
+        scopes: &mut ExecutionScopes,
+        program_input: &ProgramInputs,
+    ) -> Result<ExecutionEffect, HintError> {
+        let ids = IdsManager::new(hint, &[], &Rc::new(Identifiers::new()), registers)?;
+        hint.exec(
+            memory,
+            registers,
+            ids,
+            scopes,
+            &mut Segments::new(),
+            program_input,
+        )
+    }
+
+    #[test]
+    fn test_hint_execution() {
+        let mut memory = Memory::new(vec![]);
+        memory.write(Felt::from(memory.size()), Felt::from(1u64));
+        memory.write(Felt::from(memory.size()), Felt::from(2u64));
+        println!("{}", memory);
+        let mut registers =
+            RegisterState::new(Felt::from(1u64), Felt::from(1u64), Felt::from(1u64));
+        let hint = Hint::new(
+            String::from("pc = 2; ap = 5; memory_updates[3] = 10"),
+            vec![],
+            None,
+        );
+        let res = exec(
+            &hint,
+            &mut memory,
+            registers,
+            &mut ExecutionScopes::new(),
+            &ProgramInputs::default(),
+        )
+        .unwrap();
+        println!("res {:?}", res);
+        res.apply(&mut memory, &mut registers).unwrap();
+        assert_eq!(registers.ap, Felt::from(5u64));
+        assert_eq!(memory.read(Felt::from(3u64)), Some(Felt::from(10u64)));
+
+        // Memory is write-once, for memory updates as for the memory object
+        let hint = Hint::new(String::from("memory_updates[1] = 10"), vec![], None);
+        let res = exec(
+            &hint,
+            &mut memory,
+            registers,
+            &mut ExecutionScopes::new(),
+            &ProgramInputs::default(),
+        )
+        .unwrap();
+        assert!(matches!(
+            res.apply(&mut memory, &mut registers),
+            Err(HintError::InconsistentMemory { .. })
+        ));
+        assert_eq!(memory.read(Felt::ONE), Some(Felt::ONE));
+    }
+
+    #[test]
+    fn test_hint_program_input() {
+        let mut memory = Memory::new(vec![]);
+        let registers = RegisterState::new(Felt::ONE, Felt::ONE, Felt::ONE);
+        let program_input: ProgramInputs = serde_json::from_str(r#"{"n": 7}"#).unwrap();
+        let hint = Hint::new(
+            String::from("memory[ap] = program_input['n']"),
+            vec![],
+            None,
+        );
+        exec(
+            &hint,
+            &mut memory,
+            registers,
+            &mut ExecutionScopes::new(),
+            &program_input,
+        )
+        .unwrap();
+        assert_eq!(memory.read(Felt::ONE), Some(Felt::from(7u64)));
+    }
+
+    #[test]
+    fn test_hint_scopes() {
+        let mut memory = Memory::new(vec![]);
+        let registers = RegisterState::new(Felt::ONE, Felt::ONE, Felt::ONE);
+        let program_input = ProgramInputs::default();
+        let mut scopes = ExecutionScopes::new();
+        let enter = Hint::new(
+            String::from("n_elms = 3\nvm_enter_scope({'x': 1})"),
+            vec![],
+            None,
+        );
+        exec(&enter, &mut memory, registers, &mut scopes, &program_input).unwrap();
+        assert_eq!(scopes.depth(), 1);
+        assert_eq!(scopes.get_u64("x").unwrap(), Some(1));
+        assert_eq!(scopes.get_u64("n_elms").unwrap(), None);
+
+        let exit = Hint::new(String::from("assert x == 1\nvm_exit_scope()"), vec![], None);
+        exec(&exit, &mut memory, registers, &mut scopes, &program_input).unwrap();
+        assert_eq!(scopes.get_u64("n_elms").unwrap(), Some(3));
+        assert!(scopes.check_balanced().is_ok());
+        assert!(exec(&exit, &mut memory, registers, &mut scopes, &program_input).is_err());
+    }
+
+    #[test]
+    fn test_hint_ids() {
+        // The hints of starkware.cairo.common.alloc.alloc and (without its imports)
+        // starkware.cairo.common.math.unsigned_div_rem
+        let program: ProgramHints = serde_json::from_str(
+            r#"{
+                "hints": {
+                    "0": [
+                        {
+                            "accessible_scopes": ["__main__"],
+                            "code": "memory[ap] = segments.add()",
+                            "flow_tracking_data": {"ap_tracking": {"group": 0, "offset": 0}, "reference_ids": {}}
+                        },
+                        {
+                            "accessible_scopes": ["__main__", "__main__.main"],
+                            "code": "ids.q, ids.r = divmod(ids.value, ids.div)",
+                            "flow_tracking_data": {
+                                "ap_tracking": {"group": 0, "offset": 0},
+                                "reference_ids": {
+                                    "__main__.main.value": 0,
+                                    "__main__.main.div": 1,
+                                    "__main__.main.q": 2,
+                                    "__main__.main.r": 3
+                                }
+                            }
+                        }
+                    ]
+                },
+                "reference_manager": {
+                    "references": [
+                        {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp + (-4), felt*)]"},
+                        {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp + (-3), felt*)]"},
+                        {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp, felt*)]"},
+                        {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp + 1, felt*)]"}
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        let manager = HintManager::from(program);
+
+        let mut memory = Memory::new(vec![]);
+        memory.write(Felt::from(6u64), Felt::from(17u64));
+        memory.write(Felt::from(7u64), Felt::from(5u64));
+        let mut registers = RegisterState::new(
+            Felt::from(PROGRAM_BASE),
+            Felt::from(12u64),
+            Felt::from(10u64),
+        );
+        manager
+            .exec_hints(
+                &mut memory,
+                &mut registers,
+                &mut ExecutionScopes::new(),
+                &mut Segments::new(),
+            )
+            .unwrap();
+        assert_eq!(memory.read(Felt::from(10u64)), Some(Felt::from(3u64)));
+        assert_eq!(memory.read(Felt::from(11u64)), Some(Felt::from(2u64)));
+
+        // The new segment is placed after the stack once relocated
+        assert_eq!(memory.relocate(), (vec![13], vec![12]));
+        assert_eq!(memory.read(Felt::from(12u64)), Some(Felt::from(13u64)));
+    }
+
+    #[test]
+    fn test_program_hints() {
+        let program: ProgramHints = serde_json::from_str(
+            r#"{
+                "hints": {
+                    "0": [{
+                        "accessible_scopes": ["__main__", "__main__.main"],
+                        "code": "ids.full_word = int(ids.n_bytes >= 4)",
+                        "flow_tracking_data": {
+                            "ap_tracking": {"group": 0, "offset": 0},
+                            "reference_ids": {
+                                "__main__.main.n_bytes": 0,
+                                "__main__.main.full_word": 1
+                            }
+                        }
+                    }]
+                },
+                "reference_manager": {
+                    "references": [
+                        {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp + (-3), felt*)]"},
+                        {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp, felt*)]"}
+                    ]
+                }
+            }"#,
+        )
+        .unwrap();
+        let manager = HintManager::from(program);
+
+        let mut memory = Memory::new(vec![]);
+        memory.write(Felt::from(7u64), Felt::from(6u64));
+        let mut registers = RegisterState::new(
+            Felt::from(PROGRAM_BASE),
+            Felt::from(12u64),
+            Felt::from(10u64),
+        );
+        manager
+            .exec_hints(
+                &mut memory,
+                &mut registers,
+                &mut ExecutionScopes::new(),
+                &mut Segments::new(),
+            )
+            .unwrap();
+        assert_eq!(memory.read(Felt::from(10u64)), Some(Felt::ONE));
+    }
+
+    #[test]
+    fn test_native_hint_dispatch() {
+        // The hint of starkware.cairo.common.keccak.unsafe_keccak, hashing "abc". Its
+        // eth_hash import is not needed when the hint runs natively
+        let code = r#"from eth_hash.auto import keccak
+
+data, length = ids.data, ids.length
+
+if '__keccak_max_size' in globals():
+    assert length <= __keccak_max_size, \
+        f'unsafe_keccak() can only be used with length<={__keccak_max_size}. ' \
+        f'Got: length={length}.'
+
+keccak_input = bytearray()
+for word_i, byte_i in enumerate(range(0, length, 16)):
+    word = memory[data + word_i]
+    n_bytes = min(16, length - byte_i)
+    assert 0 <= word < 2 ** (8 * n_bytes)
+    keccak_input += word.to_bytes(n_bytes, 'big')
+
+hashed = keccak(keccak_input)
+ids.high = int.from_bytes(hashed[:16], 'big')
+ids.low = int.from_bytes(hashed[16:32], 'big')"#;
+        let program: ProgramHints = serde_json::from_value(serde_json::json!({
+            "hints": {
+                "0": [{
+                    "accessible_scopes": ["__main__", "__main__.main"],
+                    "code": code,
+                    "flow_tracking_data": {
+                        "ap_tracking": {"group": 0, "offset": 0},
+                        "reference_ids": {
+                            "__main__.main.data": 0,
+                            "__main__.main.length": 1,
+                            "__main__.main.high": 2,
+                            "__main__.main.low": 3
+                        }
+                    }
+                }]
+            },
+            "reference_manager": {
+                "references": [
+                    {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp + (-4), felt*)]"},
+                    {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp + (-3), felt*)]"},
+                    {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp, felt*)]"},
+                    {"ap_tracking_data": {"group": 0, "offset": 0}, "pc": 0, "value": "[cast(fp + 1, felt*)]"}
+                ]
+            }
+        }))
+        .unwrap();
+        let manager = HintManager::from(program);
+        let hint = &manager.get_hints(Felt::from(PROGRAM_BASE)).unwrap()[0];
+        assert!(hint.native().is_some());
+
+        let mut memory = Memory::new(vec![]);
+        memory.write(Felt::from(6u64), Felt::from(20u64));
+        memory.write(Felt::from(7u64), Felt::from(3u64));
+        memory.write(Felt::from(20u64), Felt::from(0x616263u64));
+        let mut registers = RegisterState::new(
+            Felt::from(PROGRAM_BASE),
+            Felt::from(12u64),
+            Felt::from(10u64),
+        );
+        let mut scopes = ExecutionScopes::new();
+        manager
+            .exec_hints(
+                &mut memory,
+                &mut registers,
+                &mut scopes,
+                &mut Segments::new(),
+            )
+            .unwrap();
+        assert_eq!(
+            memory.read(Felt::from(10u64)),
+            Some(Felt::from(0x4e03657aea45a94fc7d47ba826c8d667u128))
+        );
+        assert_eq!(
+            memory.read(Felt::from(11u64)),
+            Some(Felt::from(0xc0d1e6e33a64a036ec44f58fa12d6c45u128))
+        );
+        // The Python interpreter would have left the locals of the hint in scope
+        assert_eq!(scopes.get_u64("length").unwrap(), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Native implementations of the hints used by `starkware.cairo.common.cairo_blake2s.blake2s`.
+
+use super::{felt_to_u128, felt_to_u32, felt_to_usize, HintContext, HintError};
+use giza_core::{Felt, FieldElement};
+
+pub const BLAKE2S_COMPUTE: &str = r#"from starkware.cairo.common.cairo_blake2s.blake2s_utils import compute_blake2s_func
+compute_blake2s_func(segments=segments, output_ptr=ids.output)"#;
+
+pub const BLAKE2S_FINALIZE: &str = r#"# Add dummy pairs of input and output.
+from starkware.cairo.common.cairo_blake2s.blake2s_utils import IV, blake2s_compress
+
+_n_packed_instances = int(ids.N_PACKED_INSTANCES)
+assert 0 <= _n_packed_instances < 20
+_blake2s_input_chunk_size_felts = int(ids.INPUT_BLOCK_FELTS)
+assert 0 <= _blake2s_input_chunk_size_felts < 100
+
+message = [0] * _blake2s_input_chunk_size_felts
+modified_iv = [IV[0] ^ 0x01010020] + IV[1:]
+output = blake2s_compress(
+    message=message,
+    h=modified_iv,
+    t0=0,
+    t1=0,
+    f0=0xffffffff,
+    f1=0,
+)
+padding = (modified_iv + message + [0, 0xffffffff] + output) * (_n_packed_instances - 1)
+segments.write_arg(ids.blake2s_ptr_end, padding)"#;
+
+pub const BLAKE2S_ADD_UINT256: &str = r#"B = 32
+MASK = 2 ** 32 - 1
+segments.write_arg(ids.data, [(ids.low >> (B * i)) & MASK for i in range(4)])
+segments.write_arg(ids.data + 4, [(ids.high >> (B * i)) & MASK for i in range(4)])"#;
+
+pub const BLAKE2S_ADD_UINT256_BIGEND: &str = r#"B = 32
+MASK = 2 ** 32 - 1
+segments.write_arg(ids.data, [(ids.high >> (B * (3 - i))) & MASK for i in range(4)])
+segments.write_arg(ids.data + 4, [(ids.low >> (B * (3 - i))) & MASK for i in range(4)])"#;
+
+pub const IV: [u32; 8] = [
+    0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Number of felts in a blake2s message block
+const MESSAGE_WORDS: usize = 16;
+
+/// Number of felts in a blake2s state
+const STATE_WORDS: usize = 8;
+
+fn mix(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+/// The blake2s compression function, with the same signature as `blake2s_utils.blake2s_compress`
+pub fn blake2s_compress(
+    h: &[u32; STATE_WORDS],
+    message: &[u32; MESSAGE_WORDS],
+    t0: u32,
+    t1: u32,
+    f0: u32,
+    f1: u32,
+) -> [u32; STATE_WORDS] {
+    let mut v = [0u32; 16];
+    v[..8].copy_from_slice(h);
+    v[8..12].copy_from_slice(&IV[..4]);
+    v[12] = IV[4] ^ t0;
+    v[13] = IV[5] ^ t1;
+    v[14] = IV[6] ^ f0;
+    v[15] = IV[7] ^ f1;
+
+    for s in SIGMA.iter() {
+        mix(&mut v, 0, 4, 8, 12, message[s[0]], message[s[1]]);
+        mix(&mut v, 1, 5, 9, 13, message[s[2]], message[s[3]]);
+        mix(&mut v, 2, 6, 10, 14, message[s[4]], message[s[5]]);
+        mix(&mut v, 3, 7, 11, 15, message[s[6]], message[s[7]]);
+        mix(&mut v, 0, 5, 10, 15, message[s[8]], message[s[9]]);
+        mix(&mut v, 1, 6, 11, 12, message[s[10]], message[s[11]]);
+        mix(&mut v, 2, 7, 8, 13, message[s[12]], message[s[13]]);
+        mix(&mut v, 3, 4, 9, 14, message[s[14]], message[s[15]]);
+    }
+
+    let mut out = [0u32; STATE_WORDS];
+    for i in 0..STATE_WORDS {
+        out[i] = h[i] ^ v[i] ^ v[i + 8];
+    }
+    out
+}
+
+fn read_words<const N: usize>(ctx: &HintContext, addr: Felt) -> Result<[u32; N], HintError> {
+    let mut words = [0u32; N];
+    for (word, value) in words.iter_mut().zip(ctx.get_range(addr, N)?) {
+        *word = felt_to_u32(value)?;
+    }
+    Ok(words)
+}
+
+fn to_felts(words: &[u32]) -> Vec<Felt> {
+    words.iter().map(|w| Felt::from(*w)).collect()
+}
+
+/// Mirrors `blake2s_utils.compute_blake2s_func`: the 26 cells preceding `ids.output` hold
+/// the state, the message block, the byte counter and the finalization flag.
+pub fn blake2s_compute(ctx: &mut HintContext) -> Result<(), HintError> {
+    let output = ctx.get_ids("output")?;
+    let h = read_words::<STATE_WORDS>(ctx, output - Felt::from(26u8))?;
+    let message = read_words::<MESSAGE_WORDS>(ctx, output - Felt::from(18u8))?;
+    let t = felt_to_u32(ctx.read(output - Felt::from(2u8))?)?;
+    let f = felt_to_u32(ctx.read(output - Felt::ONE)?)?;
+    let new_state = blake2s_compress(&h, &message, t, 0, f, 0);
+    ctx.write_arg(output, &to_felts(&new_state))
+}
+
+pub fn blake2s_finalize(ctx: &mut HintContext) -> Result<(), HintError> {
+    let n_packed_instances = felt_to_usize(ctx.get_ids("N_PACKED_INSTANCES")?)?;
+    let input_block_felts = felt_to_usize(ctx.get_ids("INPUT_BLOCK_FELTS")?)?;
+    if n_packed_instances >= 20 || input_block_felts != MESSAGE_WORDS {
+        return Err(HintError::AssertionFailed(String::from(
+            "0 <= _n_packed_instances < 20 and _blake2s_input_chunk_size_felts == 16",
+        )));
+    }
+
+    let message = [0u32; MESSAGE_WORDS];
+    let mut modified_iv = IV;
+    modified_iv[0] ^= 0x01010020;
+    let output = blake2s_compress(&modified_iv, &message, 0, 0, 0xffffffff, 0);
+
+    let mut instance = to_felts(&modified_iv);
+    instance.extend(to_felts(&message));
+    instance.extend(to_felts(&[0, 0xffffffff]));
+    instance.extend(to_felts(&output));
+    let padding = instance.repeat(n_packed_instances.saturating_sub(1));
+
+    let blake2s_ptr_end = ctx.get_ids("blake2s_ptr_end")?;
+    ctx.write_arg(blake2s_ptr_end, &padding)
+}
+
+/// Splits a 128-bit value into four 32-bit words, least significant first
+fn split_u128(x: u128) -> [u32; 4] {
+    [
+        x as u32,
+        (x >> 32) as u32,
+        (x >> 64) as u32,
+        (x >> 96) as u32,
+    ]
+}
+
+pub fn blake2s_add_uint256(ctx: &mut HintContext) -> Result<(), HintError> {
+    let data = ctx.get_ids("data")?;
+    let low = felt_to_u128(ctx.get_ids("low")?)?;
+    let high = felt_to_u128(ctx.get_ids("high")?)?;
+    ctx.write_arg(data, &to_felts(&split_u128(low)))?;
+    ctx.write_arg(data + Felt::from(4u8), &to_felts(&split_u128(high)))
+}
+
+pub fn blake2s_add_uint256_bigend(ctx: &mut HintContext) -> Result<(), HintError> {
+    let data = ctx.get_ids("data")?;
+    let low = felt_to_u128(ctx.get_ids("low")?)?;
+    let high = felt_to_u128(ctx.get_ids("high")?)?;
+    let mut high_words = split_u128(high);
+    let mut low_words = split_u128(low);
+    high_words.reverse();
+    low_words.reverse();
+    ctx.write_arg(data, &to_felts(&high_words))?;
+    ctx.write_arg(data + Felt::from(4u8), &to_felts(&low_words))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hashes a message with blake2s-256, compressing it block by block as
+    /// `blake2s_utils.compute_blake2s_func` does
+    fn blake2s(data: &[u8]) -> String {
+        let mut h = IV;
+        h[0] ^= 0x01010020;
+        let num_blocks = usize::max(1, (data.len() + 63) / 64);
+        for (i, block) in data
+            .chunks(64)
+            .chain([&[][..]])
+            .take(num_blocks)
+            .enumerate()
+        {
+            let mut bytes = [0u8; 64];
+            bytes[..block.len()].copy_from_slice(block);
+            let mut message = [0u32; MESSAGE_WORDS];
+            for (word, chunk) in message.iter_mut().zip(bytes.chunks(4)) {
+                *word = u32::from_le_bytes(chunk.try_into().unwrap());
+            }
+            let last = i == num_blocks - 1;
+            let t = (64 * i + block.len()) as u32;
+            h = blake2s_compress(&h, &message, t, 0, if last { 0xffffffff } else { 0 }, 0);
+        }
+        hex::encode(h.iter().flat_map(|w| w.to_le_bytes()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_blake2s() {
+        assert_eq!(
+            blake2s(b""),
+            "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9"
+        );
+        // Two blocks
+        assert_eq!(
+            blake2s(&[b'a'; 100]),
+            "214f24fe1118eb854450238e11bebe22d2e3937ed85c7c96c6c010106b752ad3"
+        );
+    }
+
+    #[test]
+    fn test_blake2s_compress() {
+        // blake2s-256("abc") as a single, final block
+        let mut h = IV;
+        h[0] ^= 0x01010020;
+        let mut message = [0u32; MESSAGE_WORDS];
+        message[0] = u32::from_le_bytes([b'a', b'b', b'c', 0]);
+        let digest = blake2s_compress(&h, &message, 3, 0, 0xffffffff, 0)
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hex::encode(digest),
+            "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"
+        );
+    }
+}
@@ -0,0 +1,355 @@
+//! Native implementations of the hints used by `starkware.cairo.common.keccak` and
+//! `starkware.cairo.common.cairo_keccak.keccak`.
+
+use super::{felt_to_u128, felt_to_u64, felt_to_usize, HintContext, HintError};
+use giza_core::{Felt, FieldElement, StarkField};
+
+pub const UNSAFE_KECCAK: &str = r#"from eth_hash.auto import keccak
+
+data, length = ids.data, ids.length
+
+if '__keccak_max_size' in globals():
+    assert length <= __keccak_max_size, \
+        f'unsafe_keccak() can only be used with length<={__keccak_max_size}. ' \
+        f'Got: length={length}.'
+
+keccak_input = bytearray()
+for word_i, byte_i in enumerate(range(0, length, 16)):
+    word = memory[data + word_i]
+    n_bytes = min(16, length - byte_i)
+    assert 0 <= word < 2 ** (8 * n_bytes)
+    keccak_input += word.to_bytes(n_bytes, 'big')
+
+hashed = keccak(keccak_input)
+ids.high = int.from_bytes(hashed[:16], 'big')
+ids.low = int.from_bytes(hashed[16:32], 'big')"#;
+
+pub const UNSAFE_KECCAK_FINALIZE: &str = r#"from eth_hash.auto import keccak
+keccak_input = bytearray()
+n_elms = ids.keccak_state.end_ptr - ids.keccak_state.start_ptr
+for word in memory.get_range(ids.keccak_state.start_ptr, n_elms):
+    keccak_input += word.to_bytes(16, 'big')
+hashed = keccak(keccak_input)
+ids.high = int.from_bytes(hashed[:16], 'big')
+ids.low = int.from_bytes(hashed[16:32], 'big')"#;
+
+pub const KECCAK_WRITE_ARGS: &str = r#"segments.write_arg(ids.inputs, [ids.low % 2 ** 64, ids.low // 2 ** 64])
+segments.write_arg(ids.inputs + 2, [ids.high % 2 ** 64, ids.high // 2 ** 64])"#;
+
+pub const COMPARE_BYTES_IN_WORD_NONDET: &str =
+    r#"memory[ap] = to_felt_or_relocatable(ids.n_bytes < ids.BYTES_IN_WORD)"#;
+
+pub const COMPARE_KECCAK_FULL_RATE_IN_BYTES_NONDET: &str =
+    r#"memory[ap] = to_felt_or_relocatable(ids.n_bytes >= ids.KECCAK_FULL_RATE_IN_BYTES)"#;
+
+pub const BLOCK_PERMUTATION: &str = r#"from starkware.cairo.common.keccak_utils.keccak_utils import keccak_func
+_keccak_state_size_felts = int(ids.KECCAK_STATE_SIZE_FELTS)
+assert 0 <= _keccak_state_size_felts < 100
+
+output_values = keccak_func(memory.get_range(
+    ids.keccak_ptr - _keccak_state_size_felts, _keccak_state_size_felts))
+segments.write_arg(ids.keccak_ptr, output_values)"#;
+
+pub const CAIRO_KECCAK_FINALIZE: &str = r#"# Add dummy pairs of input and output.
+_keccak_state_size_felts = int(ids.KECCAK_STATE_SIZE_FELTS)
+_block_size = int(ids.BLOCK_SIZE)
+assert 0 <= _keccak_state_size_felts < 100
+assert 0 <= _block_size < 10
+inp = [0] * _keccak_state_size_felts
+padding = (inp + keccak_func(inp)) * _block_size
+segments.write_arg(ids.keccak_ptr_end, padding)"#;
+
+pub const SPLIT_N_BYTES: &str =
+    r#"ids.n_words_to_copy, ids.n_bytes_left = divmod(ids.n_bytes, ids.BYTES_IN_WORD)"#;
+
+pub const SPLIT_OUTPUT_MID_LOW_HIGH: &str = r#"tmp, ids.output1_low = divmod(ids.output1, 256 ** 7)
+ids.output1_high, ids.output1_mid = divmod(tmp, 2 ** 128)"#;
+
+/// Number of 64-bit lanes in the Keccak-f[1600] state
+const STATE_LANES: usize = 25;
+
+/// Rate of Keccak-256 in bytes
+const KECCAK_256_RATE: usize = 136;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+const ROTATIONS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+/// Applies the Keccak-f[1600] permutation to a state of 25 little-endian lanes
+/// (the same layout used by `keccak_utils.keccak_func`).
+pub fn keccak_f1600(state: &mut [u64; STATE_LANES]) {
+    for rc in ROUND_CONSTANTS {
+        // Theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in (0..STATE_LANES).step_by(5) {
+                state[y + x] ^= d;
+            }
+        }
+
+        // Rho and pi
+        let mut last = state[1];
+        for (&lane, &rotation) in PI_LANES.iter().zip(ROTATIONS.iter()) {
+            let tmp = state[lane];
+            state[lane] = last.rotate_left(rotation);
+            last = tmp;
+        }
+
+        // Chi
+        for y in (0..STATE_LANES).step_by(5) {
+            let row = [
+                state[y],
+                state[y + 1],
+                state[y + 2],
+                state[y + 3],
+                state[y + 4],
+            ];
+            for x in 0..5 {
+                state[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // Iota
+        state[0] ^= rc;
+    }
+}
+
+/// Computes the (Ethereum flavoured) Keccak-256 digest of the input bytes
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    let mut state = [0u64; STATE_LANES];
+    let mut padded = input.to_vec();
+    padded.push(0x01);
+    while padded.len() % KECCAK_256_RATE != 0 {
+        padded.push(0);
+    }
+    *padded.last_mut().unwrap() |= 0x80;
+
+    for block in padded.chunks(KECCAK_256_RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        keccak_f1600(&mut state);
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, lane) in digest.chunks_mut(8).zip(state.iter()) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    digest
+}
+
+/// Applies Keccak-f[1600] to a vector of 25 field elements, each holding a 64-bit lane
+fn keccak_func(values: &[Felt]) -> Result<Vec<Felt>, HintError> {
+    if values.len() != STATE_LANES {
+        return Err(HintError::AssertionFailed(format!(
+            "keccak_func expects {} lanes, got {}",
+            STATE_LANES,
+            values.len()
+        )));
+    }
+    let mut state = [0u64; STATE_LANES];
+    for (lane, value) in state.iter_mut().zip(values) {
+        *lane = felt_to_u64(*value)?;
+    }
+    keccak_f1600(&mut state);
+    Ok(state.iter().map(|lane| Felt::from(*lane)).collect())
+}
+
+/// Stores a 32-byte digest as two 128-bit big-endian halves in `ids.high` and `ids.low`
+fn write_digest(ctx: &mut HintContext, digest: [u8; 32]) -> Result<(), HintError> {
+    let high = u128::from_be_bytes(digest[..16].try_into().unwrap());
+    let low = u128::from_be_bytes(digest[16..].try_into().unwrap());
+    ctx.set_ids("high", Felt::from(high))?;
+    ctx.set_ids("low", Felt::from(low))
+}
+
+pub fn unsafe_keccak(ctx: &mut HintContext) -> Result<(), HintError> {
+    let data = ctx.get_ids("data")?;
+    let length = felt_to_usize(ctx.get_ids("length")?)?;
+    if let Some(max_size) = ctx.scopes().get_u64("__keccak_max_size")? {
+        if length as u64 > max_size {
+            return Err(HintError::AssertionFailed(format!(
+                "unsafe_keccak() can only be used with length<={}. Got: length={}.",
+                max_size, length
+            )));
+        }
+    }
+
+    let mut input = Vec::with_capacity(length);
+    for (word_i, byte_i) in (0..length).step_by(16).enumerate() {
+        let word = felt_to_u128(ctx.read(data + Felt::from(word_i as u64))?)?;
+        let n_bytes = usize::min(16, length - byte_i);
+        if n_bytes < 16 && word >> (8 * n_bytes) != 0 {
+            return Err(HintError::AssertionFailed(format!(
+                "keccak input word {} does not fit in {} bytes",
+                word_i, n_bytes
+            )));
+        }
+        input.extend_from_slice(&word.to_be_bytes()[16 - n_bytes..]);
+    }
+
+    write_digest(ctx, keccak256(&input))
+}
+
+pub fn unsafe_keccak_finalize(ctx: &mut HintContext) -> Result<(), HintError> {
+    let start_ptr = ctx.get_ids("keccak_state.start_ptr")?;
+    let end_ptr = ctx.get_ids("keccak_state.end_ptr")?;
+    let n_elms = felt_to_usize(end_ptr - start_ptr)?;
+
+    let mut input = Vec::with_capacity(16 * n_elms);
+    for word in ctx.get_range(start_ptr, n_elms)? {
+        input.extend_from_slice(&felt_to_u128(word)?.to_be_bytes());
+    }
+
+    write_digest(ctx, keccak256(&input))
+}
+
+pub fn keccak_write_args(ctx: &mut HintContext) -> Result<(), HintError> {
+    let inputs = ctx.get_ids("inputs")?;
+    let low = felt_to_u128(ctx.get_ids("low")?)?;
+    let high = felt_to_u128(ctx.get_ids("high")?)?;
+    let split = |x: u128| [Felt::from(x as u64), Felt::from((x >> 64) as u64)];
+    ctx.write_arg(inputs, &split(low))?;
+    ctx.write_arg(inputs + Felt::from(2u8), &split(high))
+}
+
+pub fn compare_bytes_in_word_nondet(ctx: &mut HintContext) -> Result<(), HintError> {
+    let n_bytes = felt_to_u64(ctx.get_ids("n_bytes")?)?;
+    let bytes_in_word = felt_to_u64(ctx.get_ids("BYTES_IN_WORD")?)?;
+    let ap = ctx.ap();
+    ctx.write(ap, Felt::from((n_bytes < bytes_in_word) as u64))
+}
+
+pub fn compare_keccak_full_rate_in_bytes_nondet(ctx: &mut HintContext) -> Result<(), HintError> {
+    let n_bytes = felt_to_u64(ctx.get_ids("n_bytes")?)?;
+    let full_rate = felt_to_u64(ctx.get_ids("KECCAK_FULL_RATE_IN_BYTES")?)?;
+    let ap = ctx.ap();
+    ctx.write(ap, Felt::from((n_bytes >= full_rate) as u64))
+}
+
+pub fn block_permutation(ctx: &mut HintContext) -> Result<(), HintError> {
+    let state_size = felt_to_usize(ctx.get_ids("KECCAK_STATE_SIZE_FELTS")?)?;
+    if state_size >= 100 {
+        return Err(HintError::AssertionFailed(String::from(
+            "0 <= _keccak_state_size_felts < 100",
+        )));
+    }
+    let keccak_ptr = ctx.get_ids("keccak_ptr")?;
+    let input = ctx.get_range(keccak_ptr - Felt::from(state_size as u64), state_size)?;
+    let output = keccak_func(&input)?;
+    ctx.write_arg(keccak_ptr, &output)
+}
+
+pub fn cairo_keccak_finalize(ctx: &mut HintContext) -> Result<(), HintError> {
+    let state_size = felt_to_usize(ctx.get_ids("KECCAK_STATE_SIZE_FELTS")?)?;
+    let block_size = felt_to_usize(ctx.get_ids("BLOCK_SIZE")?)?;
+    if state_size >= 100 || block_size >= 10 {
+        return Err(HintError::AssertionFailed(String::from(
+            "0 <= _keccak_state_size_felts < 100 and 0 <= _block_size < 10",
+        )));
+    }
+    let input = vec![Felt::ZERO; state_size];
+    let mut block = input.clone();
+    block.extend(keccak_func(&input)?);
+    let padding = block.repeat(block_size);
+    let keccak_ptr_end = ctx.get_ids("keccak_ptr_end")?;
+    ctx.write_arg(keccak_ptr_end, &padding)
+}
+
+pub fn split_n_bytes(ctx: &mut HintContext) -> Result<(), HintError> {
+    let n_bytes = felt_to_u64(ctx.get_ids("n_bytes")?)?;
+    let bytes_in_word = felt_to_u64(ctx.get_ids("BYTES_IN_WORD")?)?;
+    ctx.set_ids("n_words_to_copy", Felt::from(n_bytes / bytes_in_word))?;
+    ctx.set_ids("n_bytes_left", Felt::from(n_bytes % bytes_in_word))
+}
+
+pub fn split_output_mid_low_high(ctx: &mut HintContext) -> Result<(), HintError> {
+    // output1 is at most 200 bits wide, so it is split on its 64-bit limbs
+    let limbs = ctx.get_ids("output1")?.as_int().0;
+    let low = limbs[0] & ((1 << 56) - 1);
+    let tmp = (limbs[0] >> 56) as u128 | (limbs[1] as u128) << 8 | (limbs[2] as u128) << 72;
+    let high = (limbs[2] >> 56) as u128 | (limbs[3] as u128) << 8;
+    ctx.set_ids("output1_low", Felt::from(low))?;
+    ctx.set_ids("output1_mid", Felt::from(tmp))?;
+    ctx.set_ids("output1_high", Felt::from(high))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keccak_f1600() {
+        let mut state = [0u64; STATE_LANES];
+        keccak_f1600(&mut state);
+        assert_eq!(state[0], 0xf1258f7940e1dde7);
+        assert_eq!(state[24], 0xeaf1ff7b5ceca249);
+        // Permuting the output again, as the Keccak reference test vectors do
+        keccak_f1600(&mut state);
+        assert_eq!(state[0], 0x2d5c954df96ecb3c);
+        assert_eq!(state[1], 0x6a332cd07057b56d);
+        assert_eq!(state[24], 0x20d06cd26a8fbf5c);
+    }
+
+    #[test]
+    fn test_keccak_func() {
+        let lanes = (0..STATE_LANES as u64).map(Felt::from).collect::<Vec<_>>();
+        let output = keccak_func(&lanes).unwrap();
+        assert_eq!(output[0], Felt::from(0x8374b05252ed8115u64));
+        assert_eq!(output[24], Felt::from(0xd153f43a297e4a33u64));
+        assert!(keccak_func(&lanes[1..]).is_err());
+        assert!(keccak_func(&[-Felt::ONE; STATE_LANES]).is_err());
+    }
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex::encode(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        // Spans two blocks of the sponge
+        assert_eq!(
+            hex::encode(keccak256(&[0x61; 200])),
+            "96ea54061def936c4be90b518992fdc6f12f535068a256229aca54267b4d084d"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! Native implementations of common Cairo library hints. Hints whose code matches one of
+//! the families below are executed in Rust rather than in the Python interpreter, and
+//! produce the same memory writes as cairo-run.
+
+mod blake2s;
+mod keccak;
+mod sha256;
+
+pub use blake2s::blake2s_compress;
+pub use keccak::{keccak256, keccak_f1600};
+pub use sha256::{compute_message_schedule, sha256, sha2_compress_function};
+
+use super::scopes::ExecutionScopes;
+use super::{write_cell, HintError};
+use crate::memory::Memory;
+use giza_core::{Felt, FieldElement, FieldHelpers, RegisterState, StarkField};
+
+/// Signature shared by all native hint implementations
+pub type NativeHintFn = fn(&mut HintContext) -> Result<(), HintError>;
+
+/// Table of supported hint code and the corresponding native implementation
+const NATIVE_HINTS: &[(&str, NativeHintFn)] = &[
+    // keccak
+    (keccak::UNSAFE_KECCAK, keccak::unsafe_keccak),
+    (
+        keccak::UNSAFE_KECCAK_FINALIZE,
+        keccak::unsafe_keccak_finalize,
+    ),
+    (keccak::KECCAK_WRITE_ARGS, keccak::keccak_write_args),
+    (
+        keccak::COMPARE_BYTES_IN_WORD_NONDET,
+        keccak::compare_bytes_in_word_nondet,
+    ),
+    (
+        keccak::COMPARE_KECCAK_FULL_RATE_IN_BYTES_NONDET,
+        keccak::compare_keccak_full_rate_in_bytes_nondet,
+    ),
+    (keccak::BLOCK_PERMUTATION, keccak::block_permutation),
+    (keccak::CAIRO_KECCAK_FINALIZE, keccak::cairo_keccak_finalize),
+    (keccak::SPLIT_N_BYTES, keccak::split_n_bytes),
+    (
+        keccak::SPLIT_OUTPUT_MID_LOW_HIGH,
+        keccak::split_output_mid_low_high,
+    ),
+    // blake2s
+    (blake2s::BLAKE2S_COMPUTE, blake2s::blake2s_compute),
+    (blake2s::BLAKE2S_FINALIZE, blake2s::blake2s_finalize),
+    (blake2s::BLAKE2S_ADD_UINT256, blake2s::blake2s_add_uint256),
+    (
+        blake2s::BLAKE2S_ADD_UINT256_BIGEND,
+        blake2s::blake2s_add_uint256_bigend,
+    ),
+    // sha256
+    (sha256::SHA256_INPUT, sha256::sha256_input),
+    (sha256::SHA256_MAIN, sha256::sha256_main),
+    (sha256::SHA256_FINALIZE, sha256::sha256_finalize),
+];
+
+/// Returns the native implementation of the given hint code, if there is one
+pub fn lookup(code: &str) -> Option<NativeHintFn> {
+    let code = normalize(code);
+    NATIVE_HINTS
+        .iter()
+        .find(|(known, _)| normalize(known) == code)
+        .map(|(_, f)| *f)
+}
+
+/// Strips leading/trailing blank lines and trailing whitespace, so that hint code
+/// compares equal regardless of how the compiler indented it
+pub(crate) fn normalize(code: &str) -> String {
+    code.trim()
+        .lines()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read and write access to the `ids` of a hint (references and constants in scope)
+pub trait IdsAccess {
+    /// Returns the value of `ids.<name>`. Struct members are addressed with dotted names.
+    fn get(&self, name: &str, memory: &Memory) -> Result<Felt, HintError>;
+
+    /// Assigns a value to `ids.<name>`
+    fn set(&self, name: &str, value: Felt, memory: &mut Memory) -> Result<(), HintError>;
+}
+
+/// The view of the Cairo VM exposed to a native hint
+pub struct HintContext<'a> {
+    memory: &'a mut Memory,
+    registers: RegisterState,
+    ids: &'a dyn IdsAccess,
+    scopes: &'a mut ExecutionScopes,
+}
+
+impl<'a> HintContext<'a> {
+    pub fn new(
+        memory: &'a mut Memory,
+        registers: RegisterState,
+        ids: &'a dyn IdsAccess,
+        scopes: &'a mut ExecutionScopes,
+    ) -> Self {
+        Self {
+            memory,
+            registers,
+            ids,
+            scopes,
+        }
+    }
+
+    pub fn ap(&self) -> Felt {
+        self.registers.ap
+    }
+
+    pub fn fp(&self) -> Felt {
+        self.registers.fp
+    }
+
+    /// Returns the value of `ids.<name>`
+    pub fn get_ids(&self, name: &str) -> Result<Felt, HintError> {
+        self.ids.get(name, self.memory)
+    }
+
+    /// Assigns `ids.<name> = value`
+    pub fn set_ids(&mut self, name: &str, value: Felt) -> Result<(), HintError> {
+        self.ids.set(name, value, self.memory)
+    }
+
+    /// Equivalent of `memory[addr]`
+    pub fn read(&self, addr: Felt) -> Result<Felt, HintError> {
+        self.memory
+            .read(addr)
+            .ok_or(HintError::UnknownMemoryCell(addr))
+    }
+
+    /// Equivalent of `memory[addr] = value`
+    pub fn write(&mut self, addr: Felt, value: Felt) -> Result<(), HintError> {
+        write_cell(self.memory, addr, value)
+    }
+
+    /// Equivalent of `memory.get_range(addr, len)`
+    pub fn get_range(&self, addr: Felt, len: usize) -> Result<Vec<Felt>, HintError> {
+        (0..len as u64)
+            .map(|i| self.read(addr + Felt::from(i)))
+            .collect()
+    }
+
+    /// The execution scopes of the run, holding variables shared between hints
+    pub fn scopes(&mut self) -> &mut ExecutionScopes {
+        self.scopes
+    }
+
+    /// Equivalent of `segments.write_arg(addr, values)`
+    pub fn write_arg(&mut self, addr: Felt, values: &[Felt]) -> Result<(), HintError> {
+        for (i, value) in values.iter().enumerate() {
+            self.write(addr + Felt::from(i as u64), *value)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn felt_to_u128(x: Felt) -> Result<u128, HintError> {
+    let limbs = x.as_int().0;
+    if limbs[2] != 0 || limbs[3] != 0 {
+        return Err(HintError::ValueOutOfRange(x));
+    }
+    Ok(limbs[0] as u128 | (limbs[1] as u128) << 64)
+}
+
+fn felt_to_u64(x: Felt) -> Result<u64, HintError> {
+    let value = felt_to_u128(x)?;
+    u64::try_from(value).map_err(|_| HintError::ValueOutOfRange(x))
+}
+
+fn felt_to_u32(x: Felt) -> Result<u32, HintError> {
+    let value = x.to_u64();
+    match u32::try_from(value) {
+        Ok(value) if Felt::from(value) == x => Ok(value),
+        _ => Err(HintError::ValueOutOfRange(x)),
+    }
+}
+
+fn felt_to_usize(x: Felt) -> Result<usize, HintError> {
+    Ok(felt_to_u64(x)? as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Resolves every id to a fixed memory cell
+    struct TestIds(HashMap<&'static str, Felt>);
+
+    impl IdsAccess for TestIds {
+        fn get(&self, name: &str, memory: &Memory) -> Result<Felt, HintError> {
+            let addr = self.0[name];
+            memory.read(addr).ok_or(HintError::UnknownMemoryCell(addr))
+        }
+
+        fn set(&self, name: &str, value: Felt, memory: &mut Memory) -> Result<(), HintError> {
+            write_cell(memory, self.0[name], value)
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert!(lookup(sha256::SHA256_INPUT).is_some());
+        assert!(lookup(&format!("\n{}  \n", keccak::KECCAK_WRITE_ARGS)).is_some());
+        assert!(lookup("memory[ap] = 1").is_none());
+    }
+
+    #[test]
+    fn test_sha256_main_hint() {
+        // Message block of sha256("abc") at [10, 26), output written at 30
+        let mut memory = Memory::new(vec![]);
+        memory.write(Felt::from(10u64), Felt::from(0x61626380u64));
+        for i in 11..25u64 {
+            memory.write(Felt::from(i), Felt::ZERO);
+        }
+        memory.write(Felt::from(25u64), Felt::from(24u64));
+        memory.write(Felt::from(1u64), Felt::from(10u64)); // ids.sha256_start
+        memory.write(Felt::from(2u64), Felt::from(30u64)); // ids.output
+        memory.write(Felt::from(3u64), Felt::from(16u64)); // ids.SHA256_INPUT_CHUNK_SIZE_FELTS
+        let ids = TestIds(HashMap::from([
+            ("sha256_start", Felt::from(1u64)),
+            ("output", Felt::from(2u64)),
+            ("SHA256_INPUT_CHUNK_SIZE_FELTS", Felt::from(3u64)),
+        ]));
+
+        let hint = lookup(sha256::SHA256_MAIN).unwrap();
+        let registers = RegisterState::new(Felt::ONE, Felt::ONE, Felt::ONE);
+        let mut scopes = ExecutionScopes::new();
+        hint(&mut HintContext::new(
+            &mut memory,
+            registers,
+            &ids,
+            &mut scopes,
+        ))
+        .unwrap();
+        assert_eq!(
+            memory.read(Felt::from(30u64)),
+            Some(Felt::from(0xba7816bfu64))
+        );
+        assert_eq!(
+            memory.read(Felt::from(37u64)),
+            Some(Felt::from(0xf20015adu64))
+        );
+    }
+
+    #[test]
+    fn test_block_permutation_hint() {
+        // State [0, 25) at [100, 125), permuted state written at keccak_ptr = 125
+        let mut memory = Memory::new(vec![]);
+        for i in 0..25u64 {
+            memory.write(Felt::from(100 + i), Felt::from(i));
+        }
+        memory.write(Felt::from(1u64), Felt::from(25u64)); // ids.KECCAK_STATE_SIZE_FELTS
+        memory.write(Felt::from(2u64), Felt::from(125u64)); // ids.keccak_ptr
+        let ids = TestIds(HashMap::from([
+            ("KECCAK_STATE_SIZE_FELTS", Felt::from(1u64)),
+            ("keccak_ptr", Felt::from(2u64)),
+        ]));
+
+        let hint = lookup(keccak::BLOCK_PERMUTATION).unwrap();
+        let registers = RegisterState::new(Felt::ONE, Felt::ONE, Felt::ONE);
+        let mut scopes = ExecutionScopes::new();
+        hint(&mut HintContext::new(
+            &mut memory,
+            registers,
+            &ids,
+            &mut scopes,
+        ))
+        .unwrap();
+        assert_eq!(
+            memory.read(Felt::from(125u64)),
+            Some(Felt::from(0x8374b05252ed8115u64))
+        );
+        assert_eq!(
+            memory.read(Felt::from(149u64)),
+            Some(Felt::from(0xd153f43a297e4a33u64))
+        );
+        assert_eq!(memory.read(Felt::from(150u64)), None);
+    }
+
+    #[test]
+    fn test_blake2s_compute_hint() {
+        // State, message block, counter and flag of blake2s("abc") at [14, 40), new state
+        // written at output = 40
+        let mut memory = Memory::new(vec![]);
+        let mut input = blake2s::IV.map(u64::from).to_vec();
+        input[0] ^= 0x01010020;
+        input.push(0x00636261);
+        input.extend([0; 15]);
+        input.extend([3, 0xffffffff]);
+        for (i, word) in input.into_iter().enumerate() {
+            memory.write(Felt::from(14 + i as u64), Felt::from(word));
+        }
+        memory.write(Felt::from(1u64), Felt::from(40u64)); // ids.output
+        let ids = TestIds(HashMap::from([("output", Felt::from(1u64))]));
+
+        let hint = lookup(blake2s::BLAKE2S_COMPUTE).unwrap();
+        let registers = RegisterState::new(Felt::ONE, Felt::ONE, Felt::ONE);
+        let mut scopes = ExecutionScopes::new();
+        hint(&mut HintContext::new(
+            &mut memory,
+            registers,
+            &ids,
+            &mut scopes,
+        ))
+        .unwrap();
+        assert_eq!(
+            memory.read(Felt::from(40u64)),
+            Some(Felt::from(0x8c5e8c50u64))
+        );
+        assert_eq!(
+            memory.read(Felt::from(47u64)),
+            Some(Felt::from(0x82596786u64))
+        );
+    }
+}
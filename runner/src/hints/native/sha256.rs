@@ -0,0 +1,213 @@
+//! Native implementations of the hints used by `starkware.cairo.common.cairo_sha256.sha256`.
+
+use super::{felt_to_u32, felt_to_u64, felt_to_usize, HintContext, HintError};
+use giza_core::{Felt, FieldElement};
+
+pub const SHA256_INPUT: &str = r#"ids.full_word = int(ids.n_bytes >= 4)"#;
+
+pub const SHA256_MAIN: &str = r#"from starkware.cairo.common.cairo_sha256.sha256_utils import (
+    IV, compute_message_schedule, sha2_compress_function)
+
+_sha256_input_chunk_size_felts = int(ids.SHA256_INPUT_CHUNK_SIZE_FELTS)
+assert 0 <= _sha256_input_chunk_size_felts < 100
+
+w = compute_message_schedule(memory.get_range(
+    ids.sha256_start, _sha256_input_chunk_size_felts))
+new_state = sha2_compress_function(IV, w)
+segments.write_arg(ids.output, new_state)"#;
+
+pub const SHA256_FINALIZE: &str = r#"# Add dummy pairs of input and output.
+from starkware.cairo.common.cairo_sha256.sha256_utils import (
+    IV, compute_message_schedule, sha2_compress_function)
+
+_block_size = int(ids.BLOCK_SIZE)
+assert 0 <= _block_size < 20
+_sha256_input_chunk_size_felts = int(ids.SHA256_INPUT_CHUNK_SIZE_FELTS)
+assert 0 <= _sha256_input_chunk_size_felts < 100
+
+message = [0] * _sha256_input_chunk_size_felts
+w = compute_message_schedule(message)
+output = sha2_compress_function(IV, w)
+padding = (message + IV + output) * (_block_size - 1)
+segments.write_arg(ids.sha256_ptr_end, padding)"#;
+
+pub const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Number of felts in a sha256 message block
+const MESSAGE_WORDS: usize = 16;
+
+/// Expands a 16-word message block into the 64-word message schedule
+pub fn compute_message_schedule(message: &[u32; MESSAGE_WORDS]) -> [u32; 64] {
+    let mut w = [0u32; 64];
+    w[..MESSAGE_WORDS].copy_from_slice(message);
+    for i in MESSAGE_WORDS..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+    w
+}
+
+/// The sha256 compression function, applied to a state and an expanded message schedule
+pub fn sha2_compress_function(state: &[u32; 8], w: &[u32; 64]) -> [u32; 8] {
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let temp1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(ROUND_CONSTANTS[i])
+            .wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(temp1);
+        d = c;
+        c = b;
+        b = a;
+        a = temp1.wrapping_add(temp2);
+    }
+
+    let mut out = *state;
+    for (x, y) in out.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *x = x.wrapping_add(y);
+    }
+    out
+}
+
+/// Computes the sha256 digest of a byte string
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&(8 * data.len() as u64).to_be_bytes());
+
+    let mut state = IV;
+    for block in padded.chunks(64) {
+        let mut message = [0u32; MESSAGE_WORDS];
+        for (word, bytes) in message.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        state = sha2_compress_function(&state, &compute_message_schedule(&message));
+    }
+
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+fn to_felts(words: &[u32]) -> Vec<Felt> {
+    words.iter().map(|w| Felt::from(*w)).collect()
+}
+
+fn check_chunk_size(ctx: &HintContext) -> Result<(), HintError> {
+    let chunk_size = felt_to_usize(ctx.get_ids("SHA256_INPUT_CHUNK_SIZE_FELTS")?)?;
+    if chunk_size != MESSAGE_WORDS {
+        return Err(HintError::AssertionFailed(format!(
+            "unsupported sha256 input chunk size {}",
+            chunk_size
+        )));
+    }
+    Ok(())
+}
+
+pub fn sha256_input(ctx: &mut HintContext) -> Result<(), HintError> {
+    let n_bytes = felt_to_u64(ctx.get_ids("n_bytes")?)?;
+    ctx.set_ids("full_word", Felt::from((n_bytes >= 4) as u64))
+}
+
+pub fn sha256_main(ctx: &mut HintContext) -> Result<(), HintError> {
+    check_chunk_size(ctx)?;
+    let sha256_start = ctx.get_ids("sha256_start")?;
+    let mut message = [0u32; MESSAGE_WORDS];
+    for (word, value) in message
+        .iter_mut()
+        .zip(ctx.get_range(sha256_start, MESSAGE_WORDS)?)
+    {
+        *word = felt_to_u32(value)?;
+    }
+    let w = compute_message_schedule(&message);
+    let new_state = sha2_compress_function(&IV, &w);
+    let output = ctx.get_ids("output")?;
+    ctx.write_arg(output, &to_felts(&new_state))
+}
+
+pub fn sha256_finalize(ctx: &mut HintContext) -> Result<(), HintError> {
+    check_chunk_size(ctx)?;
+    let block_size = felt_to_usize(ctx.get_ids("BLOCK_SIZE")?)?;
+    if block_size >= 20 {
+        return Err(HintError::AssertionFailed(String::from(
+            "0 <= _block_size < 20",
+        )));
+    }
+
+    let message = [0u32; MESSAGE_WORDS];
+    let w = compute_message_schedule(&message);
+    let output = sha2_compress_function(&IV, &w);
+
+    let mut instance = to_felts(&message);
+    instance.extend(to_felts(&IV));
+    instance.extend(to_felts(&output));
+    let padding = instance.repeat(block_size.saturating_sub(1));
+
+    let sha256_ptr_end = ctx.get_ids("sha256_ptr_end")?;
+    ctx.write_arg(sha256_ptr_end, &padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_compress() {
+        // sha256("abc"), which fits in a single padded block
+        let mut message = [0u32; MESSAGE_WORDS];
+        message[0] = 0x61626380;
+        message[15] = 24;
+        let digest = sha2_compress_function(&IV, &compute_message_schedule(&message))
+            .iter()
+            .flat_map(|w| w.to_be_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hex::encode(digest),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            hex::encode(sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        // Two blocks
+        assert_eq!(
+            hex::encode(sha256(&[b'a'; 64])),
+            "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb"
+        );
+    }
+}
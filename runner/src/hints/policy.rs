@@ -0,0 +1,186 @@
+//! Whitelist of the hint code a run is allowed to execute. Hint code is compared after
+//! removing surrounding blank lines and trailing whitespace, either directly or through
+//! its sha256 hash. Hints dispatched to a native implementation and the structured hints
+//! of CASM programs never reach the Python interpreter, and are always allowed, except for
+//! the CASM hints that can't be executed natively.
+
+use super::{native, HintError, HintManager};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// Hint whitelist, as read from a JSON file of the form
+/// `{"hashes": ["<sha256 of normalized code>", ...], "code": ["<hint code>", ...]}`
+#[derive(Default, Deserialize)]
+pub struct HintPolicy {
+    #[serde(default)]
+    hashes: HashSet<String>,
+    #[serde(default)]
+    code: Vec<String>,
+}
+
+/// A hint that is not allowed by the policy
+#[derive(Debug)]
+pub struct PolicyViolation {
+    pub pc: u64,
+    pub hash: String,
+    pub code: String,
+}
+
+impl HintPolicy {
+    /// Reads a policy from a JSON file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Returns the hash identifying the given hint code in a policy
+    pub fn hash(code: &str) -> String {
+        hex::encode(native::sha256(native::normalize(code).as_bytes()))
+    }
+
+    /// Allows the given hint code
+    pub fn allow(&mut self, code: &str) {
+        self.hashes.insert(Self::hash(code));
+    }
+
+    /// Returns whether the policy lists the given hint code, regardless of whether it has a
+    /// native implementation
+    pub fn is_allowed(&self, code: &str) -> bool {
+        self.hashes.contains(&Self::hash(code))
+            || self
+                .code
+                .iter()
+                .any(|allowed| native::normalize(allowed) == native::normalize(code))
+    }
+
+    /// Checks every hint of a program against the policy, and returns all violations
+    pub fn check(&self, manager: &HintManager) -> Result<(), HintError> {
+        let mut violations = manager
+            .hints
+            .iter()
+            .flat_map(|(pc, hints)| {
+                hints
+                    .iter()
+                    .filter(|hint| match hint.casm() {
+                        Some(casm) => casm.unsupported().is_some(),
+                        None => hint.native().is_none(),
+                    })
+                    .map(move |hint| (*pc, hint.code()))
+            })
+            .filter(|(_, code)| !self.is_allowed(code))
+            .map(|(pc, code)| PolicyViolation {
+                pc,
+                hash: Self::hash(code),
+                code: code.to_string(),
+            })
+            .collect::<Vec<_>>();
+        if violations.is_empty() {
+            return Ok(());
+        }
+        violations.sort_by_key(|v| v.pc);
+        Err(HintError::PolicyViolation(violations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hints::Hint;
+
+    #[test]
+    fn test_policy() {
+        let mut manager = HintManager::default();
+        manager.push_hint(3, Hint::new(String::from("x = 1"), vec![], None));
+        manager.push_hint(
+            5,
+            Hint::new(String::from("\n  import os  \n"), vec![], None),
+        );
+        manager.push_hint(
+            7,
+            Hint::new(
+                String::from("ids.full_word = int(ids.n_bytes >= 4)"),
+                vec![],
+                None,
+            ),
+        );
+
+        let policy: HintPolicy = serde_json::from_str(r#"{"code": ["x = 1 "]}"#).unwrap();
+        match policy.check(&manager) {
+            Err(HintError::PolicyViolation(violations)) => {
+                assert_eq!(violations.len(), 1);
+                assert_eq!(violations[0].pc, 5);
+                assert_eq!(violations[0].hash, HintPolicy::hash("import os"));
+            }
+            _ => panic!("expected a policy violation"),
+        }
+
+        let mut policy = policy;
+        policy.allow("import os");
+        assert!(policy.check(&manager).is_ok());
+    }
+
+    #[test]
+    fn test_policy_native_hints() {
+        let code = "segments.write_arg(ids.inputs, [ids.low % 2 ** 64, ids.low // 2 ** 64])\n\
+            segments.write_arg(ids.inputs + 2, [ids.high % 2 ** 64, ids.high // 2 ** 64])";
+        let mut manager = HintManager::default();
+        manager.push_hint(3, Hint::new(String::from(code), vec![], None));
+        let policy = HintPolicy::default();
+        assert!(policy.check(&manager).is_ok());
+
+        // The same code without its native implementation runs in Python, so it must be
+        // whitelisted
+        let hint: Hint = serde_json::from_value(serde_json::json!({
+            "code": code,
+            "accessible_scopes": [],
+            "flow_tracking_data": null
+        }))
+        .unwrap();
+        assert!(hint.native().is_none());
+        manager.push_hint(5, hint);
+        match policy.check(&manager) {
+            Err(HintError::PolicyViolation(violations)) => {
+                assert_eq!(violations.len(), 1);
+                assert_eq!(violations[0].pc, 5);
+            }
+            _ => panic!("expected a policy violation"),
+        }
+    }
+
+    #[test]
+    fn test_policy_casm_hints() {
+        let mut manager = HintManager::default();
+        manager.push_hint(
+            3,
+            Hint::from_casm(&serde_json::json!({"AllocSegment": {
+                "dst": {"register": "AP", "offset": 0}
+            }})),
+        );
+        let policy = HintPolicy::default();
+        assert!(policy.check(&manager).is_ok());
+        assert!(manager.check_supported().is_ok());
+
+        // Hints that can't be executed natively are reported before the program runs
+        manager.push_hint(
+            5,
+            Hint::from_casm(&serde_json::json!({"SystemCall": {"system": {
+                "Deref": {"register": "FP", "offset": -3}
+            }}})),
+        );
+        match policy.check(&manager) {
+            Err(HintError::PolicyViolation(violations)) => {
+                assert_eq!(violations.len(), 1);
+                assert_eq!(violations[0].pc, 5);
+                assert!(violations[0].code.contains("SystemCall"));
+            }
+            _ => panic!("expected a policy violation"),
+        }
+        assert!(matches!(
+            manager.check_supported(),
+            Err(HintError::UnsupportedHint(name)) if name == "SystemCall at pc 5"
+        ));
+    }
+}
@@ -0,0 +1,322 @@
+//! Python objects exposed to hint code. `memory`, `segments` and `ids` are proxies backed by
+//! the Rust runner, and follow the semantics of their cairo-run counterparts (`MemoryDict`,
+//! `MemorySegmentManager` and `VmConsts`), except that addresses are plain integers: the
+//! segments allocated by hints have virtual base addresses until they are relocated after
+//! the run.
+
+use super::ids::IdsManager;
+use super::native::IdsAccess;
+use super::segments::Segments;
+use super::HintError;
+use crate::memory::Memory;
+use giza_core::{Felt, FieldElement, StarkField};
+use pyo3::exceptions::{PyAttributeError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyLong};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Returns the field prime as a Python int (`PRIME` in hints)
+pub fn prime(py: Python) -> PyResult<PyObject> {
+    let bytes = PyBytes::new(py, &Felt::get_modulus_le_bytes());
+    Ok(py
+        .get_type::<PyLong>()
+        .call_method1("from_bytes", (bytes, "little"))?
+        .into())
+}
+
+/// Converts a field element into a Python int
+pub fn felt_to_py(py: Python, x: Felt) -> PyResult<PyObject> {
+    let bytes = PyBytes::new(py, &x.as_int().to_le_bytes());
+    Ok(py
+        .get_type::<PyLong>()
+        .call_method1("from_bytes", (bytes, "little"))?
+        .into())
+}
+
+/// Converts a Python int into a field element, reducing it modulo the field prime
+pub fn py_to_felt(value: &PyAny) -> PyResult<Felt> {
+    let reduced = value.call_method1("__mod__", (prime(value.py())?,))?;
+    let bytes = reduced.call_method1("to_bytes", (32, "little"))?;
+    Felt::try_from(bytes.extract::<&[u8]>()?).map_err(PyValueError::new_err)
+}
+
+fn to_py_err(err: HintError) -> PyErr {
+    match err {
+        HintError::PythonError(err) => err,
+        HintError::UnknownIdentifier(name) => PyAttributeError::new_err(name),
+        HintError::UnknownMemoryCell(addr) => {
+            PyKeyError::new_err(format!("Unknown value for memory cell at address {}", addr))
+        }
+        HintError::InconsistentMemory {
+            addr,
+            current,
+            value,
+        } => PyValueError::new_err(format!(
+            "Inconsistent memory assignment at address {}. {} != {}.",
+            addr, current, value
+        )),
+        err => PyValueError::new_err(format!("{:?}", err)),
+    }
+}
+
+/// Writes a memory cell, failing if it already holds a different value
+fn write_cell(memory: &RefCell<Memory>, addr: Felt, value: Felt) -> PyResult<()> {
+    super::write_cell(&mut memory.borrow_mut(), addr, value).map_err(to_py_err)
+}
+
+// MEMORY
+// ================================================================================================
+
+/// The `memory` object of a hint
+#[pyclass(unsendable)]
+pub struct MemoryProxy {
+    memory: Rc<RefCell<Memory>>,
+}
+
+impl MemoryProxy {
+    pub fn new(memory: Rc<RefCell<Memory>>) -> Self {
+        Self { memory }
+    }
+
+    fn read(&self, addr: &PyAny) -> PyResult<Option<Felt>> {
+        Ok(self.memory.borrow().read(py_to_felt(addr)?))
+    }
+}
+
+#[pymethods]
+impl MemoryProxy {
+    fn __getitem__(&self, py: Python, addr: &PyAny) -> PyResult<PyObject> {
+        match self.read(addr)? {
+            Some(value) => felt_to_py(py, value),
+            None => Err(to_py_err(HintError::UnknownMemoryCell(py_to_felt(addr)?))),
+        }
+    }
+
+    fn __setitem__(&self, addr: &PyAny, value: &PyAny) -> PyResult<()> {
+        write_cell(&self.memory, py_to_felt(addr)?, py_to_felt(value)?)
+    }
+
+    fn __contains__(&self, addr: &PyAny) -> PyResult<bool> {
+        Ok(self.read(addr)?.is_some())
+    }
+
+    #[args(default = "None")]
+    fn get(&self, py: Python, addr: &PyAny, default: Option<PyObject>) -> PyResult<PyObject> {
+        match self.read(addr)? {
+            Some(value) => felt_to_py(py, value),
+            None => Ok(default.unwrap_or_else(|| py.None())),
+        }
+    }
+
+    fn get_range(&self, py: Python, addr: &PyAny, size: usize) -> PyResult<Vec<PyObject>> {
+        let addr = py_to_felt(addr)?;
+        (0..size as u64)
+            .map(|i| {
+                let cell = addr + Felt::from(i);
+                match self.memory.borrow().read(cell) {
+                    Some(value) => felt_to_py(py, value),
+                    None => Err(to_py_err(HintError::UnknownMemoryCell(cell))),
+                }
+            })
+            .collect()
+    }
+}
+
+// SEGMENTS
+// ================================================================================================
+
+/// The `segments` object of a hint
+#[pyclass(unsendable)]
+pub struct SegmentsProxy {
+    memory: Rc<RefCell<Memory>>,
+    segments: Rc<RefCell<Segments>>,
+}
+
+impl SegmentsProxy {
+    pub fn new(memory: Rc<RefCell<Memory>>, segments: Rc<RefCell<Segments>>) -> Self {
+        Self { memory, segments }
+    }
+
+    fn add_segment(&self) -> Felt {
+        self.segments
+            .borrow_mut()
+            .add(&mut self.memory.borrow_mut())
+    }
+
+    /// Iterables are written to a new segment and replaced by its base; other values are
+    /// converted to field elements
+    fn gen(&self, arg: &PyAny) -> PyResult<Felt> {
+        if arg.is_instance_of::<PyLong>()? {
+            return py_to_felt(arg);
+        }
+        match arg.iter() {
+            Ok(_) => {
+                let base = self.add_segment();
+                self.load_data(base, arg)?;
+                Ok(base)
+            }
+            Err(_) => py_to_felt(arg),
+        }
+    }
+
+    /// Writes the values of an iterable starting at `ptr`, and returns the address after
+    /// the last value written
+    fn load_data(&self, ptr: Felt, data: &PyAny) -> PyResult<Felt> {
+        let mut addr = ptr;
+        for item in data.iter()? {
+            let value = self.gen(item?)?;
+            write_cell(&self.memory, addr, value)?;
+            addr += Felt::ONE;
+        }
+        Ok(addr)
+    }
+}
+
+#[pymethods]
+impl SegmentsProxy {
+    fn add(&self, py: Python) -> PyResult<PyObject> {
+        felt_to_py(py, self.add_segment())
+    }
+
+    fn add_temp_segment(&self, py: Python) -> PyResult<PyObject> {
+        felt_to_py(py, self.add_segment())
+    }
+
+    fn write_arg(&self, py: Python, ptr: &PyAny, arg: &PyAny) -> PyResult<PyObject> {
+        felt_to_py(py, self.load_data(py_to_felt(ptr)?, arg)?)
+    }
+
+    fn gen_arg(&self, py: Python, arg: &PyAny) -> PyResult<PyObject> {
+        felt_to_py(py, self.gen(arg)?)
+    }
+}
+
+// IDS
+// ================================================================================================
+
+/// The `ids` object of a hint, or a struct reached through it (e.g. `ids.keccak_state`).
+/// Members of structs are accessed as attributes, and `address_` returns the address of
+/// the struct itself.
+#[pyclass(unsendable)]
+pub struct IdsProxy {
+    ids: Rc<IdsManager>,
+    memory: Rc<RefCell<Memory>>,
+    /// dotted path of the struct, or None for the `ids` object itself
+    path: Option<String>,
+}
+
+impl IdsProxy {
+    pub fn new(ids: Rc<IdsManager>, memory: Rc<RefCell<Memory>>) -> Self {
+        Self {
+            ids,
+            memory,
+            path: None,
+        }
+    }
+
+    fn member(&self, name: &str) -> String {
+        match &self.path {
+            Some(path) => format!("{}.{}", path, name),
+            None => name.to_string(),
+        }
+    }
+}
+
+#[pymethods]
+impl IdsProxy {
+    fn __getattr__(&self, py: Python, name: &str) -> PyResult<PyObject> {
+        let memory = self.memory.borrow();
+        if let (Some(path), "address_") = (&self.path, name) {
+            let addr = self.ids.struct_addr(path, &memory).map_err(to_py_err)?;
+            return felt_to_py(
+                py,
+                addr.expect("struct proxies are only created for structs"),
+            );
+        }
+
+        let path = self.member(name);
+        match self.ids.struct_addr(&path, &memory).map_err(to_py_err)? {
+            Some(_) => Ok(Py::new(
+                py,
+                IdsProxy {
+                    ids: self.ids.clone(),
+                    memory: self.memory.clone(),
+                    path: Some(path),
+                },
+            )?
+            .into_py(py)),
+            None => felt_to_py(py, self.ids.get(&path, &memory).map_err(to_py_err)?),
+        }
+    }
+
+    fn __setattr__(&self, name: &str, value: &PyAny) -> PyResult<()> {
+        let value = py_to_felt(value)?;
+        self.ids
+            .set(&self.member(name), value, &mut self.memory.borrow_mut())
+            .map_err(to_py_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn test_felt_conversion() {
+        Python::with_gil(|py| {
+            for x in [Felt::ZERO, Felt::from(7u64), -Felt::ONE] {
+                let value = felt_to_py(py, x).unwrap();
+                assert_eq!(py_to_felt(value.as_ref(py)).unwrap(), x);
+            }
+            let minus_one = py.eval("-1", None, None).unwrap();
+            assert_eq!(py_to_felt(minus_one).unwrap(), -Felt::ONE);
+        });
+    }
+
+    #[test]
+    fn test_memory_and_segments() {
+        let memory = Rc::new(RefCell::new(Memory::new(vec![Felt::from(3u64)])));
+        let segments = Rc::new(RefCell::new(Segments::new()));
+        Python::with_gil(|py| {
+            let globals = PyDict::new(py);
+            globals
+                .set_item(
+                    "memory",
+                    Py::new(py, MemoryProxy::new(memory.clone())).unwrap(),
+                )
+                .unwrap();
+            globals
+                .set_item(
+                    "segments",
+                    Py::new(py, SegmentsProxy::new(memory.clone(), segments.clone())).unwrap(),
+                )
+                .unwrap();
+            py.run(
+                r#"
+assert memory[1] == 3
+assert 2 not in memory and memory.get(2, 5) == 5
+memory[2] = memory[1] + 1
+ptr = segments.add()
+end = segments.write_arg(ptr, [10, [20, 30]])
+assert end == ptr + 2
+assert memory.get_range(memory[ptr + 1], 2) == [20, 30]
+try:
+    memory[2] = 0
+    assert False
+except ValueError:
+    pass
+"#,
+                Some(globals),
+                None,
+            )
+            .unwrap();
+        });
+        assert_eq!(
+            memory.borrow().read(Felt::from(2u64)),
+            Some(Felt::from(4u64))
+        );
+        assert_eq!(segments.borrow().bases().len(), 2);
+    }
+}
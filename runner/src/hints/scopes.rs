@@ -0,0 +1,148 @@
+//! Execution scopes, which hold the hint variables that persist across steps (e.g.
+//! `__dict_manager` or `n_elms`). Scopes follow the semantics of `vm_enter_scope` and
+//! `vm_exit_scope` in cairo-run: a new scope only sees the variables it was created with,
+//! and the main scope can never be exited.
+
+use super::HintError;
+use giza_core::{Felt, FieldHelpers};
+use pyo3::prelude::*;
+use std::any::Any;
+use std::collections::HashMap;
+
+/// A variable stored in an execution scope
+pub enum ScopeValue {
+    /// Value assigned by a native hint
+    Native(Box<dyn Any>),
+    /// Value assigned by a hint executed in the Python interpreter
+    Python(PyObject),
+}
+
+pub type Scope = HashMap<String, ScopeValue>;
+
+/// Stack of execution scopes. The bottom of the stack is the main scope.
+pub struct ExecutionScopes {
+    scopes: Vec<Scope>,
+}
+
+impl Default for ExecutionScopes {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope::new()],
+        }
+    }
+}
+
+impl ExecutionScopes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Equivalent of `vm_enter_scope(new_scope_locals)`
+    pub fn enter_scope(&mut self, locals: Scope) {
+        self.scopes.push(locals);
+    }
+
+    /// Equivalent of `vm_exit_scope()`
+    pub fn exit_scope(&mut self) -> Result<(), HintError> {
+        if self.scopes.len() == 1 {
+            return Err(HintError::ExitMainScope);
+        }
+        self.scopes.pop();
+        Ok(())
+    }
+
+    /// Number of scopes entered on top of the main scope
+    pub fn depth(&self) -> usize {
+        self.scopes.len() - 1
+    }
+
+    /// Returns an error if some scope entered during the run was never exited
+    pub fn check_balanced(&self) -> Result<(), HintError> {
+        match self.depth() {
+            0 => Ok(()),
+            depth => Err(HintError::UnbalancedScopes(depth)),
+        }
+    }
+
+    pub fn current(&self) -> &Scope {
+        self.scopes.last().expect("main scope is never exited")
+    }
+
+    pub fn current_mut(&mut self) -> &mut Scope {
+        self.scopes.last_mut().expect("main scope is never exited")
+    }
+
+    /// Returns a variable of the current scope that was set by a native hint
+    pub fn get<T: Any>(&self, name: &str) -> Option<&T> {
+        match self.current().get(name)? {
+            ScopeValue::Native(value) => value.downcast_ref::<T>(),
+            ScopeValue::Python(_) => None,
+        }
+    }
+
+    /// Mutable variant of [ExecutionScopes::get]
+    pub fn get_mut<T: Any>(&mut self, name: &str) -> Option<&mut T> {
+        match self.current_mut().get_mut(name)? {
+            ScopeValue::Native(value) => value.downcast_mut::<T>(),
+            ScopeValue::Python(_) => None,
+        }
+    }
+
+    /// Returns an integer variable of the current scope, regardless of whether it was set
+    /// by a native or a Python hint
+    pub fn get_u64(&self, name: &str) -> Result<Option<u64>, HintError> {
+        let invalid = || HintError::InvalidScopeValue(name.to_string());
+        match self.current().get(name) {
+            None => Ok(None),
+            Some(ScopeValue::Native(value)) => match native_to_felt(value.as_ref()) {
+                Some(x) if Felt::from(x.to_u64()) == x => Ok(Some(x.to_u64())),
+                _ => Err(invalid()),
+            },
+            Some(ScopeValue::Python(value)) => {
+                Python::with_gil(|py| value.extract::<u64>(py).map(Some).map_err(|_| invalid()))
+            }
+        }
+    }
+
+    /// Assigns a variable of the current scope
+    pub fn insert<T: Any>(&mut self, name: &str, value: T) {
+        self.current_mut()
+            .insert(name.to_string(), ScopeValue::Native(Box::new(value)));
+    }
+}
+
+/// Converts native integer values to field elements
+pub(crate) fn native_to_felt(value: &dyn Any) -> Option<Felt> {
+    if let Some(x) = value.downcast_ref::<Felt>() {
+        Some(*x)
+    } else if let Some(x) = value.downcast_ref::<u64>() {
+        Some(Felt::from(*x))
+    } else {
+        value.downcast_ref::<usize>().map(|x| Felt::from(*x as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes() {
+        let mut scopes = ExecutionScopes::new();
+        scopes.insert("n_elms", 3u64);
+        assert_eq!(scopes.get_u64("n_elms").unwrap(), Some(3));
+
+        // Variables of outer scopes are not visible in a new scope
+        scopes.enter_scope(Scope::new());
+        assert!(scopes.get::<u64>("n_elms").is_none());
+        assert!(matches!(
+            scopes.check_balanced(),
+            Err(HintError::UnbalancedScopes(1))
+        ));
+
+        scopes.exit_scope().unwrap();
+        assert_eq!(scopes.get::<u64>("n_elms"), Some(&3));
+        assert!(scopes.check_balanced().is_ok());
+        assert!(matches!(scopes.exit_scope(), Err(HintError::ExitMainScope)));
+    }
+}
@@ -0,0 +1,69 @@
+//! Allocation of the memory segments created by hints (e.g. `segments.add()` in `alloc`).
+//!
+//! As in cairo-run, segments are relocatable: a new segment has a virtual base address
+//! outside of flat memory, and grows as cells are written to it. Once the run is over,
+//! [Memory::relocate] places the segments after all other memory, so that they overlap
+//! neither each other nor the execution segment.
+
+use crate::memory::Memory;
+use giza_core::Felt;
+
+#[derive(Default)]
+pub struct Segments {
+    /// base addresses of all segments allocated so far
+    bases: Vec<Felt>,
+}
+
+impl Segments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates a new segment and returns its base address
+    pub fn add(&mut self, memory: &mut Memory) -> Felt {
+        let base = memory.add_segment();
+        self.bases.push(base);
+        base
+    }
+
+    /// Base addresses of all segments allocated so far
+    pub fn bases(&self) -> &[Felt] {
+        &self.bases
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use giza_core::FieldElement;
+
+    #[test]
+    fn test_segments() {
+        let mut memory = Memory::new(vec![Felt::from(1u64); 10]);
+        let mut segments = Segments::new();
+        let a = segments.add(&mut memory);
+        let b = segments.add(&mut memory);
+
+        // Segments grow past any fixed capacity, and so does the execution segment
+        for i in 0..5000u64 {
+            memory.write(a + Felt::from(i), Felt::from(i));
+        }
+        memory.write(b, a + Felt::from(4999u64));
+        for addr in 11..5011u64 {
+            memory.write(Felt::from(addr), Felt::ONE);
+        }
+        memory.write(Felt::from(5011u64), b);
+
+        // Segments are relocated after all other memory, and pointers to them follow
+        let (bases, pointers) = memory.relocate();
+        assert_eq!(bases, vec![5012, 10012]);
+        assert_eq!(pointers, vec![5011, 10012]);
+        assert_eq!(memory.read(Felt::from(5011u64)), Some(Felt::from(10012u64)));
+        assert_eq!(
+            memory.read(Felt::from(10012u64)),
+            Some(Felt::from(10011u64))
+        );
+        assert_eq!(memory.read(Felt::from(10011u64)), Some(Felt::from(4999u64)));
+        assert_eq!(segments.bases().len(), 2);
+    }
+}
@@ -7,10 +7,25 @@ pub use runner::Program;
 #[cfg(feature = "hints")]
 pub mod hints;
 
+pub mod pie;
+pub use pie::CairoPie;
+
+pub mod tape;
+pub use tape::HintTape;
+
 mod trace;
 pub use trace::ExecutionTrace;
 
+pub mod export;
+pub use export::{TraceFormat, TraceTable};
+
 mod errors;
-pub use errors::ExecutionError;
+pub use errors::{ExecutionError, ReadError};
 
 mod cairo_interop;
+#[cfg(feature = "hints")]
+pub use cairo_interop::read_hints;
+pub use cairo_interop::{
+    load_program, read_air_private_input, read_air_public_input, write_air_public_input,
+    AirPrivateInput, AirPublicInput, CasmProgram, EntryPoint, EntryPoints,
+};
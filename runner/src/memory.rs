@@ -7,6 +7,52 @@ use std::ops::{Index, IndexMut};
 use core::iter::repeat;
 use giza_core::{Felt, FieldHelpers, StarkField, Word};
 
+/// Upper limbs of the addresses of the segments allocated by hints, before relocation. The
+/// cell at offset `o` of the `i`-th segment has address `[o, i, SEGMENT_TAG]` (in 64-bit
+/// limbs), which is out of the range of flat addresses.
+const SEGMENT_TAG: [u64; 2] = [0x7365676d656e7473, 0x67697a61];
+
+/// Location of a memory cell
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Address {
+    /// address in flat memory
+    Flat(u64),
+    /// segment index and offset of a cell in a segment allocated by a hint, which is
+    /// not relocated yet
+    Segment(usize, u64),
+}
+
+impl From<Felt> for Address {
+    fn from(addr: Felt) -> Self {
+        let limbs = addr.as_int().0;
+        if limbs[2..] == SEGMENT_TAG && limbs[1] <= u32::MAX as u64 {
+            Address::Segment(limbs[1] as usize, limbs[0])
+        } else {
+            Address::Flat(limbs[0])
+        }
+    }
+}
+
+impl From<Address> for Felt {
+    fn from(addr: Address) -> Self {
+        match addr {
+            Address::Flat(addr) => Felt::from(addr),
+            Address::Segment(index, offset) => {
+                Felt::from([offset, index as u64, SEGMENT_TAG[0], SEGMENT_TAG[1]])
+            }
+        }
+    }
+}
+
+/// Returns the flat address of a cell in a relocated segment, given the base addresses of
+/// the segments, and any other value as is
+pub fn relocate_value(value: Felt, bases: &[u64]) -> Felt {
+    match Address::from(value) {
+        Address::Segment(index, offset) if index < bases.len() => Felt::from(bases[index] + offset),
+        _ => value,
+    }
+}
+
 /// This data structure stores the memory of the program
 #[derive(Clone)]
 pub struct Memory {
@@ -14,21 +60,37 @@ pub struct Memory {
     codelen: usize,
     /// full memory vector, None if non initialized
     pub data: Vec<Option<Word>>,
+    /// segments allocated by hints, until they are relocated after the flat memory
+    segments: Vec<Vec<Option<Word>>>,
+    /// log of writes, if logging is enabled
+    log: Option<Vec<(Felt, Felt)>>,
 }
 
 impl Index<Felt> for Memory {
     type Output = Option<Word>;
     fn index(&self, idx: Felt) -> &Self::Output {
-        let addr: u64 = idx.to_u64();
-        &self.data[addr as usize]
+        self.get(idx).unwrap_or(&None)
     }
 }
 
 impl IndexMut<Felt> for Memory {
     fn index_mut(&mut self, idx: Felt) -> &mut Self::Output {
-        let addr: u64 = idx.to_u64();
-        self.resize(addr);
-        &mut self.data[addr as usize]
+        match Address::from(idx) {
+            Address::Flat(addr) => {
+                self.resize(addr);
+                &mut self.data[addr as usize]
+            }
+            Address::Segment(index, offset) => {
+                if self.segments.len() <= index {
+                    self.segments.resize(index + 1, vec![]);
+                }
+                let segment = &mut self.segments[index];
+                if segment.len() <= offset as usize {
+                    segment.resize(offset as usize + 1, None);
+                }
+                &mut segment[offset as usize]
+            }
+        }
     }
 }
 
@@ -57,6 +119,8 @@ impl Memory {
         Memory {
             codelen: aux.len(),
             data: aux.into_iter().map(|i| Some(Word::new(i))).collect(),
+            segments: vec![],
+            log: None,
         }
     }
 
@@ -84,9 +148,23 @@ impl Memory {
 
     /// Write u64 element in memory address
     pub fn write(&mut self, addr: Felt, elem: Felt) {
+        if let Some(log) = self.log.as_mut() {
+            log.push((addr, elem));
+        }
         self[addr] = Some(Word::new(elem));
     }
 
+    /// Starts logging all subsequent writes
+    pub fn start_log(&mut self) {
+        self.log = Some(vec![]);
+    }
+
+    /// Stops logging writes and returns the (address, value) pairs written since
+    /// logging was started
+    pub fn take_log(&mut self) -> Vec<(Felt, Felt)> {
+        self.log.take().unwrap_or_default()
+    }
+
     /// Write u64 element in memory address
     pub fn write_pub(&mut self, addr: Felt, elem: Felt) {
         self.write(addr, elem);
@@ -95,8 +173,64 @@ impl Memory {
 
     /// Read element in memory address
     pub fn read(&self, addr: Felt) -> Option<Felt> {
-        //self.resize(addr.to_u64()); // Resize if necessary
-        self[addr].map(|x| x.word())
+        self.get(addr).copied().flatten().map(|x| x.word())
+    }
+
+    /// Returns the cell at the given address, if it is within allocated memory
+    fn get(&self, addr: Felt) -> Option<&Option<Word>> {
+        match Address::from(addr) {
+            Address::Flat(addr) => self.data.get(addr as usize),
+            Address::Segment(index, offset) => self
+                .segments
+                .get(index)
+                .and_then(|segment| segment.get(offset as usize)),
+        }
+    }
+
+    /// Allocates a new segment and returns its base address. Segments grow as cells are
+    /// written to them, and are only placed in flat memory by [relocate](Self::relocate).
+    pub fn add_segment(&mut self) -> Felt {
+        self.segments.push(vec![]);
+        Felt::from(Address::Segment(self.segments.len() - 1, 0))
+    }
+
+    /// Places the segments allocated by hints one after the other after all flat memory,
+    /// and replaces the pointers to them by flat addresses. Returns the base addresses of
+    /// the segments, and the addresses of the cells holding pointers to them.
+    pub fn relocate(&mut self) -> (Vec<u64>, Vec<u64>) {
+        let segments = std::mem::take(&mut self.segments);
+        // Pointers may refer to segments that were never written to
+        let num_segments = self
+            .data
+            .iter()
+            .chain(segments.iter().flatten())
+            .filter_map(|cell| match Address::from((*cell)?.word()) {
+                Address::Segment(index, _) => Some(index + 1),
+                Address::Flat(_) => None,
+            })
+            .fold(segments.len(), usize::max);
+
+        let mut bases = vec![];
+        let mut base = self.size();
+        for index in 0..num_segments {
+            bases.push(base);
+            base += segments
+                .get(index)
+                .map_or(0, |segment| segment.len() as u64);
+        }
+        self.data.extend(segments.into_iter().flatten());
+
+        let mut pointers = vec![];
+        for (addr, cell) in self.data.iter_mut().enumerate() {
+            if let Some(word) = cell {
+                let value = relocate_value(word.word(), &bases);
+                if value != word.word() {
+                    *cell = Some(Word::new(value));
+                    pointers.push(addr as u64);
+                }
+            }
+        }
+        (bases, pointers)
     }
 
     /// Returns a list of all memory holes (defined as missing private memory
@@ -0,0 +1,603 @@
+//! Cairo PIE (Position Independent Execution) archives, which hold the program, memory and
+//! execution resources of a run without the trace. The memory of a PIE is not relocated:
+//! addresses (and pointers stored in memory) are given as a segment index and an offset
+//! within the segment.
+//!
+//! A PIE is a zip archive with the following files:
+//! - metadata.json: the program and the index and size of each segment
+//! - memory.bin: the memory cells, encoded as in the memory dumps of the cairo-runner,
+//!   except that relocatable values have their most significant bit set, followed by the
+//!   segment index and the offset
+//! - execution_resources.json: the number of steps and builtin instances of the run
+//! - additional_data.json: builtin specific data (e.g. output pages)
+//! - version.json: the version of the PIE format
+
+use crate::cairo_interop::{felt_to_hex, le_u64, RecordReader, MEMORY_RECORD_SIZE};
+use crate::errors::ReadError;
+use crate::memory::{Address, Memory};
+use giza_core::{
+    flags::{OP1_VAL, OPC_AEQ, OPC_CALL, RES_ADD, RES_ONE},
+    Builtin, Felt, FieldElement, FieldHelpers, FlagGroupDecomposition, InstructionState,
+    MemorySegment, RegisterState, SegmentTable, StarkField, Word,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+pub const METADATA_FILENAME: &str = "metadata.json";
+pub const MEMORY_FILENAME: &str = "memory.bin";
+pub const EXECUTION_RESOURCES_FILENAME: &str = "execution_resources.json";
+pub const ADDITIONAL_DATA_FILENAME: &str = "additional_data.json";
+pub const VERSION_FILENAME: &str = "version.json";
+
+/// Version of the PIE format written by native runs
+const PIE_VERSION: &str = "1.0";
+
+/// Prime of the field of Cairo programs
+const PRIME: &str = "0x800000000000011000000000000000000000000000000000000000000000001";
+
+/// Number of bits used to encode the offset of a relocatable value
+const OFFSET_BITS: u32 = 47;
+
+/// Number of bits used to encode the segment index of a relocatable value
+const SEGMENT_BITS: u32 = 16;
+
+/// Program of a PIE, stripped of its hints and debug information
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StrippedProgram {
+    pub data: Vec<String>,
+    pub builtins: Vec<String>,
+    pub main: u64,
+    pub prime: String,
+}
+
+/// Index and size of a memory segment
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SegmentInfo {
+    pub index: usize,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PieMetadata {
+    pub program: StrippedProgram,
+    pub program_segment: SegmentInfo,
+    pub execution_segment: SegmentInfo,
+    pub ret_fp_segment: SegmentInfo,
+    pub ret_pc_segment: SegmentInfo,
+    pub builtin_segments: BTreeMap<String, SegmentInfo>,
+    pub extra_segments: Vec<SegmentInfo>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionResources {
+    pub n_steps: usize,
+    pub n_memory_holes: usize,
+    pub builtin_instance_counter: BTreeMap<String, usize>,
+}
+
+/// A field element, or an address given as a segment index and an offset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaybeRelocatable {
+    Int(Felt),
+    Relocatable(usize, u64),
+}
+
+pub struct CairoPie {
+    pub metadata: PieMetadata,
+    /// memory cells, with addresses given as a segment index and an offset
+    pub memory: Vec<((usize, u64), MaybeRelocatable)>,
+    pub execution_resources: ExecutionResources,
+    pub additional_data: serde_json::Value,
+    pub version: serde_json::Value,
+}
+
+/// Memory and initial state of a PIE, after its segments have been placed in memory
+pub struct RelocatedPie {
+    pub memory: Memory,
+    /// register state at the start of the run
+    pub init: RegisterState,
+    /// value of pc once main returns
+    pub end_pc: Felt,
+    /// number of steps of the run
+    pub n_steps: usize,
+    pub builtins: Vec<Builtin>,
+    /// public memory: the program, the initial stack and the output
+    pub public_mem: (Vec<u64>, Vec<Option<Word>>),
+    /// relocated program, execution and builtin segments
+    pub segments: SegmentTable,
+}
+
+impl CairoPie {
+    /// Reads a PIE from a zip archive
+    pub fn read_file(path: &Path) -> Result<Self, ReadError> {
+        let file = File::open(path).map_err(|source| ReadError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let mut archive =
+            ZipArchive::new(BufReader::new(file)).map_err(|err| invalid(path, err))?;
+
+        let metadata = read_entry(path, &mut archive, METADATA_FILENAME)?;
+        let execution_resources = read_entry(path, &mut archive, EXECUTION_RESOURCES_FILENAME)?;
+        let additional_data = read_entry(path, &mut archive, ADDITIONAL_DATA_FILENAME)?;
+        let version = read_entry(path, &mut archive, VERSION_FILENAME)?;
+
+        let memory_path = path.join(MEMORY_FILENAME);
+        let entry = archive
+            .by_name(MEMORY_FILENAME)
+            .map_err(|err| invalid(path, err))?;
+        let len = entry.size();
+        let mut reader = RecordReader::<_, MEMORY_RECORD_SIZE>::new(&memory_path, entry, len)?;
+        let mut memory = Vec::with_capacity(reader.num_records());
+        let mut byte = 0;
+        while let Some(record) = reader.next_record()? {
+            let address = match decode_value(&record[0..8]) {
+                MaybeRelocatable::Relocatable(segment, offset) => (segment, offset),
+                MaybeRelocatable::Int(_) => {
+                    return Err(invalid(
+                        &memory_path,
+                        format!("address at byte {} is not relocatable", byte),
+                    ))
+                }
+            };
+            memory.push((address, decode_value(&record[8..])));
+            byte += MEMORY_RECORD_SIZE;
+        }
+
+        Ok(Self {
+            metadata,
+            memory,
+            execution_resources,
+            additional_data,
+            version,
+        })
+    }
+
+    /// Places the segments of the PIE one after the other in memory (starting at address
+    /// 1, as the cairo-runner does), and resolves all relocatable values. The return fp and
+    /// pc of main are placed at the end of memory. Returns an error message if the segments
+    /// of the memory do not match the metadata.
+    pub fn relocate(&self) -> Result<RelocatedPie, String> {
+        let metadata = &self.metadata;
+        let mut segments = vec![
+            metadata.program_segment,
+            metadata.execution_segment,
+            metadata.ret_fp_segment,
+            metadata.ret_pc_segment,
+        ];
+        segments.extend(metadata.builtin_segments.values());
+        segments.extend(metadata.extra_segments.iter());
+        segments.sort_by_key(|segment| segment.index);
+
+        let mut bases = vec![None; segments.len()];
+        let mut next = 1;
+        for segment in segments.iter() {
+            match bases.get_mut(segment.index) {
+                Some(base @ None) => *base = Some(next),
+                _ => return Err(format!("invalid segment index {}", segment.index)),
+            }
+            next += segment.size;
+        }
+        // The return segments are empty, so main returns past the end of memory
+        let end = next;
+        bases[metadata.ret_fp_segment.index] = Some(end);
+        bases[metadata.ret_pc_segment.index] = Some(end);
+        let bases = bases.into_iter().map(Option::unwrap).collect::<Vec<_>>();
+        let relocate = |segment: usize, offset: u64| match bases.get(segment) {
+            Some(base) => Ok(base + offset),
+            None => Err(format!("unknown segment index {}", segment)),
+        };
+
+        let mut memory = Memory::new(vec![]);
+        for ((segment, offset), value) in self.memory.iter() {
+            let value = match *value {
+                MaybeRelocatable::Int(value) => value,
+                MaybeRelocatable::Relocatable(segment, offset) => {
+                    Felt::from(relocate(segment, offset)?)
+                }
+            };
+            memory.write(Felt::from(relocate(*segment, *offset)?), value);
+        }
+
+        // The initial stack holds the builtin pointers, and the return fp and pc
+        let program_base = bases[metadata.program_segment.index];
+        let execution_base = bases[metadata.execution_segment.index];
+        let ap = execution_base + metadata.program.builtins.len() as u64 + 2;
+        let init = RegisterState::new(program_base + metadata.program.main, ap, ap);
+
+        let mut public_addrs = (program_base..program_base + metadata.program_segment.size)
+            .chain(execution_base..ap)
+            .collect::<Vec<_>>();
+        let mut builtins = vec![];
+        if let Some(output) = metadata.builtin_segments.get("output") {
+            let base = bases[output.index];
+            public_addrs.extend(base..base + output.size);
+            builtins.push(Builtin::Output(output.size));
+        }
+        let public_values = public_addrs
+            .iter()
+            .map(|&addr| memory.read(Felt::from(addr)).map(Word::new))
+            .collect::<Vec<_>>();
+        if public_values.iter().any(Option::is_none) {
+            return Err("public memory is not fully assigned".to_string());
+        }
+
+        let segment = |info: SegmentInfo| {
+            let base = bases[info.index];
+            MemorySegment::new(base, base + info.size)
+        };
+        let segments = SegmentTable {
+            program: segment(metadata.program_segment),
+            execution: segment(metadata.execution_segment),
+            builtins: metadata
+                .program
+                .builtins
+                .iter()
+                .filter_map(|name| {
+                    let info = metadata.builtin_segments.get(name)?;
+                    Some((name.clone(), segment(*info)))
+                })
+                .collect(),
+        };
+
+        Ok(RelocatedPie {
+            memory,
+            init,
+            end_pc: Felt::from(end),
+            n_steps: self.execution_resources.n_steps,
+            builtins,
+            public_mem: (public_addrs, public_values),
+            segments,
+        })
+    }
+
+    /// Builds the PIE of a native run from its final memory. Native memory is flat, so the
+    /// segments are recovered from the layout of [load_program](crate::load_program):
+    /// - the program segment holds the bytecode followed by the end loop, from address 1 up
+    ///   to the initial stack
+    /// - the execution segment starts with the return fp and pc of main (which are mapped
+    ///   to the return segments), and extends up to the first segment allocated by a hint
+    /// - segments allocated by hints follow, in order of allocation
+    ///
+    /// Memory values are exported as relocatable if they were identified as pointers by
+    /// the [PointerTracker] of the run.
+    pub fn from_native_run(
+        memory: &Memory,
+        init: RegisterState,
+        n_steps: usize,
+        pointers: &PointerTracker,
+    ) -> Self {
+        let execution_base = init.fp.to_u64() - 2;
+        let mut begins = vec![1, execution_base];
+        begins.extend(pointers.segment_bases());
+        // Segment indices follow the cairo-runner: program, execution, return fp, return pc,
+        // then the segments allocated by hints
+        let index = |k: usize| if k < 2 { k } else { k + 2 };
+        let locate = |addr: u64| match begins.partition_point(|&begin| begin <= addr) {
+            0 => None,
+            k => Some((index(k - 1), addr - begins[k - 1])),
+        };
+
+        let mut cells = vec![];
+        let mut sizes = vec![0; begins.len()];
+        let mut assigned = vec![0; begins.len()];
+        for addr in 1..memory.size() {
+            let value = match memory.read(Felt::from(addr)) {
+                Some(value) => value,
+                None => continue,
+            };
+            let k = begins.partition_point(|&begin| begin <= addr) - 1;
+            sizes[k] = addr - begins[k] + 1;
+            assigned[k] += 1;
+            let value = if addr == execution_base {
+                MaybeRelocatable::Relocatable(2, 0)
+            } else if addr == execution_base + 1 {
+                MaybeRelocatable::Relocatable(3, 0)
+            } else if pointers.is_pointer(Felt::from(addr), Some(value)) {
+                match locate(value.to_u64()) {
+                    Some((segment, offset)) => MaybeRelocatable::Relocatable(segment, offset),
+                    None => MaybeRelocatable::Int(value),
+                }
+            } else {
+                MaybeRelocatable::Int(value)
+            };
+            cells.push(((index(k), addr - begins[k]), value));
+        }
+        // The program segment extends up to the initial stack, even if it ends with holes
+        sizes[0] = execution_base - 1;
+
+        let segment = |k: usize| SegmentInfo {
+            index: index(k),
+            size: sizes[k],
+        };
+        let program = StrippedProgram {
+            data: (1..execution_base)
+                .map(|addr| felt_to_hex(memory.read(Felt::from(addr)).unwrap_or(Felt::ZERO)))
+                .collect(),
+            builtins: vec![],
+            main: init.pc.to_u64() - 1,
+            prime: PRIME.to_string(),
+        };
+        let metadata = PieMetadata {
+            program,
+            program_segment: segment(0),
+            execution_segment: segment(1),
+            ret_fp_segment: SegmentInfo { index: 2, size: 0 },
+            ret_pc_segment: SegmentInfo { index: 3, size: 0 },
+            builtin_segments: BTreeMap::new(),
+            extra_segments: (2..begins.len()).map(segment).collect(),
+        };
+        let n_memory_holes = (1..begins.len())
+            .map(|k| sizes[k] as usize - assigned[k])
+            .sum();
+
+        Self {
+            metadata,
+            memory: cells,
+            execution_resources: ExecutionResources {
+                n_steps,
+                n_memory_holes,
+                builtin_instance_counter: BTreeMap::new(),
+            },
+            additional_data: serde_json::json!({}),
+            version: serde_json::json!({ "cairo_pie": PIE_VERSION }),
+        }
+    }
+
+    /// Writes the PIE to a zip archive
+    pub fn write_file(&self, path: &Path) -> io::Result<()> {
+        let mut zip = ZipWriter::new(BufWriter::new(File::create(path)?));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let files = [
+            (METADATA_FILENAME, serde_json::to_value(&self.metadata)?),
+            (
+                EXECUTION_RESOURCES_FILENAME,
+                serde_json::to_value(&self.execution_resources)?,
+            ),
+            (ADDITIONAL_DATA_FILENAME, self.additional_data.clone()),
+            (VERSION_FILENAME, self.version.clone()),
+        ];
+        for (name, value) in files.iter() {
+            zip.start_file(*name, options)?;
+            serde_json::to_writer(&mut zip, value)?;
+        }
+
+        zip.start_file(MEMORY_FILENAME, options)?;
+        for ((segment, offset), value) in self.memory.iter() {
+            zip.write_all(&encode_value(
+                MaybeRelocatable::Relocatable(*segment, *offset),
+                8,
+            ))?;
+            zip.write_all(&encode_value(*value, 32))?;
+        }
+        zip.finish()?.flush()
+    }
+}
+
+// POINTER TRACKING
+// ================================================================================================
+
+/// Identifies the memory cells that hold pointers during a native run, so that they can be
+/// exported as relocatable values. This is done on a best effort basis, by following how
+/// pointers are created and copied:
+/// - `call` stores the frame pointer and the return address
+/// - hints allocate segments, whose base addresses are pointers
+/// - assert-equal instructions copy pointers, or add offsets to them
+#[derive(Default)]
+pub struct PointerTracker {
+    cells: HashSet<Address>,
+    /// base addresses of the segments allocated by hints, once they are relocated
+    bases: Vec<u64>,
+}
+
+impl PointerTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the cell at the given address holds a pointer. Before relocation,
+    /// pointers to the segments allocated by hints are recognized by their value.
+    pub fn is_pointer(&self, addr: Felt, value: Option<Felt>) -> bool {
+        self.cells.contains(&Address::from(addr))
+            || matches!(value.map(Address::from), Some(Address::Segment(..)))
+    }
+
+    /// Follows the relocation of the segments allocated by hints, given the base
+    /// addresses of the segments and the cells holding pointers to them
+    pub fn relocate(&mut self, bases: Vec<u64>, pointers: Vec<u64>) {
+        self.cells = self
+            .cells
+            .drain()
+            .map(|cell| match cell {
+                Address::Segment(index, offset) => Address::Flat(bases[index] + offset),
+                cell => cell,
+            })
+            .chain(pointers.into_iter().map(Address::Flat))
+            .collect();
+        self.bases = bases;
+    }
+
+    pub fn segment_bases(&self) -> &[u64] {
+        &self.bases
+    }
+
+    /// Follows the pointers created or copied by an executed instruction
+    pub fn step(&mut self, curr: RegisterState, inst: &InstructionState) {
+        match inst.inst.opcode() {
+            OPC_CALL => {
+                self.cells.insert(Address::from(curr.ap));
+                self.cells.insert(Address::from(curr.ap + Felt::ONE));
+            }
+            OPC_AEQ => {
+                let is_pointer = |addr: Felt, value: Option<Felt>| self.is_pointer(addr, value);
+                let op1_imm = inst.inst.op1_src() == OP1_VAL;
+                let op0 = is_pointer(inst.op0_addr, inst.op0);
+                let op1 = !op1_imm && is_pointer(inst.op1_addr, inst.op1);
+                let dst = is_pointer(inst.dst_addr, inst.dst);
+                match inst.inst.res_log() {
+                    RES_ONE if op1 => self.cells.insert(Address::from(inst.dst_addr)),
+                    RES_ONE if dst && !op1_imm => self.cells.insert(Address::from(inst.op1_addr)),
+                    RES_ADD if op0 != op1 => self.cells.insert(Address::from(inst.dst_addr)),
+                    _ => false,
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+fn invalid<E: ToString>(path: &Path, err: E) -> ReadError {
+    ReadError::InvalidPie {
+        path: path.to_path_buf(),
+        message: err.to_string(),
+    }
+}
+
+fn read_entry<T: DeserializeOwned, R: Read + Seek>(
+    path: &Path,
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<T, ReadError> {
+    let entry = archive.by_name(name).map_err(|err| invalid(path, err))?;
+    serde_json::from_reader(entry).map_err(|source| ReadError::InvalidJson {
+        path: path.join(name),
+        source,
+    })
+}
+
+/// Decodes a little-endian value of a PIE memory dump (an address or a memory value)
+pub fn decode_value(bytes: &[u8]) -> MaybeRelocatable {
+    let msb = bytes[bytes.len() - 1] & 0x80 != 0;
+    if !msb {
+        let mut value = [0u8; 32];
+        value[..bytes.len()].copy_from_slice(bytes);
+        return MaybeRelocatable::Int(Felt::from(value));
+    }
+    let low = le_u64(&bytes[0..8]);
+    let segment = (low >> OFFSET_BITS) & ((1 << SEGMENT_BITS) - 1);
+    let offset = low & ((1 << OFFSET_BITS) - 1);
+    MaybeRelocatable::Relocatable(segment as usize, offset)
+}
+
+/// Encodes a value in the format of a PIE memory dump, using `n` bytes
+pub fn encode_value(value: MaybeRelocatable, n: usize) -> Vec<u8> {
+    match value {
+        MaybeRelocatable::Int(value) => value.as_int().to_le_bytes()[..n].to_vec(),
+        MaybeRelocatable::Relocatable(segment, offset) => {
+            let mut bytes = vec![0u8; n];
+            let encoded = ((segment as u64) << OFFSET_BITS) | offset;
+            bytes[0..8].copy_from_slice(&encoded.to_le_bytes());
+            bytes[n - 1] |= 0x80;
+            bytes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::Step;
+
+    fn segment(index: usize, size: u64) -> SegmentInfo {
+        SegmentInfo { index, size }
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let mut address = ((3u64 << OFFSET_BITS) | 7).to_le_bytes();
+        address[7] |= 0x80;
+        assert_eq!(decode_value(&address), MaybeRelocatable::Relocatable(3, 7));
+        let mut value = [0u8; 32];
+        value[0] = 5;
+        assert_eq!(
+            decode_value(&value),
+            MaybeRelocatable::Int(Felt::from(5u64))
+        );
+        value[31] |= 0x80;
+        assert_eq!(decode_value(&value), MaybeRelocatable::Relocatable(0, 5));
+    }
+
+    #[test]
+    fn test_relocate() {
+        let int = |x: u64| MaybeRelocatable::Int(Felt::from(x));
+        let pie = CairoPie {
+            metadata: PieMetadata {
+                program: StrippedProgram {
+                    data: vec!["0x1".to_string(), "0x2".to_string()],
+                    builtins: vec!["output".to_string()],
+                    main: 1,
+                    prime: "0x0".to_string(),
+                },
+                program_segment: segment(0, 2),
+                execution_segment: segment(1, 4),
+                ret_fp_segment: segment(3, 0),
+                ret_pc_segment: segment(4, 0),
+                builtin_segments: [("output".to_string(), segment(2, 1))]
+                    .into_iter()
+                    .collect(),
+                extra_segments: vec![],
+            },
+            memory: vec![
+                ((0, 0), int(1)),
+                ((0, 1), int(2)),
+                ((1, 0), MaybeRelocatable::Relocatable(2, 0)),
+                ((1, 1), MaybeRelocatable::Relocatable(3, 0)),
+                ((1, 2), MaybeRelocatable::Relocatable(4, 0)),
+                ((2, 0), int(9)),
+            ],
+            execution_resources: ExecutionResources::default(),
+            additional_data: serde_json::Value::Null,
+            version: serde_json::Value::Null,
+        };
+        let relocated = pie.relocate().unwrap();
+
+        // program at 1, execution at 3, output at 7 and the return segments at 8
+        assert_eq!(relocated.init.pc, Felt::from(2u64));
+        assert_eq!(relocated.init.ap, Felt::from(6u64));
+        assert_eq!(relocated.end_pc, Felt::from(8u64));
+        let read = |addr: u64| relocated.memory.read(Felt::from(addr)).unwrap();
+        assert_eq!(read(3), Felt::from(7u64));
+        assert_eq!(read(5), Felt::from(8u64));
+        assert_eq!(relocated.public_mem.0, vec![1, 2, 3, 4, 5, 7]);
+        assert!(matches!(relocated.builtins[..], [Builtin::Output(1)]));
+        assert_eq!(
+            relocated.segments.builtin("output"),
+            Some(MemorySegment::new(7, 8))
+        );
+
+        // Missing public memory is rejected
+        let mut pie = pie;
+        pie.memory.pop();
+        assert!(pie.relocate().is_err());
+    }
+
+    #[test]
+    fn test_native_run_roundtrip() {
+        // main: ret
+        let end = Felt::from(1u64 << 32);
+        let mut memory = Memory::new(vec![Felt::from(0x208b7fff7fff7ffeu64)]);
+        memory.write_pub(Felt::from(2u64), end);
+        memory.write_pub(Felt::from(3u64), end);
+        let init = RegisterState::new(1u64, 4u64, 4u64);
+        let pie = CairoPie::from_native_run(&memory, init, 1, &PointerTracker::new());
+        assert_eq!(pie.metadata.program.main, 0);
+        assert_eq!(pie.metadata.execution_segment.size, 2);
+        assert_eq!(pie.memory[1].1, MaybeRelocatable::Relocatable(2, 0));
+
+        let path = std::env::temp_dir().join("giza_test_pie.zip");
+        pie.write_file(&path).unwrap();
+        let relocated = CairoPie::read_file(&path).unwrap().relocate().unwrap();
+        assert_eq!(relocated.init.pc, Felt::from(1u64));
+        assert_eq!(relocated.init.ap, Felt::from(4u64));
+        assert_eq!(relocated.n_steps, 1);
+
+        // main returns to the return pc segment
+        let mut step = Step::new(&relocated.memory, relocated.init);
+        step.execute(false);
+        assert_eq!(step.next.unwrap().pc, relocated.end_pc);
+    }
+}
@@ -1,20 +1,32 @@
 // Modified from https://github.com/o1-labs/proof-systems
 
 use crate::errors::ExecutionError;
-use crate::memory::Memory;
+use crate::memory::{relocate_value, Memory};
+use crate::pie::{CairoPie, PointerTracker};
+use crate::tape::HintTape;
 use crate::trace::ExecutionTrace;
 use giza_core::{flags::*, *};
 
 #[cfg(feature = "hints")]
-use crate::hints::{ExecutionEffect as HintExecutionEffect, HintManager};
+use crate::hints::{policy::HintPolicy, scopes::ExecutionScopes, segments::Segments, HintManager};
+
+/// Bytecode of the end loop `jmp rel 0`, which repeats itself without changing the
+/// registers. Executions end in an end loop, which pads their trace.
+pub const END_LOOP: [u64; 2] = [0x10780017fff7fff, 0];
+
+/// Returns whether the end loop is located at the given address
+pub fn is_end_loop(mem: &Memory, pc: Felt) -> bool {
+    END_LOOP
+        .iter()
+        .enumerate()
+        .all(|(i, word)| mem.read(pc + Felt::from(i as u64)) == Some(Felt::from(*word)))
+}
 
 /// A data structure to store a current step of computation
 pub struct Step<'a> {
     pub mem: &'a Memory,
     pub curr: RegisterState,
     pub next: Option<RegisterState>,
-    #[cfg(feature = "hints")]
-    hints: Option<&'a HintManager>,
 }
 
 impl<'a> Step<'a> {
@@ -29,10 +41,6 @@ impl<'a> Step<'a> {
 
     /// Executes a step from the current registers and returns the instruction state
     pub fn execute(&mut self, write: bool) -> InstructionState {
-        // Execute hints and apply changes
-        #[cfg(feature = "hints")]
-        self.execute_hints();
-
         // Execute instruction
         let (op0_addr, mut op0) = self.set_op0();
         let (op1_addr, mut op1, size) = self.set_op1(op0);
@@ -71,33 +79,6 @@ impl<'a> Step<'a> {
         )
     }
 
-    #[cfg(feature = "hints")]
-    fn set_hint_manager(&mut self, hints: &'a HintManager) {
-        self.hints = hints;
-    }
-
-    #[cfg(feature = "hints")]
-    fn execute_hints(&mut self) {
-        if let Some(manager) = self.hints {
-            for hint in manager.get_hints(self.curr.pc).into_iter().flatten() {
-                let changes = hint.exec(&self).unwrap();
-                self.apply_hint_effects(changes);
-            }
-        }
-    }
-
-    #[cfg(feature = "hints")]
-    fn apply_hint_effects(&mut self, res: HintExecutionEffect) {
-        self.curr.pc = res.pc;
-        self.curr.ap = res.ap;
-        self.curr.fp = res.fp;
-        if let Some(updates) = res.mem_updates {
-            for (addr, elem) in updates.0.iter() {
-                self.mem.write(Felt::from(*addr), elem.word());
-            }
-        }
-    }
-
     /// This function returns the current word instruction being executed
     fn inst(&mut self) -> Word {
         Word::new(self.mem.read(self.curr.pc).expect("pc points to None cell"))
@@ -356,55 +337,74 @@ impl<'a> Step<'a> {
 }
 
 /// Trace-friendly record of registers and instruction state across
-/// all program execution steps
+/// all program execution steps. The memory accesses and offsets of a step
+/// are followed by its filler accesses and offsets, one for each row of the
+/// step.
 pub struct State {
-    pub flags: [Vec<Felt>; FLAG_TRACE_WIDTH],
-    pub res: [Vec<Felt>; RES_TRACE_WIDTH],
-    pub mem_p: [Vec<Felt>; MEM_P_TRACE_WIDTH],
-    pub mem_a: [Vec<Felt>; MEM_A_TRACE_WIDTH],
-    pub mem_v: [Vec<Felt>; MEM_V_TRACE_WIDTH],
-    pub offsets: [Vec<Felt>; OFF_X_TRACE_WIDTH],
+    pub flags: [Vec<Felt>; NUM_FLAGS],
+    pub res: [Vec<Felt>; 1],
+    pub mem_p: [Vec<Felt>; 2],
+    pub mem_a: [Vec<Felt>; STEP_HEIGHT],
+    pub mem_v: [Vec<Felt>; STEP_HEIGHT],
+    pub offsets: [Vec<Felt>; STEP_HEIGHT],
 }
 
 impl State {
     pub fn new(init_trace_len: usize) -> Self {
-        let mut flags: Vec<Vec<Felt>> = Vec::with_capacity(FLAG_TRACE_WIDTH);
-        let mut res: Vec<Vec<Felt>> = Vec::with_capacity(RES_TRACE_WIDTH);
-        let mut mem_p: Vec<Vec<Felt>> = Vec::with_capacity(MEM_P_TRACE_WIDTH);
-        let mut mem_a: Vec<Vec<Felt>> = Vec::with_capacity(MEM_A_TRACE_WIDTH);
-        let mut mem_v: Vec<Vec<Felt>> = Vec::with_capacity(MEM_V_TRACE_WIDTH);
-        let mut offsets: Vec<Vec<Felt>> = Vec::with_capacity(OFF_X_TRACE_WIDTH);
-        for _ in 0..FLAG_TRACE_WIDTH {
-            let column = Felt::zeroed_vector(init_trace_len);
-            flags.push(column);
+        State {
+            flags: zeroed_columns(init_trace_len),
+            res: zeroed_columns(init_trace_len),
+            mem_p: zeroed_columns(init_trace_len),
+            mem_a: zeroed_columns(init_trace_len),
+            mem_v: zeroed_columns(init_trace_len),
+            offsets: zeroed_columns(init_trace_len),
         }
-        for _ in 0..RES_TRACE_WIDTH {
-            let column = Felt::zeroed_vector(init_trace_len);
-            res.push(column);
+    }
+
+    fn columns_mut(&mut self) -> impl Iterator<Item = &mut Vec<Felt>> + '_ {
+        self.flags
+            .iter_mut()
+            .chain(self.res.iter_mut())
+            .chain(self.mem_p.iter_mut())
+            .chain(self.mem_a.iter_mut())
+            .chain(self.mem_v.iter_mut())
+            .chain(self.offsets.iter_mut())
+    }
+
+    /// Discards all steps after the first `len` steps
+    pub fn truncate(&mut self, len: usize) {
+        for column in self.columns_mut() {
+            column.truncate(len);
         }
-        for _ in 0..MEM_P_TRACE_WIDTH {
-            let column = Felt::zeroed_vector(init_trace_len);
-            mem_p.push(column);
+    }
+
+    /// Pads the record up to `len` steps by repeating the given step
+    pub fn pad(&mut self, len: usize, regs: RegisterState, inst: InstructionState) {
+        let step = self.mem_a[0].len();
+        if len <= step {
+            return;
         }
-        for _ in 0..MEM_A_TRACE_WIDTH {
-            let column = Felt::zeroed_vector(init_trace_len);
-            mem_a.push(column);
+        for column in self.columns_mut() {
+            column.push(Felt::ZERO);
         }
-        for _ in 0..MEM_V_TRACE_WIDTH {
-            let column = Felt::zeroed_vector(init_trace_len);
-            mem_v.push(column);
+        self.set_register_state(step, regs);
+        self.set_instruction_state(step, inst);
+        for column in self.columns_mut() {
+            let value = column[step];
+            column.resize(len, value);
         }
-        for _ in 0..OFF_X_TRACE_WIDTH {
-            let column = Felt::zeroed_vector(init_trace_len);
-            offsets.push(column);
+    }
+
+    /// Replaces the addresses in the segments allocated by hints by their flat address,
+    /// given the base addresses of the relocated segments
+    pub fn relocate(&mut self, bases: &[u64]) {
+        if bases.is_empty() {
+            return;
         }
-        State {
-            flags: flags.try_into().unwrap(),
-            res: res.try_into().unwrap(),
-            mem_p: mem_p.try_into().unwrap(),
-            mem_a: mem_a.try_into().unwrap(),
-            mem_v: mem_v.try_into().unwrap(),
-            offsets: offsets.try_into().unwrap(),
+        for column in self.columns_mut() {
+            for value in column.iter_mut() {
+                *value = relocate_value(*value, bases);
+            }
         }
     }
 
@@ -444,6 +444,10 @@ impl State {
     }
 }
 
+fn zeroed_columns<const N: usize>(len: usize) -> [Vec<Felt>; N] {
+    core::array::from_fn(|_| Felt::zeroed_vector(len))
+}
+
 /// Stores all information needed to run a program
 pub struct Program<'a> {
     /// total number of steps
@@ -459,6 +463,22 @@ pub struct Program<'a> {
     /// hints
     #[cfg(feature = "hints")]
     hints: Option<HintManager>,
+    /// variables shared between hints
+    #[cfg(feature = "hints")]
+    scopes: ExecutionScopes,
+    /// memory segments allocated by hints
+    #[cfg(feature = "hints")]
+    segments: Segments,
+    /// recorded hint effects, replayed instead of executing hints
+    tape: Option<HintTape>,
+    /// hint effects recorded during execution
+    #[cfg(feature = "hints")]
+    recording: Option<HintTape>,
+    /// whitelist of hints allowed to run
+    #[cfg(feature = "hints")]
+    policy: Option<HintPolicy>,
+    /// memory cells holding pointers, tracked to export the run as a Cairo PIE
+    pointers: Option<PointerTracker>,
 }
 
 impl<'a> Program<'a> {
@@ -472,6 +492,12 @@ impl<'a> Program<'a> {
             fin: RegisterState::new(Felt::ZERO, Felt::ZERO, Felt::ZERO),
             builtins: vec![],
             hints,
+            scopes: ExecutionScopes::new(),
+            segments: Segments::new(),
+            tape: None,
+            recording: None,
+            policy: None,
+            pointers: None,
         }
     }
 
@@ -483,9 +509,58 @@ impl<'a> Program<'a> {
             init: RegisterState::new(Felt::from(pc), Felt::from(ap), Felt::from(ap)),
             fin: RegisterState::new(Felt::ZERO, Felt::ZERO, Felt::ZERO),
             builtins: vec![],
+            tape: None,
+            pointers: None,
         }
     }
 
+    /// Sets the input made available to hints as `program_input`
+    #[cfg(feature = "hints")]
+    pub fn set_program_input(&mut self, program_input: ProgramInputs) {
+        self.hints
+            .get_or_insert_with(HintManager::default)
+            .set_program_input(program_input);
+    }
+
+    /// Restricts the hints that may run to those allowed by a policy. Programs with
+    /// other hints are rejected before execution starts.
+    #[cfg(feature = "hints")]
+    pub fn set_hint_policy(&mut self, policy: HintPolicy) {
+        self.policy = Some(policy);
+    }
+
+    /// Replays the hint effects recorded in a tape instead of executing hints
+    pub fn set_tape(&mut self, tape: HintTape) {
+        self.tape = Some(tape);
+    }
+
+    /// Records the effects of all hints executed during the run into a tape
+    #[cfg(feature = "hints")]
+    pub fn record_tape(&mut self) {
+        self.recording = Some(HintTape::new());
+    }
+
+    /// Outputs the tape recorded during the run, if recording was enabled
+    #[cfg(feature = "hints")]
+    pub fn get_tape(&self) -> Option<&HintTape> {
+        self.recording.as_ref()
+    }
+
+    /// Tracks the pointers stored in memory during the run, so that it can be exported as
+    /// a Cairo PIE
+    pub fn record_pie(&mut self) {
+        self.pointers = Some(PointerTracker::new());
+    }
+
+    /// Outputs the Cairo PIE of the run, if PIE recording was enabled. The program must
+    /// have been loaded with [load_program](crate::load_program).
+    pub fn get_pie(&self) -> Option<CairoPie> {
+        let pointers = self.pointers.as_ref()?;
+        Some(CairoPie::from_native_run(
+            self.mem, self.init, self.steps, pointers,
+        ))
+    }
+
     /// Outputs the total number of steps of the execution carried out by the runner
     pub fn get_steps(&self) -> usize {
         self.steps
@@ -505,17 +580,36 @@ impl<'a> Program<'a> {
         let mut curr = self.init;
         let mut next = curr;
 
+        // refuse to run hints that are not allowed or can't be executed
+        #[cfg(feature = "hints")]
+        if let (None, Some(manager)) = (&self.tape, &self.hints) {
+            if let Some(policy) = &self.policy {
+                policy.check(manager).map_err(ExecutionError::HintError)?;
+            }
+            manager
+                .check_supported()
+                .map_err(ExecutionError::HintError)?;
+        }
+
         // keep executing steps until the end is reached
         while !end {
+            // execute hints (or replay their recorded effects) before the instruction
+            if let Some(tape) = self.tape.as_mut() {
+                tape.replay(n, self.mem, &mut next)?;
+            } else {
+                #[cfg(feature = "hints")]
+                self.execute_hints(n, &mut next)?;
+            }
+
             // create current step of computation
             let mut step = Step::new(self.mem, next);
             curr = step.curr;
 
-            #[cfg(feature = "hints")]
-            step.set_hint_manager(self.hints.as_ref());
-
             // execute current step and save state
             let inst_state = step.execute(true);
+            if let Some(pointers) = self.pointers.as_mut() {
+                pointers.step(curr, &inst_state);
+            }
             state.set_register_state(n, curr);
             state.set_instruction_state(n, inst_state);
 
@@ -524,8 +618,8 @@ impl<'a> Program<'a> {
                 None => end = true,
                 _ => {
                     next = step.next.expect("Empty next pointers");
-                    if curr.ap.as_int() <= next.pc.as_int() {
-                        // if reading from unallocated memory, end
+                    if curr.ap.as_int() <= next.pc.as_int() || is_end_loop(self.mem, next.pc) {
+                        // if reading from unallocated memory or reaching the end loop, end
                         end = true;
                     }
                 }
@@ -534,11 +628,44 @@ impl<'a> Program<'a> {
         self.fin = curr;
         self.steps = n;
 
-        Ok(ExecutionTrace::new(
-            n,
-            &mut state,
-            &self.mem,
-            self.builtins.clone(),
-        ))
+        // every recorded hint effect must have been replayed
+        if let Some(tape) = &self.tape {
+            tape.check_consumed()?;
+        }
+
+        // every scope entered by a hint must have been exited
+        #[cfg(feature = "hints")]
+        self.scopes
+            .check_balanced()
+            .map_err(ExecutionError::HintError)?;
+
+        // place the segments allocated by hints after all other memory
+        let (bases, pointers) = self.mem.relocate();
+        state.relocate(&bases);
+        if let Some(tracker) = self.pointers.as_mut() {
+            tracker.relocate(bases, pointers);
+        }
+
+        ExecutionTrace::new(n, &mut state, &self.mem, self.builtins.clone(), None, None)
+    }
+
+    /// Executes the hints of the current step, recording their effects if requested
+    #[cfg(feature = "hints")]
+    fn execute_hints(&mut self, n: usize, next: &mut RegisterState) -> Result<(), ExecutionError> {
+        let manager = match &self.hints {
+            Some(manager) if manager.get_hints(next.pc).is_some() => manager,
+            _ => return Ok(()),
+        };
+        let pc = next.pc;
+        if self.recording.is_some() {
+            self.mem.start_log();
+        }
+        manager
+            .exec_hints(self.mem, next, &mut self.scopes, &mut self.segments)
+            .map_err(ExecutionError::HintError)?;
+        if let Some(tape) = self.recording.as_mut() {
+            tape.record(n, pc, *next, self.mem.take_log());
+        }
+        Ok(())
     }
 }
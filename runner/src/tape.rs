@@ -0,0 +1,212 @@
+//! Recording of the nondeterministic effects of hints (memory writes and register
+//! changes). A tape recorded during a run with hints can be replayed to re-execute the
+//! same program without a hint engine.
+//!
+//! The tape is stored in the same little-endian encoding as the cairo-runner outputs.
+//! Each entry consists of:
+//! - the step index, and the pc at that step before hints were run (8 bytes each)
+//! - the pc, ap and fp after hints were run (8 bytes each)
+//! - the number of memory writes (8 bytes), followed by the writes, each encoded as an
+//!   address and a field element (32 bytes each). Addresses in segments allocated by hints
+//!   are recorded before relocation.
+
+use crate::errors::ExecutionError;
+use crate::memory::Memory;
+use giza_core::{Felt, FieldHelpers, RegisterState, StarkField};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Effects of the hints executed before a single step
+#[derive(Clone, Debug)]
+pub struct TapeEntry {
+    /// index of the step
+    pub step: u64,
+    /// pc at which the hints were executed
+    pub pc: u64,
+    /// register state after the hints were executed
+    pub registers: RegisterState,
+    /// memory writes made by the hints, in order
+    pub writes: Vec<(Felt, Felt)>,
+}
+
+/// Nondeterminism tape of a run
+#[derive(Clone, Debug, Default)]
+pub struct HintTape {
+    entries: Vec<TapeEntry>,
+    /// index of the next entry to replay
+    cursor: usize,
+}
+
+impl HintTape {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[TapeEntry] {
+        &self.entries
+    }
+
+    /// Records the effects of the hints executed at the given step
+    pub fn record(
+        &mut self,
+        step: usize,
+        pc: Felt,
+        registers: RegisterState,
+        writes: Vec<(Felt, Felt)>,
+    ) {
+        self.entries.push(TapeEntry {
+            step: step as u64,
+            pc: pc.to_u64(),
+            registers,
+            writes,
+        });
+    }
+
+    /// Applies the recorded hint effects of the given step (if any) to memory and to the
+    /// register state. Steps must be replayed in order.
+    pub fn replay(
+        &mut self,
+        step: usize,
+        memory: &mut Memory,
+        registers: &mut RegisterState,
+    ) -> Result<(), ExecutionError> {
+        let entry = match self.entries.get(self.cursor) {
+            Some(entry) if entry.step == step as u64 => entry,
+            _ => return Ok(()),
+        };
+        if entry.pc != registers.pc.to_u64() {
+            return Err(ExecutionError::TapeMismatch(step));
+        }
+        for (addr, value) in entry.writes.iter() {
+            memory.write(*addr, *value);
+        }
+        *registers = entry.registers;
+        self.cursor += 1;
+        Ok(())
+    }
+
+    /// Returns an error if some recorded entries were never replayed
+    pub fn check_consumed(&self) -> Result<(), ExecutionError> {
+        match self.entries.get(self.cursor) {
+            Some(entry) => Err(ExecutionError::TapeMismatch(entry.step as usize)),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the tape to a file
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut f = BufWriter::new(File::create(path)?);
+        for entry in self.entries.iter() {
+            f.write_all(&entry.step.to_le_bytes())?;
+            f.write_all(&entry.pc.to_le_bytes())?;
+            f.write_all(&entry.registers.pc.to_u64().to_le_bytes())?;
+            f.write_all(&entry.registers.ap.to_u64().to_le_bytes())?;
+            f.write_all(&entry.registers.fp.to_u64().to_le_bytes())?;
+            f.write_all(&(entry.writes.len() as u64).to_le_bytes())?;
+            for (addr, value) in entry.writes.iter() {
+                f.write_all(&addr.as_int().to_le_bytes())?;
+                f.write_all(&value.as_int().to_le_bytes())?;
+            }
+        }
+        f.flush()
+    }
+
+    /// Reads a tape from a file
+    pub fn read_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut f = BufReader::new(File::open(path)?);
+        let mut entries = vec![];
+        while let Some(step) = read_u64_or_eof(&mut f)? {
+            let pc = read_u64(&mut f)?;
+            let registers =
+                RegisterState::new(read_u64(&mut f)?, read_u64(&mut f)?, read_u64(&mut f)?);
+            let num_writes = read_u64(&mut f)?;
+            let mut writes = vec![];
+            for _ in 0..num_writes {
+                let addr = read_felt(&mut f)?;
+                let value = read_felt(&mut f)?;
+                writes.push((addr, value));
+            }
+            entries.push(TapeEntry {
+                step,
+                pc,
+                registers,
+                writes,
+            });
+        }
+        Ok(Self { entries, cursor: 0 })
+    }
+}
+
+fn read_u64<R: Read>(f: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    f.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_felt<R: Read>(f: &mut R) -> io::Result<Felt> {
+    let mut buf = [0u8; 32];
+    f.read_exact(&mut buf)?;
+    Ok(Felt::from(buf))
+}
+
+/// Reads a u64, or returns None if the reader is already at the end of its input
+fn read_u64_or_eof<R: Read>(f: &mut R) -> io::Result<Option<u64>> {
+    let mut buf = [0u8; 8];
+    let mut read = 0;
+    while read < buf.len() {
+        match f.read(&mut buf[read..])? {
+            0 if read == 0 => return Ok(None),
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => read += n,
+        }
+    }
+    Ok(Some(u64::from_le_bytes(buf)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::Address;
+    use giza_core::FieldElement;
+
+    #[test]
+    fn test_tape_roundtrip() {
+        let segment = Felt::from(Address::Segment(0, 1));
+        let mut tape = HintTape::new();
+        tape.record(
+            3,
+            Felt::from(7u64),
+            RegisterState::new(7u64, 30u64, 28u64),
+            vec![(Felt::from(30u64), Felt::from(5u64)), (segment, -Felt::ONE)],
+        );
+        tape.record(
+            9,
+            Felt::from(12u64),
+            RegisterState::new(12u64, 40u64, 28u64),
+            vec![],
+        );
+
+        let path = std::env::temp_dir().join(format!("giza_test_tape_{}.bin", std::process::id()));
+        tape.write_file(&path).unwrap();
+        let replayed = HintTape::read_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mut replayed = replayed.unwrap();
+        assert_eq!(replayed.entries().len(), 2);
+
+        // Steps without recorded hints are left untouched
+        let mut memory = Memory::new(vec![]);
+        let mut registers = RegisterState::new(7u64, 30u64, 28u64);
+        replayed.replay(2, &mut memory, &mut registers).unwrap();
+        assert_eq!(memory.read(Felt::from(30u64)), None);
+
+        replayed.replay(3, &mut memory, &mut registers).unwrap();
+        assert_eq!(memory.read(Felt::from(30u64)), Some(Felt::from(5u64)));
+        assert_eq!(memory.read(segment), Some(-Felt::ONE));
+        assert!(replayed.check_consumed().is_err());
+
+        // Replaying at a different pc than the one recorded is an error
+        let mut registers = RegisterState::new(13u64, 40u64, 28u64);
+        assert!(replayed.replay(9, &mut memory, &mut registers).is_err());
+    }
+}
@@ -1,19 +1,29 @@
-use crate::cairo_interop::{read_builtins, read_memory_bin, read_trace_bin};
+use crate::cairo_interop::{
+    read_air_private_input, read_air_public_input, read_builtins, read_memory_bin,
+    read_memory_dump, read_segments, read_trace_bin, validate_segments,
+};
+use crate::errors::{ExecutionError, ReadError};
 use crate::memory::Memory;
-use crate::runner::{State, Step};
+use crate::pie::CairoPie;
+
+use crate::runner::{is_end_loop, State, Step, END_LOOP};
 use giza_core::{
-    Builtin, Felt, FieldElement, StarkField, Word, AP, A_M_PRIME_WIDTH, A_RC_PRIME_WIDTH,
-    MEM_A_TRACE_RANGE, MEM_A_TRACE_WIDTH, MEM_V_TRACE_RANGE, OFF_X_TRACE_RANGE, OFF_X_TRACE_WIDTH,
-    P_M_WIDTH, P_RC_WIDTH, TRACE_WIDTH, V_M_PRIME_WIDTH,
+    flags::NUM_FLAGS, AuxArgument, Builtin, Felt, FieldElement, FieldHelpers, OffsetDecomposition,
+    RegisterState, SegmentTable, StarkField, Word, AP, AUX_LOGUP_MEM_TRACE_WIDTH,
+    AUX_LOGUP_RC_TRACE_WIDTH, AUX_MEM_TRACE_WIDTH, AUX_RC_TRACE_WIDTH, CPU_HEIGHT, FP,
+    MEM_A_TRACE_OFFSET, MEM_TABLE_A_OFFSET, MEM_TABLE_M_OFFSET, MEM_TABLE_V_OFFSET,
+    MEM_V_TRACE_OFFSET, OFF_X_TRACE_OFFSET, PC, RC_TABLE_M_OFFSET, RC_TABLE_OFFSET, STEP_HEIGHT,
+    STEP_MEM_WIDTH, STEP_OFF_WIDTH, TRACE_WIDTH,
 };
 use winterfell::{Matrix, Trace, TraceLayout};
 
 use indicatif::ParallelProgressIterator;
 use indicatif::ProgressIterator;
-use itertools::Itertools;
+use rand::RngCore;
 use rayon::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+#[derive(Clone)]
 pub struct ExecutionTrace {
     layout: TraceLayout,
     meta: Vec<u8>,
@@ -23,6 +33,17 @@ pub struct ExecutionTrace {
     pub rc_max: u16,
     pub num_steps: usize,
     pub builtins: Vec<Builtin>,
+    /// public memory, reported by the runner that generated the trace or derived from the
+    /// program and its output
+    public_mem: (Vec<u64>, Vec<Option<Word>>),
+    /// memory segments of the run
+    segments: Option<SegmentTable>,
+    /// number of steps needed by the execution, before padding
+    min_len: usize,
+    /// number of random rows ending a blinded trace
+    num_blinding_rows: usize,
+    /// argument proving memory consistency and range checks
+    argument: AuxArgument,
 }
 
 /// A virtual column is composed of one or more subcolumns.
@@ -46,20 +67,6 @@ impl<'a, E: FieldElement> VirtualColumn<'a, E> {
         }
         col
     }
-
-    /// Split subcolumns into multiple output columns: for each subcolumn, output a single
-    /// value to each output column, cycling through each output column until exhuasted.
-    fn to_columns(&self, num_rows: &[usize]) -> Vec<Vec<E>> {
-        let mut n = 0;
-        let mut cols: Vec<Vec<E>> = vec![vec![]; num_rows.iter().sum()];
-        for (subcol, width) in self.subcols.iter().zip(num_rows) {
-            for (elem, idx) in subcol.iter().zip((0..*width).cycle()) {
-                cols[idx + n].push(*elem);
-            }
-            n += width;
-        }
-        cols
-    }
 }
 
 struct Layouter<'a, E: FieldElement> {
@@ -74,13 +81,15 @@ impl<'a, E: FieldElement> Layouter<'a, E> {
 
     /// Add one or more columns to the trace. The chunk size determines the number
     /// of subcolumn elements to place within each frame chunk (defaults to 1)
-    /// starting from the top most row of the chunk.
+    /// starting from the top most row of the chunk. The remaining rows of the
+    /// chunk are set to zero.
     fn add_columns(&mut self, subcols: &[Vec<E>], chunk_size: Option<usize>) {
+        let chunk_size = chunk_size.unwrap_or(1);
         for subcol in subcols.iter() {
-            let mut col = E::zeroed_vector(subcol.len());
+            let mut col = E::zeroed_vector(subcol.len() / chunk_size * self.frame_len);
             for (col_chunk, subcol_chunk) in col
                 .chunks_mut(self.frame_len)
-                .zip(subcol.chunks(chunk_size.unwrap_or(1)))
+                .zip(subcol.chunks(chunk_size))
             {
                 for (n, elem) in subcol_chunk.iter().enumerate() {
                     col_chunk[n] = *elem
@@ -97,120 +106,404 @@ impl<'a, E: FieldElement> Layouter<'a, E> {
 }
 
 impl ExecutionTrace {
-    /// Builds an execution trace
+    /// Builds an execution trace. The trace is padded up to a power of two steps by repeating
+    /// the end loop (`jmp rel 0`) that the execution runs into after its last step.
     pub(super) fn new(
         num_steps: usize,
         state: &mut State,
         memory: &Memory,
         builtins: Vec<Builtin>,
-    ) -> Self {
-        // Compute the derived ("auxiliary") trace values: t0, t1, and mul.
-        // Note that in a conditional jump instruction we substitute res with dst^{-1}
-        // (see page 53 of the whitepaper).
-        let mut t0 = vec![];
-        let mut t1 = vec![];
-        let mut mul = vec![];
-        for step in 0..num_steps {
-            // TODO: Don't hardcode index values
-            let f_pc_jnz = state.flags[9][step];
-            let dst = state.mem_v[1][step];
-            let res = state.res[0][step];
-            t0.push(f_pc_jnz * dst); // f_pc_jnz * dst
-            t1.push(t0[step] * res); // t_0 * res
-            mul.push(state.mem_v[2][step] * state.mem_v[3][step]); // op0 * op1
-        }
+        public_mem: Option<(Vec<u64>, Vec<Option<Word>>)>,
+        segments: Option<SegmentTable>,
+    ) -> Result<Self, ExecutionError> {
+        let mut memory = memory.clone();
+        state.truncate(num_steps);
 
-        // 1. Append dummy artificial accesses to mem_a and mem_v to fill memory holes.
-        //    These gaps are due to interaction with builtins, and they still need to be handled
-        //    elsewhere in the code for soundness.
-        // 2. Append dummy (0,0) public memory values to mem_a and mem_v.
-        //    Note that we don't need to worry about precise placement (i.e. ensuring that they are
-        //    the final n entries in the columns), because these dummy values will extend into the
-        //    resized column cells.
-        //    TODO: We should also append dummy output (not just program) public memory, in case the
-        //    trace length is not already long enough to contain these values.
-        let mut col_extension = memory.get_holes(VirtualColumn::new(&state.mem_a).to_column());
-        col_extension.extend(vec![Felt::ZERO; memory.get_codelen()]);
-        for (n, col) in VirtualColumn::new(&[col_extension])
-            .to_columns(&[MEM_A_TRACE_WIDTH])
-            .iter()
-            .enumerate()
-        {
-            state.mem_a[n].extend(col);
-            state.mem_v[n].extend(Felt::zeroed_vector(col.len()));
-        }
+        // Unless reported by the runner, public memory consists of the program and its output
+        let mut public_mem = public_mem.unwrap_or_else(|| {
+            let (mut addrs, mut values) = program_mem(&memory);
+            let final_ap = state.mem_p[0][num_steps - 1];
+            let (out_addrs, out_values) =
+                output_mem(&memory, &builtins, segments.as_ref(), final_ap);
+            addrs.extend(out_addrs);
+            values.extend(out_values);
+            (addrs, values)
+        });
+
+        // Find the end loop, either at the last step (e.g. in proof mode, where the runner
+        // already pads the execution with it) or right after it. Executions that return past
+        // the end of memory (e.g. runs of main outside of proof mode) are given an end loop at
+        // their return address, which is added to the public memory.
+        let last = RegisterState::new(
+            state.mem_a[0][num_steps - 1],
+            state.mem_p[0][num_steps - 1],
+            state.mem_p[1][num_steps - 1],
+        );
+        let (end, min_len) = if is_end_loop(&memory, last.pc) {
+            (last, num_steps)
+        } else {
+            let mut step = Step::new(&memory, last);
+            step.execute(false);
+            let end = step.next.expect("Empty next pointers");
+            if !is_end_loop(&memory, end.pc) {
+                let pc = end.pc.to_u64();
+                if pc < memory.size() {
+                    return Err(ExecutionError::MissingEndLoop(pc));
+                }
+                for (addr, word) in (pc..).zip(END_LOOP) {
+                    memory.write(Felt::from(addr), Felt::from(word));
+                    public_mem.0.push(addr);
+                    public_mem.1.push(Some(Word::new(Felt::from(word))));
+                }
+            }
+            (end, num_steps + 1)
+        };
+        let end_inst = Step::new(&memory, end).execute(false);
+
+        // Find the memory holes, so that they can be filled with filler accesses and make
+        // accessed addresses continuous. Holes are cells that are neither accessed by the CPU
+        // nor public (e.g. unused builtin cells); the AIR constrains filler accesses to zero
+        // values, so they only claim these cells to be zero.
+        let mut accesses = VirtualColumn::new(&state.mem_a[..STEP_MEM_WIDTH]).to_column();
+        accesses.extend([
+            end.pc,
+            end_inst.dst_addr,
+            end_inst.op0_addr,
+            end_inst.op1_addr,
+        ]);
+        accesses.extend(public_mem.0.iter().map(|addr| Felt::from(*addr)));
+        let holes = memory.get_holes(accesses);
 
         // 1. Convert offsets into an unbiased representation by adding 2^15, so that values are
         //    within [0, 2^16].
-        // 2. Fill gaps between sorted offsets so that we can compute the proper permutation
-        //    product column in the range check auxiliary segment (if we implemented Ord for Felt
-        //    we could achieve a speedup here)
+        // 2. Find gaps between sorted offsets, to be filled with filler offsets so that we can
+        //    compute the proper permutation product column in the range check auxiliary segment
+        //    (if we implemented Ord for Felt we could achieve a speedup here)
         let b15 = Felt::from(2u8).exp(15u32.into());
-        let mut rc_column: Vec<Felt> = VirtualColumn::new(&state.offsets)
-            .to_column()
-            .into_iter()
-            .map(|x| x + b15)
-            .collect();
+        let mut rc_column = VirtualColumn::new(&state.offsets[..STEP_OFF_WIDTH]).to_column();
+        rc_column.extend([
+            end_inst.inst.off_dst(),
+            end_inst.inst.off_op0(),
+            end_inst.inst.off_op1(),
+        ]);
         let mut rc_sorted: Vec<u16> = rc_column
             .iter()
-            .map(|x| x.as_int().try_into().unwrap())
+            .map(|x| (*x + b15).as_int().try_into().unwrap())
             .collect();
         rc_sorted.sort_unstable();
         let rc_min = rc_sorted.first().unwrap().clone();
         let rc_max = rc_sorted.last().unwrap().clone();
+        let mut rc_gaps = vec![];
         for s in rc_sorted.windows(2).progress() {
             match s[1] - s[0] {
                 0 | 1 => {}
-                _ => {
-                    rc_column.extend((s[0] + 1..s[1]).map(|x| Felt::from(x)).collect::<Vec<_>>());
-                }
+                _ => rc_gaps.extend((s[0] + 1..s[1]).map(|x| Felt::from(x))),
             }
         }
-        let offsets = VirtualColumn::new(&[rc_column]).to_columns(&[3]);
-
-        // This is hacky... We're adding a selector to the main trace to disable the Cairo
-        // transition constraints for public memory (and any extended trace cells that were added
-        // to ensure that that length is a power of two). If we instead used transition
-        // exemptions, then proving/verifying time would be too slow for programs with a large
-        // number of instructions.
-        //
-        // There are two methods that can be combined to avoid selectors:
-        // - Transform traces so that they end in an inifite loop (use the instruction
-        //   0x10780017fff7fffu64).
-        // - Use a short bootloader program so that the number of transition exemptions is small
-        //   and doesn't harm performance. This bootloader will compute and expose a hash of the
-        //   private memory containing the program instructions to be run.
-        let mut selector = vec![Felt::ONE; num_steps];
-        selector[num_steps - 1] = Felt::ZERO;
-
-        // Layout the trace
+
+        // Pad the execution with the end loop. The trace must have room for a filler access
+        // per public memory cell and per hole, and for a filler offset per gap between offsets.
+        // The last filler access and offset are left free, so that the last row of the trace
+        // is known to the verifier, as required by the LogUp argument. It also needs room for
+        // the tables of memory cells and range check values, followed by a padding row.
+        let num_filler_accesses = STEP_HEIGHT - STEP_MEM_WIDTH;
+        let num_filler_offsets = STEP_HEIGHT - STEP_OFF_WIDTH;
+        let table_len = (memory.size() as usize).max((rc_max - rc_min) as usize + 1) + 1;
+        let min_len = min_len
+            .max((public_mem.0.len() + holes.len() + num_filler_accesses) / num_filler_accesses)
+            .max((rc_gaps.len() + num_filler_offsets) / num_filler_offsets)
+            .max((table_len + STEP_HEIGHT - 1) / STEP_HEIGHT);
+        let trace_len = min_len.next_power_of_two();
+        state.pad(trace_len, end, end_inst);
+
+        // Filler memory accesses hold dummy (0,0) accesses for public memory, which are
+        // replaced by the public memory when building the memory aux segment, followed by the
+        // holes. Filler values are left to zero.
+        let mut filler_addrs = vec![Felt::ZERO; public_mem.0.len()];
+        filler_addrs.extend(holes);
+        filler_addrs.resize(trace_len * num_filler_accesses, Felt::ZERO);
+        fill_subcolumns(&mut state.mem_a[STEP_MEM_WIDTH..], &filler_addrs);
+
+        // Filler offsets hold the gaps between offsets, and are otherwise set to rc_min
+        for column in state.offsets[..STEP_OFF_WIDTH].iter_mut() {
+            column.iter_mut().for_each(|x| *x += b15);
+        }
+        rc_gaps.resize(trace_len * num_filler_offsets, Felt::from(rc_min));
+        fill_subcolumns(&mut state.offsets[STEP_OFF_WIDTH..], &rc_gaps);
+
+        // Compute the derived ("auxiliary") trace values: t0, t1, and mul.
+        // Note that in a conditional jump instruction we substitute res with dst^{-1}
+        // (see page 53 of the whitepaper).
+        let mut t0 = vec![];
+        let mut t1 = vec![];
+        let mut mul = vec![];
+        for step in 0..trace_len {
+            // TODO: Don't hardcode index values
+            let f_pc_jnz = state.flags[9][step];
+            let dst = state.mem_v[1][step];
+            let res = state.res[0][step];
+            t0.push(f_pc_jnz * dst); // f_pc_jnz * dst
+            t1.push(t0[step] * res); // t_0 * res
+            mul.push(state.mem_v[2][step] * state.mem_v[3][step]); // op0 * op1
+        }
+
+        // Layout the trace, spreading each step across STEP_HEIGHT rows. The cpu column holds
+        // the registers and derived values in the order of their rows.
+        let cpu = [
+            state.mem_p[0].clone(),
+            state.mem_p[1].clone(),
+            state.res[0].clone(),
+            t0,
+            t1,
+            mul,
+        ];
         let mut columns: Vec<Vec<Felt>> = Vec::with_capacity(TRACE_WIDTH);
-        let mut layouter = Layouter::new(&mut columns, 1);
-        layouter.add_columns(&state.flags, None);
-        layouter.add_columns(&state.res, None);
-        layouter.add_columns(&state.mem_p, None);
-        layouter.add_columns(&state.mem_a, None);
-        layouter.add_columns(&state.mem_v, None);
-        layouter.add_columns(&offsets, None);
-        layouter.add_columns(&[t0, t1, mul], None);
-        layouter.add_columns(&[selector], None);
+        let mut layouter = Layouter::new(&mut columns, STEP_HEIGHT);
+        for (subcols, chunk_size) in [
+            (&state.flags[..], NUM_FLAGS),
+            (&state.offsets[..], STEP_HEIGHT),
+            (&state.mem_a[..], STEP_HEIGHT),
+            (&state.mem_v[..], STEP_HEIGHT),
+            (&cpu[..], CPU_HEIGHT),
+        ] {
+            layouter.add_columns(&[VirtualColumn::new(subcols).to_column()], Some(chunk_size));
+        }
 
         layouter.resize_all();
 
-        Self {
+        Ok(Self {
             layout: TraceLayout::new(
                 TRACE_WIDTH,
-                &[12, 6], // aux_segment widths
-                &[2, 1],  // aux_segment rands
+                &[AUX_MEM_TRACE_WIDTH, AUX_RC_TRACE_WIDTH], // aux_segment widths
+                &[2, 1],                                    // aux_segment rands
             ),
             meta: Vec::new(),
             trace: Matrix::new(columns),
-            memory: memory.clone(),
+            memory,
             rc_min,
             rc_max,
             num_steps,
             builtins,
+            public_mem,
+            segments,
+            min_len,
+            num_blinding_rows: 0,
+            argument: AuxArgument::default(),
+        })
+    }
+
+    /// Appends random rows to the trace to blind it, extending it with the end loop if it
+    /// lacks room for them. The number of random rows is rounded up to whole steps. The last
+    /// step before the random rows is a copy of the end loop whose own
+    /// transitions are not enforced. Random rows of the auxiliary segments are drawn when
+    /// building them.
+    pub fn blind(&mut self, num_rows: usize) {
+        if num_rows == 0 {
+            return;
+        }
+        assert!(
+            self.argument == AuxArgument::Permutation,
+            "Traces must be blinded before selecting the LogUp argument"
+        );
+        let num_rows = (num_rows + STEP_HEIGHT - 1) / STEP_HEIGHT * STEP_HEIGHT;
+        // Random rows take up at most half of the trace, which the verifier checks
+        let trace_len = self
+            .length()
+            .max((self.min_len * STEP_HEIGHT + num_rows).next_power_of_two())
+            .max((2 * num_rows).next_power_of_two());
+        self.extend(trace_len, num_rows);
+    }
+
+    /// Resizes the trace to the given length, repeating the end loop after the last step and
+    /// ending with the given number of random rows
+    fn extend(&mut self, trace_len: usize, num_rows: usize) {
+        let last_step = self.last_step() * STEP_HEIGHT;
+        let mut rng = rand::thread_rng();
+        let columns = (0..self.trace.num_cols())
+            .map(|i| {
+                // Added steps repeat the end loop, without filler accesses and offsets
+                let mut column = self.trace.get_column(i)[..last_step + STEP_HEIGHT].to_vec();
+                let mut end_loop = column[last_step..].to_vec();
+                match i {
+                    MEM_A_TRACE_OFFSET | MEM_V_TRACE_OFFSET => {
+                        end_loop[STEP_MEM_WIDTH..].fill(Felt::ZERO)
+                    }
+                    OFF_X_TRACE_OFFSET => end_loop[STEP_OFF_WIDTH..].fill(Felt::from(self.rc_min)),
+                    _ => {}
+                }
+                while column.len() < trace_len - num_rows {
+                    column.extend_from_slice(&end_loop);
+                }
+                column.truncate(trace_len - num_rows);
+                column.extend((0..num_rows).map(|_| random_element::<Felt>(&mut rng)));
+                column
+            })
+            .collect::<Vec<_>>();
+        self.trace = Matrix::new(columns);
+        self.num_blinding_rows = num_rows;
+        self.write_meta();
+    }
+
+    /// Selects the argument proving memory consistency and range checks. The LogUp argument
+    /// extends the main trace with tables of the memory cells and range check values, and
+    /// with the number of accesses of each of their entries. Tables are built over the
+    /// steps of the trace, so a trace must be blinded first. If the tables don't fit before
+    /// the last row of the last step, the trace is extended with the end
+    /// loop up to the next power of two that fits them. Returns an error if the tables can't
+    /// be built from the trace.
+    pub fn set_argument(&mut self, argument: AuxArgument) -> Result<(), ExecutionError> {
+        if argument == self.argument {
+            return Ok(());
+        }
+        if argument == AuxArgument::LogUp {
+            let table_len =
+                (self.memory.size() as usize).max((self.rc_max - self.rc_min) as usize + 1) + 1;
+            if table_len > (self.last_step() + 1) * STEP_HEIGHT {
+                self.min_len = self
+                    .min_len
+                    .max((table_len + STEP_HEIGHT - 1) / STEP_HEIGHT);
+                let num_rows = self.num_blinding_rows;
+                self.extend(
+                    (self.min_len * STEP_HEIGHT + num_rows).next_power_of_two(),
+                    num_rows,
+                );
+            }
         }
+        let mut columns = (0..TRACE_WIDTH)
+            .map(|i| self.trace.get_column(i).to_vec())
+            .collect::<Vec<_>>();
+        let aux_widths = match argument {
+            AuxArgument::Permutation => [AUX_MEM_TRACE_WIDTH, AUX_RC_TRACE_WIDTH],
+            AuxArgument::LogUp => {
+                columns.extend(self.build_tables()?);
+                [AUX_LOGUP_MEM_TRACE_WIDTH, AUX_LOGUP_RC_TRACE_WIDTH]
+            }
+        };
+        self.layout = TraceLayout::new(columns.len(), &aux_widths, &[2, 1]);
+        self.trace = Matrix::new(columns);
+        self.argument = argument;
+        self.write_meta();
+        Ok(())
+    }
+
+    /// Builds the LogUp tables. The memory table lists every memory cell by address, and the
+    /// range check table every value from rc_min to rc_max, with the number of accesses of
+    /// each entry (including public memory). Tables are padded up to the last step with their
+    /// last entry, with zero multiplicity, and followed by the random rows of a
+    /// blinded trace. Accesses outside of memory and offsets outside of the range
+    /// check bounds are reported as errors.
+    fn build_tables(&self) -> Result<Vec<Vec<Felt>>, ExecutionError> {
+        let n = (self.last_step() + 1) * STEP_HEIGHT;
+        let main = self.main_segment();
+
+        // Memory cells. Accesses are indexed by address, so the table needs no sorting.
+        let size = self.memory.size() as usize;
+        let mut values = vec![Felt::ZERO; size];
+        let mut counts = vec![0u64; size];
+        let accesses = main.get_column(MEM_A_TRACE_OFFSET)[..n]
+            .iter()
+            .zip(&main.get_column(MEM_V_TRACE_OFFSET)[..n])
+            .map(|(a, v)| (*a, Some(*v)));
+        let public = self
+            .public_mem
+            .0
+            .iter()
+            .zip(&self.public_mem.1)
+            .map(|(a, v)| (Felt::from(*a), v.map(|v| v.word())));
+        for (addr, value) in accesses.chain(public) {
+            let index = match addr.to_u64() as usize {
+                index if Felt::from(index as u64) == addr && index < size => index,
+                _ => {
+                    return Err(ExecutionError::InvalidTables(format!(
+                        "memory access at address {} is out of memory",
+                        addr
+                    )))
+                }
+            };
+            values[index] = value.ok_or_else(|| {
+                ExecutionError::InvalidTables(format!(
+                    "public memory at address {} has no value",
+                    index
+                ))
+            })?;
+            counts[index] += 1;
+        }
+        let mut mem_a = (0..size as u64).map(Felt::from).collect::<Vec<_>>();
+        let mut mem_m = counts.into_iter().map(Felt::from).collect::<Vec<_>>();
+        let mut mem_v = values;
+
+        // Range check values
+        let size = (self.rc_max - self.rc_min) as usize + 1;
+        let mut counts = vec![0u64; size];
+        for offset in main.get_column(OFF_X_TRACE_OFFSET)[..n].iter() {
+            let index = offset.to_u64().wrapping_sub(self.rc_min as u64);
+            match counts.get_mut(index as usize) {
+                Some(count) if Felt::from(offset.to_u64()) == *offset => *count += 1,
+                _ => {
+                    return Err(ExecutionError::InvalidTables(format!(
+                        "offset {} is out of the range check bounds",
+                        offset
+                    )))
+                }
+            }
+        }
+        let mut rc = (self.rc_min..=self.rc_max)
+            .map(Felt::from)
+            .collect::<Vec<_>>();
+        let mut rc_m = counts.into_iter().map(Felt::from).collect::<Vec<_>>();
+
+        let mut rng = rand::thread_rng();
+        let mut tables = vec![];
+        for (column, multiplicities) in [(&mut mem_a, &mut mem_m), (&mut rc, &mut rc_m)] {
+            if column.len() >= n {
+                return Err(ExecutionError::InvalidTables(
+                    "the tables don't fit in the trace".to_string(),
+                ));
+            }
+            let last_value = column.last().copied().unwrap();
+            column.resize(n, last_value);
+            multiplicities.resize(n, Felt::ZERO);
+        }
+        let last_value = mem_v.last().copied().unwrap();
+        mem_v.resize(n, last_value);
+        for mut column in [mem_a, mem_v, mem_m, rc, rc_m] {
+            column.resize_with(self.length(), || random_element(&mut rng));
+            tables.push(column);
+        }
+        Ok(tables)
+    }
+
+    /// Writes the number of random rows and the argument to the trace metadata
+    fn write_meta(&mut self) {
+        self.meta = air::trace_meta(self.num_blinding_rows, self.argument);
+    }
+
+    /// Returns the argument proving memory consistency and range checks
+    pub fn argument(&self) -> AuxArgument {
+        self.argument
+    }
+
+    /// Returns the number of random rows ending the trace
+    pub fn num_blinding_rows(&self) -> usize {
+        self.num_blinding_rows
+    }
+
+    /// Returns the last step of the trace, which holds the final registers. Its own
+    /// transitions are not enforced, and it is followed by the random rows of a
+    /// blinded trace.
+    pub fn last_step(&self) -> usize {
+        (self.length() - self.num_blinding_rows) / STEP_HEIGHT - 1
+    }
+
+    /// Returns the registers at the given step
+    pub fn register_state(&self, step: usize) -> RegisterState {
+        let row = step * STEP_HEIGHT;
+        let main = self.main_segment();
+        RegisterState::new(
+            main.get(PC.0, row + PC.1),
+            main.get(AP.0, row + AP.1),
+            main.get(FP.0, row + FP.1),
+        )
     }
 
     /// Reconstructs the execution trace from file
@@ -219,17 +512,98 @@ impl ExecutionTrace {
         trace_path: PathBuf,
         memory_path: PathBuf,
         output_len: Option<u64>,
-    ) -> ExecutionTrace {
-        let mem = read_memory_bin(&memory_path, &program_path);
-        let registers = read_trace_bin(&trace_path);
-        let builtins = read_builtins(&program_path, output_len);
+    ) -> Result<ExecutionTrace, ReadError> {
+        let mem = read_memory_bin(&memory_path, &program_path)?;
+        let registers = read_trace_bin(&trace_path)?;
+        let segments = read_segments(&program_path, &memory_path, &mem, &registers)?;
+        let output_len = output_len.or_else(|| segments.builtin("output").map(|s| s.size()));
+        let builtins = read_builtins(&program_path, output_len)?;
+        Self::from_registers(registers, &mem, builtins, None, Some(segments))
+            .map_err(missing_end_loop(&trace_path))
+    }
+
+    /// Reconstructs the execution trace from the AIR public and private inputs outputted
+    /// by the cairo-runner. Builtins and public memory are taken from the public input
+    /// rather than derived from the program.
+    pub fn from_air_inputs(
+        public_input_path: PathBuf,
+        private_input_path: PathBuf,
+    ) -> Result<ExecutionTrace, ReadError> {
+        let public_input = read_air_public_input(&public_input_path)?;
+        let private_input = read_air_private_input(&private_input_path)?;
+        let mut mem = read_memory_dump(&private_input.memory_path)?;
+        if let Some(program) = public_input.memory_segments.get("program") {
+            mem.set_codelen((program.stop_ptr - program.begin_addr) as usize);
+        }
+        let segments = public_input.segments();
+        if let Some(segments) = &segments {
+            validate_segments(segments, &mem).map_err(|message| ReadError::InvalidSegments {
+                path: private_input.memory_path.clone(),
+                message,
+            })?;
+        }
+        let registers = read_trace_bin(&private_input.trace_path)?;
+        Self::from_registers(
+            registers,
+            &mem,
+            public_input.builtins(),
+            Some(public_input.public_mem()),
+            segments,
+        )
+        .map_err(missing_end_loop(&private_input.trace_path))
+    }
+
+    /// Reconstructs the execution trace of a Cairo PIE, by re-executing its program on the
+    /// relocated memory of the PIE
+    pub fn from_pie(pie_path: PathBuf) -> Result<ExecutionTrace, ReadError> {
+        let invalid = |message| ReadError::InvalidPie {
+            path: pie_path.clone(),
+            message,
+        };
+        let pie = CairoPie::read_file(&pie_path)?
+            .relocate()
+            .map_err(invalid)?;
+
+        let mut registers = Vec::with_capacity(pie.n_steps);
+        let mut curr = pie.init;
+        for _ in 0..pie.n_steps {
+            registers.push(curr);
+            let mut step = Step::new(&pie.memory, curr);
+            step.execute(false);
+            curr = step.next.expect("Empty next pointers");
+        }
+        if curr.pc != pie.end_pc {
+            return Err(invalid(format!(
+                "main does not return after {} steps",
+                pie.n_steps
+            )));
+        }
+
+        Self::from_registers(
+            registers,
+            &pie.memory,
+            pie.builtins,
+            Some(pie.public_mem),
+            Some(pie.segments),
+        )
+        .map_err(missing_end_loop(&pie_path))
+    }
+
+    /// Builds the execution trace by re-executing each step of a register trace
+    pub(crate) fn from_registers(
+        registers: Vec<RegisterState>,
+        mem: &Memory,
+        builtins: Vec<Builtin>,
+        public_mem: Option<(Vec<u64>, Vec<Option<Word>>)>,
+        segments: Option<SegmentTable>,
+    ) -> Result<ExecutionTrace, ExecutionError> {
         let num_steps = registers.len();
 
         let inst_states = registers
             .par_iter()
             .progress()
             .map(|ptrs| {
-                let mut step = Step::new(&mem, *ptrs);
+                let mut step = Step::new(mem, *ptrs);
                 step.execute(false)
             })
             .collect::<Vec<_>>();
@@ -240,43 +614,107 @@ impl ExecutionTrace {
             state.set_instruction_state(n, inst_state);
         }
 
-        Self::new(num_steps, &mut state, &mem, builtins)
+        Self::new(num_steps, &mut state, mem, builtins, public_mem, segments)
     }
 
     /// Return the program public memory
     pub fn get_program_mem(&self) -> (Vec<u64>, Vec<Option<Word>>) {
-        let addrs = (0..self.memory.get_codelen() as u64).collect::<Vec<_>>();
-        let vals = self.memory.data[..self.memory.get_codelen()].to_vec();
-        (addrs, vals)
+        program_mem(&self.memory)
+    }
+
+    /// Return the memory segments of the run, if they are known
+    pub fn segments(&self) -> Option<&SegmentTable> {
+        self.segments.as_ref()
     }
 
-    /// Return the output public memory
+    /// Return the output public memory. Without a segment table, the output segment is
+    /// assumed to follow the execution segment.
     pub fn get_output_mem(&self) -> (Vec<u64>, Vec<Option<Word>>) {
-        for builtin in self.builtins.iter() {
-            if let Builtin::Output(len) = builtin {
-                let ptr_start: u64 = self.main_segment().get_column(AP)[self.num_steps - 1]
-                    .as_int()
-                    .try_into()
-                    .unwrap();
-                let ptr_end = ptr_start + len;
-                let addrs = (ptr_start..ptr_end).collect::<Vec<_>>();
-                let vals = addrs
-                    .iter()
-                    .map(|i| self.memory.data[*i as usize])
-                    .collect::<Vec<_>>();
-                return (addrs, vals);
-            }
-        }
-        return (vec![], vec![]);
+        let final_ap = self.register_state(self.num_steps - 1).ap;
+        output_mem(
+            &self.memory,
+            &self.builtins,
+            self.segments.as_ref(),
+            final_ap,
+        )
     }
 
-    /// Return the combined public memory
+    /// Return the combined public memory. If the trace was built from AIR inputs or from a
+    /// Cairo PIE, this is the public memory reported by the runner.
     pub fn get_public_mem(&self) -> (Vec<u64>, Vec<Option<Word>>) {
-        let (mut a, mut v) = self.get_program_mem();
-        let (out_a, out_v) = self.get_output_mem();
-        a.extend(out_a);
-        v.extend(out_v);
-        (a, v)
+        self.public_mem.clone()
+    }
+}
+
+fn program_mem(memory: &Memory) -> (Vec<u64>, Vec<Option<Word>>) {
+    let addrs = (0..memory.get_codelen() as u64).collect::<Vec<_>>();
+    let vals = memory.data[..memory.get_codelen()].to_vec();
+    (addrs, vals)
+}
+
+fn output_mem(
+    memory: &Memory,
+    builtins: &[Builtin],
+    segments: Option<&SegmentTable>,
+    final_ap: Felt,
+) -> (Vec<u64>, Vec<Option<Word>>) {
+    for builtin in builtins.iter() {
+        if let Builtin::Output(len) = builtin {
+            let output = segments.and_then(|s| s.builtin("output"));
+            let (ptr_start, ptr_end) = match output {
+                Some(output) => (output.begin_addr, output.stop_ptr),
+                None => {
+                    let ptr_start = final_ap.to_u64();
+                    (ptr_start, ptr_start + len)
+                }
+            };
+            let addrs = (ptr_start..ptr_end).collect::<Vec<_>>();
+            let vals = addrs
+                .iter()
+                .map(|i| memory.data[*i as usize])
+                .collect::<Vec<_>>();
+            return (addrs, vals);
+        }
+    }
+    (vec![], vec![])
+}
+
+/// Distributes values over subcolumns, filling the subcolumns of a step before moving on
+/// to the next step
+fn fill_subcolumns(subcols: &mut [Vec<Felt>], values: &[Felt]) {
+    let width = subcols.len();
+    for (step, chunk) in values.chunks(width).enumerate() {
+        for (subcol, value) in subcols.iter_mut().zip(chunk) {
+            subcol[step] = *value;
+        }
+    }
+}
+
+/// Returns the row of the i-th filler memory access of the trace
+fn filler_access_row(i: usize) -> usize {
+    let num_filler_accesses = STEP_HEIGHT - STEP_MEM_WIDTH;
+    i / num_filler_accesses * STEP_HEIGHT + STEP_MEM_WIDTH + i % num_filler_accesses
+}
+
+/// Draws a uniformly random field element
+fn random_element<E: FieldElement>(rng: &mut impl RngCore) -> E {
+    let mut bytes = vec![0u8; E::VALUE_SIZE];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        if let Some(value) = E::from_random_bytes(&bytes) {
+            return value;
+        }
+    }
+}
+
+/// Maps the error of a trace that can't be padded to a read error of the given trace
+fn missing_end_loop(path: &Path) -> impl FnOnce(ExecutionError) -> ReadError + '_ {
+    move |err| match err {
+        ExecutionError::MissingEndLoop(pc) => ReadError::MissingEndLoop {
+            path: path.to_path_buf(),
+            pc,
+        },
+        err => panic!("Unexpected error while building the trace: {:?}", err),
     }
 }
 
@@ -307,9 +745,11 @@ impl Trace for ExecutionTrace {
     where
         E: FieldElement<BaseField = Self::BaseField>,
     {
-        match aux_segments.len() {
-            0 => build_aux_segment_mem(self, rand_elements),
-            1 => build_aux_segment_rc(self, rand_elements),
+        match (self.argument, aux_segments.len()) {
+            (AuxArgument::Permutation, 0) => build_aux_segment_mem(self, rand_elements),
+            (AuxArgument::Permutation, 1) => build_aux_segment_rc(self, rand_elements),
+            (AuxArgument::LogUp, 0) => build_aux_segment_logup_mem(self, rand_elements),
+            (AuxArgument::LogUp, 1) => build_aux_segment_logup_rc(self, rand_elements),
             _ => None,
         }
     }
@@ -323,62 +763,54 @@ where
     let z = rand_elements[0];
     let alpha = rand_elements[1];
 
-    // Pack main memory access trace columns into two virtual columns
+    // The memory access columns are read as virtual columns, row by row
     let main = trace.main_segment();
-    let (a, v) = [MEM_A_TRACE_RANGE, MEM_V_TRACE_RANGE]
-        .iter()
-        .map(|range| {
-            VirtualColumn::new(
-                &range
-                    .clone()
-                    .map(|i| main.get_column(i).to_vec())
-                    .collect::<Vec<_>>()[..],
-            )
-            .to_column()
-        })
-        .collect_tuple()
-        .unwrap();
+    let a = main.get_column(MEM_A_TRACE_OFFSET);
+    let v = main.get_column(MEM_V_TRACE_OFFSET);
 
-    // Construct duplicate virtual columns sorted by memory access, with dummy public
-    // memory addresses/values replaced by their true values
+    // Construct duplicate columns sorted by memory access, with dummy public memory
+    // addresses/values (held by the first filler accesses) replaced by their true values
     let mut a_prime = vec![E::ZERO; a.len()];
     let mut v_prime = vec![E::ZERO; a.len()];
-    let mut a_replaced = a.clone();
-    let mut v_replaced = v.clone();
+    let mut a_replaced = a.to_vec();
+    let mut v_replaced = v.to_vec();
     let (pub_a, pub_v) = trace.get_public_mem();
-    let l = a.len() - pub_a.len() - 1;
     for (i, (n, x)) in pub_a.iter().copied().zip(pub_v).enumerate() {
-        a_replaced[l + i] = Felt::from(n);
-        v_replaced[l + i] = x.unwrap().word().into();
+        let j = filler_access_row(i);
+        debug_assert!(a[j] == Felt::ZERO && v[j] == Felt::ZERO);
+        a_replaced[j] = Felt::from(n);
+        v_replaced[j] = x.unwrap().word().into();
     }
-    let mut indices = (0..a.len()).collect::<Vec<_>>();
+    // Only accesses up to the last step are permuted
+    let n = (trace.last_step() + 1) * STEP_HEIGHT;
+    let mut indices = (0..n).collect::<Vec<_>>();
     indices.sort_by_key(|&i| a_replaced[i].as_int());
     for (i, j) in indices.iter().copied().enumerate() {
         a_prime[i] = a_replaced[j].into();
         v_prime[i] = v_replaced[j].into();
     }
 
-    // Construct virtual column of computed permutation products
-    let mut p = vec![E::ZERO; trace.length() * MEM_A_TRACE_WIDTH];
+    // Construct column of computed permutation products
+    let mut p = vec![E::ZERO; trace.length()];
     let a_0: E = a[0].into();
     let v_0: E = v[0].into();
     p[0] = (z - (a_0 + alpha * v_0).into()) / (z - (a_prime[0] + alpha * v_prime[0]).into());
-    for i in (1..p.len()).progress() {
+    for i in (1..n).progress() {
         let a_i: E = a[i].into();
         let v_i: E = v[i].into();
         p[i] = (z - (a_i + alpha * v_i).into()) * p[i - 1]
             / (z - (a_prime[i] + alpha * v_prime[i]).into());
     }
 
-    // Split virtual columns into separate auxiliary columns
-    let mut aux_columns = VirtualColumn::new(&[a_prime, v_prime, p]).to_columns(&[
-        A_M_PRIME_WIDTH,
-        V_M_PRIME_WIDTH,
-        P_M_WIDTH,
-    ]);
-    resize_to_pow2(&mut aux_columns);
+    // The rows following the last step of a blinded trace are random
+    if n < p.len() {
+        let mut rng = rand::thread_rng();
+        for column in [&mut a_prime, &mut v_prime, &mut p] {
+            column[n..].fill_with(|| random_element(&mut rng));
+        }
+    }
 
-    Some(Matrix::new(aux_columns))
+    Some(Matrix::new(vec![a_prime, v_prime, p]))
 }
 
 /// Write documentation
@@ -388,35 +820,107 @@ where
 {
     let z = rand_elements[0];
 
-    // Pack main offset trace columns into a single virtual column
-    let main = trace.main_segment();
-    let a = VirtualColumn::new(
-        &OFF_X_TRACE_RANGE
-            .map(|i| main.get_column(i).to_vec())
-            .collect::<Vec<_>>()[..],
-    )
-    .to_column();
-
-    // Construct duplicate virtual column sorted by offset value
-    let mut indices = (0..a.len()).collect::<Vec<_>>();
+    // The offset column is read as a virtual column, row by row
+    let a = trace.main_segment().get_column(OFF_X_TRACE_OFFSET);
+
+    // Construct duplicate column sorted by offset value. Only offsets up to the last step
+    // are permuted.
+    let n = (trace.last_step() + 1) * STEP_HEIGHT;
+    let mut indices = (0..n).collect::<Vec<_>>();
     indices.sort_by_key(|&i| a[i].as_int());
-    let a_prime = indices.iter().map(|x| a[*x].into()).collect::<Vec<E>>();
+    let mut a_prime = indices.iter().map(|x| a[*x].into()).collect::<Vec<E>>();
 
-    // Construct virtual column of computed permutation products
-    let mut p = vec![E::ZERO; trace.length() * OFF_X_TRACE_WIDTH];
+    // Construct column of computed permutation products
+    let mut p = vec![E::ZERO; trace.length()];
     let a_0: E = a[0].into();
     p[0] = (z - a_0) / (z - a_prime[0]);
-    for i in (1..p.len()).progress() {
+    for i in (1..n).progress() {
         let a_i: E = a[i].into();
         p[i] = (z - a_i) * p[i - 1] / (z - a_prime[i]);
     }
 
-    // Split virtual columns into separate auxiliary columns
-    let mut aux_columns =
-        VirtualColumn::new(&[a_prime, p]).to_columns(&[A_RC_PRIME_WIDTH, P_RC_WIDTH]);
-    resize_to_pow2(&mut aux_columns);
+    // The rows following the last step of a blinded trace are random
+    if n < p.len() {
+        let mut rng = rand::thread_rng();
+        a_prime.resize_with(p.len(), || random_element(&mut rng));
+        p[n..].fill_with(|| random_element(&mut rng));
+    }
+
+    Some(Matrix::new(vec![a_prime, p]))
+}
+
+/// Builds the LogUp memory segment: the running sum of the inverses of the memory access
+/// terms, minus those of the memory table terms weighted by their multiplicities
+fn build_aux_segment_logup_mem<E>(trace: &ExecutionTrace, rand_elements: &[E]) -> Option<Matrix<E>>
+where
+    E: FieldElement + From<Felt>,
+{
+    let z = rand_elements[0];
+    let alpha = rand_elements[1];
+
+    let main = trace.main_segment();
+    let a = main.get_column(MEM_A_TRACE_OFFSET);
+    let v = main.get_column(MEM_V_TRACE_OFFSET);
+    let table_a = main.get_column(MEM_TABLE_A_OFFSET);
+    let table_v = main.get_column(MEM_TABLE_V_OFFSET);
+    let table_m = main.get_column(MEM_TABLE_M_OFFSET);
+
+    let n = (trace.last_step() + 1) * STEP_HEIGHT;
+    let mut s = vec![E::ZERO; trace.length()];
+    let mut w = vec![E::ZERO; trace.length()];
+    for i in (0..n).progress() {
+        let a_i: E = a[i].into();
+        let v_i: E = v[i].into();
+        w[i] = (z - (a_i + alpha * v_i)).inv();
+        if i + 1 < n {
+            let entry = z - (E::from(table_a[i]) + alpha * E::from(table_v[i]));
+            s[i + 1] = s[i] + w[i] - E::from(table_m[i]) / entry;
+        }
+    }
+
+    // The rows following the last step of a blinded trace are random
+    if n < s.len() {
+        let mut rng = rand::thread_rng();
+        for column in [&mut s, &mut w] {
+            column[n..].fill_with(|| random_element(&mut rng));
+        }
+    }
 
-    Some(Matrix::new(aux_columns))
+    Some(Matrix::new(vec![s, w]))
+}
+
+/// Builds the LogUp range check segment: the running sum of the inverses of the offset
+/// terms, minus those of the range check table terms weighted by their multiplicities
+fn build_aux_segment_logup_rc<E>(trace: &ExecutionTrace, rand_elements: &[E]) -> Option<Matrix<E>>
+where
+    E: FieldElement + From<Felt>,
+{
+    let z = rand_elements[0];
+
+    let main = trace.main_segment();
+    let a = main.get_column(OFF_X_TRACE_OFFSET);
+    let table = main.get_column(RC_TABLE_OFFSET);
+    let table_m = main.get_column(RC_TABLE_M_OFFSET);
+
+    let n = (trace.last_step() + 1) * STEP_HEIGHT;
+    let mut s = vec![E::ZERO; trace.length()];
+    let mut w = vec![E::ZERO; trace.length()];
+    for i in (0..n).progress() {
+        w[i] = (z - E::from(a[i])).inv();
+        if i + 1 < n {
+            s[i + 1] = s[i] + w[i] - E::from(table_m[i]) / (z - E::from(table[i]));
+        }
+    }
+
+    // The rows following the last step of a blinded trace are random
+    if n < s.len() {
+        let mut rng = rand::thread_rng();
+        for column in [&mut s, &mut w] {
+            column[n..].fill_with(|| random_element(&mut rng));
+        }
+    }
+
+    Some(Matrix::new(vec![s, w]))
 }
 
 /// Resize columns to next power of two
@@ -431,3 +935,371 @@ fn resize_to_pow2<E: FieldElement>(columns: &mut [Vec<E>]) {
         column.resize(trace_len_pow2, last_value);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use air::{ConstraintFailure, ProcessorAir, ProofOptions, PublicInputs};
+    use giza_core::{
+        MemorySegment, A_RC_PRIME_LAST, LOGUP_TRACE_WIDTH, P_M_LAST, S_M_OFFSET, S_RC_OFFSET,
+    };
+    use winterfell::math::fields::QuadExtension;
+    use winterfell::Air;
+
+    const Z: u64 = 7919;
+    const ALPHA: u64 = 104729;
+
+    /// Builds the trace of a short execution with an output much longer than the execution
+    fn output_trace() -> ExecutionTrace {
+        // [ap] = 5, ap++; [ap] = 6, ap++; jmp rel 0
+        let mut mem = Memory::new(
+            [
+                0x480680017fff8000u64,
+                5,
+                0x480680017fff8000,
+                6,
+                0x10780017fff7fff,
+                0,
+            ]
+            .iter()
+            .map(|x| Felt::from(*x))
+            .collect(),
+        );
+        mem.write(Felt::from(8u64), Felt::from(5u64));
+        mem.write(Felt::from(9u64), Felt::from(6u64));
+        let output_len = 40u64;
+        for addr in 10..10 + output_len {
+            mem.write(Felt::from(addr), Felt::from(addr * addr));
+        }
+        let segments = SegmentTable {
+            program: MemorySegment::new(1, 7),
+            execution: MemorySegment::new(8, 10),
+            builtins: vec![(
+                "output".to_string(),
+                MemorySegment::new(10, 10 + output_len),
+            )],
+        };
+        let registers = vec![
+            RegisterState::new(1u64, 8u64, 8u64),
+            RegisterState::new(3u64, 9u64, 8u64),
+            RegisterState::new(5u64, 10u64, 8u64),
+        ];
+        ExecutionTrace::from_registers(
+            registers,
+            &mem,
+            vec![Builtin::Output(output_len)],
+            None,
+            Some(segments),
+        )
+        .unwrap()
+    }
+
+    /// Builds the trace of `[ap + 2] = 5, ap++; jmp rel 0`, run with ap = fp = 6, which
+    /// leaves a memory hole at address 7
+    fn hole_trace() -> ExecutionTrace {
+        let mut mem = Memory::new(
+            [0x480680017fff8002u64, 5, 0x10780017fff7fff, 0]
+                .iter()
+                .map(|x| Felt::from(*x))
+                .collect(),
+        );
+        mem.write(Felt::from(8u64), Felt::from(5u64));
+        let registers = vec![
+            RegisterState::new(1u64, 6u64, 6u64),
+            RegisterState::new(3u64, 7u64, 6u64),
+        ];
+        ExecutionTrace::from_registers(registers, &mem, vec![], None, None).unwrap()
+    }
+
+    /// Evaluates the AIR over a trace, with the aux segments built from fixed random
+    /// elements. Returns the transition constraints that don't hold, and the number of
+    /// cells that don't match the assertions.
+    fn check_air(trace: &mut ExecutionTrace) -> (Vec<ConstraintFailure>, usize) {
+        let pub_inputs = trace_pub_inputs(trace);
+        let (failures, assertion_failures) = check_air_with(trace, pub_inputs);
+        (failures, assertion_failures.len())
+    }
+
+    /// Returns the public inputs of a trace, as the prover computes them
+    fn trace_pub_inputs(trace: &ExecutionTrace) -> PublicInputs {
+        PublicInputs::new(
+            trace.register_state(0),
+            trace.register_state(trace.last_step()),
+            trace.rc_min,
+            trace.rc_max,
+            trace.get_public_mem(),
+            trace.num_steps,
+            trace.builtins.clone(),
+            trace.segments().cloned(),
+        )
+        .with_trace_layout(trace.num_blinding_rows, trace.argument)
+    }
+
+    /// Evaluates the AIR over a trace against the given public inputs. Returns the
+    /// transition constraints and the assertions that don't hold.
+    fn check_air_with(
+        trace: &ExecutionTrace,
+        pub_inputs: PublicInputs,
+    ) -> (Vec<ConstraintFailure>, Vec<ConstraintFailure>) {
+        let options = ProofOptions::default().into_inner();
+        let air = ProcessorAir::new(trace.get_info(), pub_inputs, options);
+
+        let mut aux_segments: Vec<Matrix<Felt>> = vec![];
+        let mut rand_elements = vec![];
+        for i in 0..trace.layout().num_aux_segments() {
+            let num_elements = trace.layout().get_aux_segment_rand_elements(i);
+            let elements = [Felt::from(Z), Felt::from(ALPHA)][..num_elements].to_vec();
+            aux_segments.push(trace.build_aux_segment(&aux_segments, &elements).unwrap());
+            rand_elements.push(elements);
+        }
+        let main = (0..trace.main_segment().num_cols())
+            .map(|i| trace.main_segment().get_column(i))
+            .collect::<Vec<_>>();
+        let aux = aux_segments
+            .iter()
+            .flat_map(|segment| (0..segment.num_cols()).map(|i| segment.get_column(i)))
+            .collect::<Vec<_>>();
+        (
+            air.check_transitions(&main, &aux, rand_elements.clone()),
+            air.check_assertions(&main, &aux, rand_elements),
+        )
+    }
+
+    /// Replaces a cell of the main trace
+    fn forge(trace: &mut ExecutionTrace, column: usize, row: usize, value: Felt) {
+        let mut columns = (0..trace.main_segment().num_cols())
+            .map(|i| trace.main_segment().get_column(i).to_vec())
+            .collect::<Vec<_>>();
+        columns[column][row] = value;
+        trace.trace = Matrix::new(columns);
+    }
+
+    /// Returns the expected value of the memory permutation product at the last step
+    fn public_memory_product<E: FieldElement + From<Felt>>(
+        trace: &ExecutionTrace,
+        z: E,
+        alpha: E,
+    ) -> E {
+        let (pub_a, pub_v) = trace.get_public_mem();
+        let num = z.exp((pub_a.len() as u64).into());
+        let den = pub_a
+            .iter()
+            .zip(&pub_v)
+            .map(|(a, v)| z - (E::from(Felt::from(*a)) + alpha * E::from(v.unwrap().word())))
+            .reduce(|a, b| a * b)
+            .unwrap();
+        num / den
+    }
+
+    #[test]
+    fn test_public_memory_rows() {
+        let mut trace = output_trace();
+        assert_eq!(trace.get_public_mem().0.len(), 47);
+
+        // The trace is padded with the end loop, up to room for the public memory
+        assert_eq!(trace.length(), 4 * STEP_HEIGHT);
+        assert_eq!(trace.last_step(), 3);
+        assert!((2..=3).all(|step| trace.register_state(step).pc == Felt::from(5u64)));
+
+        // The permutation product only matches the public memory if no real access was
+        // replaced by a public memory cell
+        let aux = trace
+            .build_aux_segment(&[], &[Felt::from(Z), Felt::from(ALPHA)])
+            .unwrap();
+        let p_m_last = aux.get(P_M_LAST, trace.length() - 1);
+        assert_eq!(
+            p_m_last,
+            public_memory_product(&trace, Felt::from(Z), Felt::from(ALPHA))
+        );
+    }
+
+    #[test]
+    fn test_aux_segments_extension() {
+        let mut trace = output_trace();
+        let z = QuadExtension::new(Felt::from(Z), Felt::ONE);
+        let alpha = QuadExtension::new(Felt::from(ALPHA), Felt::from(2u64));
+        let mem = trace.build_aux_segment(&[], &[z, alpha]).unwrap();
+        let last_row = trace.length() - 1;
+        assert_eq!(
+            mem.get(P_M_LAST, last_row),
+            public_memory_product(&trace, z, alpha)
+        );
+        let rc = trace.build_aux_segment(&[mem], &[z]).unwrap();
+        let a_rc_last = A_RC_PRIME_LAST - AUX_MEM_TRACE_WIDTH;
+        assert_eq!(
+            rc.get(a_rc_last, last_row),
+            QuadExtension::from(Felt::from(trace.rc_max))
+        );
+    }
+
+    #[test]
+    fn test_blinding() {
+        let mut trace = output_trace();
+        trace.blind(20);
+
+        // The trace is extended to fit the random rows, rounded up to two steps, after the
+        // public memory
+        assert_eq!(trace.length(), 8 * STEP_HEIGHT);
+        let last_step = trace.last_step();
+        assert_eq!(last_step, 5);
+        assert!((2..=last_step).all(|step| trace.register_state(step).pc == Felt::from(5u64)));
+
+        // Permutation products are complete at the last row of the last step
+        let last_row = (last_step + 1) * STEP_HEIGHT - 1;
+        let mem = trace
+            .build_aux_segment(&[], &[Felt::from(Z), Felt::from(ALPHA)])
+            .unwrap();
+        assert_eq!(
+            mem.get(P_M_LAST, last_row),
+            public_memory_product(&trace, Felt::from(Z), Felt::from(ALPHA))
+        );
+        let rc = trace.build_aux_segment(&[mem], &[Felt::from(Z)]).unwrap();
+        let a_rc_last = A_RC_PRIME_LAST - AUX_MEM_TRACE_WIDTH;
+        assert_eq!(rc.get(a_rc_last, last_row), Felt::from(trace.rc_max));
+    }
+
+    #[test]
+    fn test_logup() {
+        let mut trace = output_trace();
+        trace.blind(20);
+        trace.set_argument(AuxArgument::LogUp).unwrap();
+        assert_eq!(trace.main_segment().num_cols(), LOGUP_TRACE_WIDTH);
+
+        // Running sums start from zero, and end up missing the terms of the public memory
+        // and of the last row
+        let z = Felt::from(Z);
+        let alpha = Felt::from(ALPHA);
+        let last_row = (trace.last_step() + 1) * STEP_HEIGHT - 1;
+        let mem = trace.build_aux_segment(&[], &[z, alpha]).unwrap();
+        let (pub_a, pub_v) = trace.get_public_mem();
+        let public = pub_a.iter().zip(&pub_v).fold(Felt::ZERO, |acc, (a, v)| {
+            acc + (z - (Felt::from(*a) + alpha * v.unwrap().word())).inv()
+        });
+        assert_eq!(mem.get(S_M_OFFSET, 0), Felt::ZERO);
+        assert_eq!(mem.get(S_M_OFFSET, last_row), -public - z.inv());
+        let rc = trace.build_aux_segment(&[mem], &[z]).unwrap();
+        let s_rc = S_RC_OFFSET - AUX_LOGUP_MEM_TRACE_WIDTH;
+        assert_eq!(rc.get(s_rc, 0), Felt::ZERO);
+        assert_eq!(
+            rc.get(s_rc, last_row),
+            -(z - Felt::from(trace.rc_min)).inv()
+        );
+    }
+
+    #[test]
+    fn test_logup_air() {
+        let mut blinded = output_trace();
+        blinded.blind(20);
+        for mut trace in [hole_trace(), blinded] {
+            trace.set_argument(AuxArgument::LogUp).unwrap();
+            let (failures, num_failed_assertions) = check_air(&mut trace);
+            assert!(failures.is_empty());
+            assert_eq!(num_failed_assertions, 0);
+
+            // A wrong multiplicity still satisfies the transitions of the running sums, but
+            // not the value asserted at their end
+            for (column, row) in [(MEM_TABLE_M_OFFSET, 1), (RC_TABLE_M_OFFSET, 0)] {
+                let mut forged = trace.clone();
+                let m = forged.main_segment().get(column, row);
+                forge(&mut forged, column, row, m + Felt::ONE);
+                let (failures, num_failed_assertions) = check_air(&mut forged);
+                assert!(failures.is_empty());
+                assert_eq!(num_failed_assertions, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_logup_tables_growth() {
+        // Every memory cell gets an entry in the table, even if it isn't accessed
+        let mut trace = hole_trace();
+        trace.memory.write(Felt::from(100u64), Felt::from(1u64));
+        assert!(trace.length() < 101);
+        trace.set_argument(AuxArgument::LogUp).unwrap();
+
+        // The trace is extended with the end loop to fit the table
+        assert_eq!(trace.length(), 8 * STEP_HEIGHT);
+        assert!(
+            (1..=trace.last_step()).all(|step| trace.register_state(step).pc == Felt::from(3u64))
+        );
+        let mem_a = trace.main_segment().get_column(MEM_TABLE_A_OFFSET);
+        assert_eq!(mem_a[100], Felt::from(100u64));
+        let (failures, num_failed_assertions) = check_air(&mut trace);
+        assert!(failures.is_empty());
+        assert_eq!(num_failed_assertions, 0);
+    }
+
+    #[test]
+    fn test_assertion_failures() {
+        for argument in [AuxArgument::Permutation, AuxArgument::LogUp] {
+            let mut trace = output_trace();
+            trace.set_argument(argument).unwrap();
+
+            // A final register that differs from the public inputs fails at the last step
+            let mut tampered = trace_pub_inputs(&trace);
+            tampered.fin.ap += Felt::ONE;
+            let (failures, assertion_failures) = check_air_with(&trace, tampered);
+            assert!(failures.is_empty());
+            assert_eq!(assertion_failures.len(), 1);
+            assert_eq!(assertion_failures[0].constraint, "ASSERT_AP");
+            assert_eq!(assertion_failures[0].step, trace.last_step());
+            assert_eq!(assertion_failures[0].evaluation, -Felt::ONE);
+
+            // So does public memory that differs from the memory of the trace, in the
+            // running product or sum of the memory accesses
+            let mut tampered = trace_pub_inputs(&trace);
+            tampered.mem.1[0] = Some(Word::new(Felt::from(7u64)));
+            let (failures, assertion_failures) = check_air_with(&trace, tampered);
+            assert!(failures.is_empty());
+            let name = match argument {
+                AuxArgument::Permutation => "ASSERT_P_M",
+                AuxArgument::LogUp => "ASSERT_S_M",
+            };
+            assert_eq!(assertion_failures.len(), 1);
+            assert_eq!(assertion_failures[0].constraint, name);
+            assert_eq!(assertion_failures[0].row, trace.length() - 1);
+        }
+    }
+
+    #[test]
+    fn test_logup_invalid_tables() {
+        // Accesses out of memory and offsets out of the range check bounds are errors
+        let trace = hole_trace();
+        let size = trace.memory.size();
+        let rc_max = Felt::from(trace.rc_max);
+        for (column, value) in [
+            (MEM_A_TRACE_OFFSET, Felt::from(size)),
+            (MEM_A_TRACE_OFFSET, -Felt::ONE),
+            (OFF_X_TRACE_OFFSET, rc_max + Felt::ONE),
+        ] {
+            let mut forged = trace.clone();
+            forge(&mut forged, column, 1, value);
+            assert!(matches!(
+                forged.set_argument(AuxArgument::LogUp),
+                Err(ExecutionError::InvalidTables(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_forged_hole() {
+        let mut trace = hole_trace();
+        let (failures, num_failed_assertions) = check_air(&mut trace);
+        assert!(failures.is_empty());
+        assert_eq!(num_failed_assertions, 0);
+
+        // The hole is filled by a filler access, which claims it to be zero
+        let mem_a = trace.main_segment().get_column(MEM_A_TRACE_OFFSET);
+        let row = (0..trace.length())
+            .find(|row| row % STEP_HEIGHT >= STEP_MEM_WIDTH && mem_a[*row] == Felt::from(7u64))
+            .unwrap();
+        forge(&mut trace, MEM_V_TRACE_OFFSET, row, Felt::from(7u64));
+        let (failures, _) = check_air(&mut trace);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].constraint,
+            format!("FILLER_VALUE[{}]", row % STEP_HEIGHT - STEP_MEM_WIDTH)
+        );
+        assert_eq!(failures[0].step, row / STEP_HEIGHT);
+    }
+}
@@ -20,7 +20,11 @@ pub fn verify(buffer: &Uint8Array) {
     let pub_inputs = PublicInputs::read_from(&mut SliceReader::new(&data.input_bytes[..])).unwrap();
     let proof = StarkProof::from_bytes(&data.proof_bytes).unwrap();
 
-    // Verify execution
+    // Verify execution, once the trace is known to have the layout of the public inputs
+    if let Err(err) = pub_inputs.check_trace_info(&proof.get_trace_info()) {
+        log(format!("Failed to verify execution: {}", err).as_str());
+        return;
+    }
     match winterfell::verify::<ProcessorAir>(proof, pub_inputs) {
         Ok(_) => log("Execution verified"),
         Err(err) => log(format!("Failed to verify execution: {}", err).as_str()),